@@ -0,0 +1,1220 @@
+use crate::deserializer::NodeDeserializer;
+use crate::error::Error;
+use crate::id::Id;
+use crate::kind::{AnyKind, Kind};
+use crate::serializer::NodeSerializer;
+use crate::{dedup, intern, Node};
+use foldhash::HashMap;
+use serde::de::value::BorrowedStrDeserializer;
+use serde::de::{
+    Deserialize, DeserializeSeed, Deserializer, EnumAccess, Error as _, IgnoredAny, MapAccess,
+    SeqAccess, Unexpected, VariantAccess, Visitor,
+};
+use serde::forward_to_deserialize_any;
+use serde::ser::{
+    Serialize, SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant, SerializeTuple,
+    SerializeTupleStruct, SerializeTupleVariant, Serializer,
+};
+use std::fmt::Display;
+use std::mem;
+use std::slice;
+use std::str;
+
+// The encoding is:
+//
+//     file   := MAGIC node
+//     node   := id:varint kind:varint fields children:varint node*
+//     fields := (value value)* END
+//
+// where the nodes following a node are its `children` many children in order,
+// and the fields are the entries of the JSON object of the node other than
+// "id", "kind" and "inner". Varints are LEB128.
+//
+// A kind of 0 means the node has no kind. Otherwise it is one more than an
+// index into the table of kinds seen so far, or one more than the size of that
+// table for a new kind, in which case the kind follows as a length and UTF-8.
+//
+// Every value begins with one of the following tags. Strings are likewise
+// either new, or an index into a second table of strings seen so far.
+const MAGIC: &[u8; 8] = b"\0clast\x01\0";
+const NULL: u8 = 0;
+const FALSE: u8 = 1;
+const TRUE: u8 = 2;
+const UNSIGNED: u8 = 3;
+// Followed by the bitwise complement of the value, which is nonnegative.
+const NEGATIVE: u8 = 4;
+const FLOAT: u8 = 5;
+const STRING: u8 = 6;
+const STRING_REF: u8 = 7;
+const BYTES: u8 = 8;
+const SEQ: u8 = 9;
+const MAP: u8 = 10;
+// Closes a SEQ or MAP, or the fields of a node.
+const END: u8 = 11;
+// The value of an "offset" field, as the zigzag-encoded difference from the
+// previous one.
+const OFFSET: u8 = 12;
+
+// Depth of arrays and objects nested within a field of a node, to guard
+// against stack overflow. Nesting of nodes is not limited.
+const RECURSION_LIMIT: u8 = 128;
+
+/// Serializes a syntax tree into a compact binary encoding.
+///
+/// The encoding holds the same data as the JSON which `T`'s `Serialize` impl
+/// produces, and [`from_binary`] reads it back into an identical `Node<T>`
+/// much faster than deserializing JSON. It is also several times smaller:
+///
+/// - Each distinct kind, field name and string value, such as a file path or a
+///   type, is written out once and afterward referred to by index.
+/// - Node ids and other integers are variable-length.
+/// - The offset of each source location is written as the difference from the
+///   previous one.
+///
+/// ```no_run
+/// # use serde_derive::{Deserialize, Serialize};
+/// #
+/// # #[derive(Deserialize, Serialize)]
+/// # enum Clang {
+/// #     Other,
+/// # }
+/// #
+/// fn main() -> Result<(), clang_ast::Error> {
+///     let json = std::fs::read("ast.json").unwrap();
+///     let node = clang_ast::from_slice::<Clang>(&json)?;
+///     std::fs::write("ast.bin", clang_ast::to_binary(&node)?).unwrap();
+///
+///     let binary = std::fs::read("ast.bin").unwrap();
+///     let node = clang_ast::from_binary::<Clang>(&binary)?;
+///     # let _ = node;
+///     Ok(())
+/// }
+/// ```
+pub fn to_binary<T>(node: &Node<T>) -> Result<Vec<u8>, Error>
+where
+    T: Serialize,
+{
+    let _dedup = dedup::activate();
+    let mut encoder = Encoder::default();
+    encoder.out.extend_from_slice(MAGIC);
+    let mut stack = vec![slice::from_ref(node).iter()];
+    while let Some(siblings) = stack.last_mut() {
+        if let Some(node) = siblings.next() {
+            encoder.write_node(node)?;
+            stack.push(node.inner.iter());
+        } else {
+            stack.pop();
+        }
+    }
+    Ok(encoder.out)
+}
+
+/// Deserializes a syntax tree from the output of [`to_binary`].
+///
+/// This uses a constant amount of stack regardless of how deeply nested the
+/// tree is.
+pub fn from_binary<'de, T>(bytes: &'de [u8]) -> Result<Node<T>, Error>
+where
+    T: Deserialize<'de>,
+{
+    let _intern = intern::activate();
+    if !bytes.starts_with(MAGIC) {
+        return Err(Error::custom("not a binary syntax tree"));
+    }
+    let mut reader = Reader::new(bytes);
+    reader.index = MAGIC.len();
+
+    // Each ancestor of the next node, and how many more children it has.
+    let mut stack = Vec::new();
+    loop {
+        let (mut node, children) = reader.read_node()?;
+        if children > 0 {
+            stack.push((node, children));
+            continue;
+        }
+        loop {
+            let Some((mut parent, remaining)) = stack.pop() else {
+                reader.end()?;
+                return Ok(node);
+            };
+            parent.inner.push(node);
+            if remaining > 1 {
+                stack.push((parent, remaining - 1));
+                break;
+            }
+            node = parent;
+        }
+    }
+}
+
+#[allow(clippy::cast_possible_truncation)]
+pub(crate) fn write_varint(out: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        out.push(n as u8 | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+pub(crate) fn write_str(out: &mut Vec<u8>, string: &str) {
+    write_varint(out, string.len() as u64);
+    out.extend_from_slice(string.as_bytes());
+}
+
+#[derive(Default)]
+struct Encoder {
+    out: Vec<u8>,
+    // The fields of the node being written, which are serialized before its
+    // kind is known.
+    fields: Vec<u8>,
+    strings: HashMap<Box<str>, u64>,
+    kinds: HashMap<Box<str>, u64>,
+    last_offset: u64,
+    // What the most recent map key says about the value which follows it.
+    next: Next,
+}
+
+#[derive(Copy, Clone, Default)]
+enum Next {
+    #[default]
+    Value,
+    Offset,
+    Kind,
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum Role {
+    Value,
+    Key,
+    // A key among the fields of a node, where "kind" is special.
+    FieldKey,
+    Offset,
+}
+
+impl Encoder {
+    fn write_node<T>(&mut self, node: &Node<T>) -> Result<(), Error>
+    where
+        T: Serialize,
+    {
+        mem::swap(&mut self.out, &mut self.fields);
+        self.out.clear();
+        let mut kind = None;
+        let result = node.kind.serialize(NodeSerializer::new(&mut FieldMap {
+            encoder: &mut *self,
+            kind: &mut kind,
+        }));
+        self.out.push(END);
+        mem::swap(&mut self.out, &mut self.fields);
+        result?;
+
+        write_varint(&mut self.out, node.id.as_u64());
+        self.write_kind(kind.as_deref());
+        self.out.extend_from_slice(&self.fields);
+        write_varint(&mut self.out, node.inner.len() as u64);
+        Ok(())
+    }
+
+    fn write_kind(&mut self, kind: Option<&str>) {
+        let Some(kind) = kind else {
+            write_varint(&mut self.out, 0);
+            return;
+        };
+        if let Some(&i) = self.kinds.get(kind) {
+            write_varint(&mut self.out, i + 1);
+        } else {
+            let i = self.kinds.len() as u64;
+            self.kinds.insert(Box::from(kind), i);
+            write_varint(&mut self.out, i + 1);
+            write_str(&mut self.out, kind);
+        }
+    }
+
+    fn write_string(&mut self, string: &str) {
+        if let Some(&i) = self.strings.get(string) {
+            self.out.push(STRING_REF);
+            write_varint(&mut self.out, i);
+        } else {
+            let i = self.strings.len() as u64;
+            self.strings.insert(Box::from(string), i);
+            self.out.push(STRING);
+            write_str(&mut self.out, string);
+        }
+    }
+
+    fn value_role(&mut self) -> Role {
+        match mem::take(&mut self.next) {
+            Next::Offset => Role::Offset,
+            Next::Value | Next::Kind => Role::Value,
+        }
+    }
+}
+
+// The kind of a node as serialized by T, which must be a string, or unit if the
+// node has no kind. Read back through this same encoding so as not to need yet
+// another Serializer accepting only those two.
+fn kind_of<V>(value: &V) -> Result<Option<String>, Error>
+where
+    V: ?Sized + Serialize,
+{
+    let mut encoder = Encoder::default();
+    value.serialize(BinarySerializer {
+        encoder: &mut encoder,
+        role: Role::Value,
+    })?;
+    let mut reader = Reader::new(&encoder.out);
+    Option::<String>::deserialize(&mut reader).map_err(|_| Error::custom("unsupported \"kind\""))
+}
+
+struct FieldMap<'a> {
+    encoder: &'a mut Encoder,
+    kind: &'a mut Option<String>,
+}
+
+impl<'a> SerializeMap for FieldMap<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        key.serialize(BinarySerializer {
+            encoder: &mut *self.encoder,
+            role: Role::FieldKey,
+        })
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        if let Next::Kind = self.encoder.next {
+            self.encoder.next = Next::Value;
+            *self.kind = kind_of(value)?;
+            return Ok(());
+        }
+        let role = self.encoder.value_role();
+        value.serialize(BinarySerializer {
+            encoder: &mut *self.encoder,
+            role,
+        })
+    }
+
+    fn end(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+struct BinarySerializer<'a> {
+    encoder: &'a mut Encoder,
+    role: Role,
+}
+
+impl<'a> BinarySerializer<'a> {
+    fn begin_variant(self, variant: &str) -> &'a mut Encoder {
+        self.encoder.out.push(MAP);
+        self.encoder.write_string(variant);
+        self.encoder
+    }
+}
+
+impl<'a> Serializer for BinarySerializer<'a> {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = Compound<'a>;
+    type SerializeTuple = Compound<'a>;
+    type SerializeTupleStruct = Compound<'a>;
+    type SerializeTupleVariant = Compound<'a>;
+    type SerializeMap = Compound<'a>;
+    type SerializeStruct = Compound<'a>;
+    type SerializeStructVariant = Compound<'a>;
+
+    fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
+        self.encoder.out.push(if v { TRUE } else { FALSE });
+        Ok(())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Self::Ok, Self::Error> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Self::Ok, Self::Error> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
+        if let Ok(v) = u64::try_from(v) {
+            self.serialize_u64(v)
+        } else {
+            self.encoder.out.push(NEGATIVE);
+            write_varint(&mut self.encoder.out, (!v).unsigned_abs());
+            Ok(())
+        }
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
+        self.serialize_u64(u64::from(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Self::Ok, Self::Error> {
+        self.serialize_u64(u64::from(v))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Self::Ok, Self::Error> {
+        self.serialize_u64(u64::from(v))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Self::Ok, Self::Error> {
+        let encoder = self.encoder;
+        if self.role == Role::Offset {
+            let delta = v.wrapping_sub(encoder.last_offset);
+            encoder.last_offset = v;
+            encoder.out.push(OFFSET);
+            write_varint(&mut encoder.out, delta << 1 ^ (delta >> 63).wrapping_neg());
+        } else {
+            encoder.out.push(UNSIGNED);
+            write_varint(&mut encoder.out, v);
+        }
+        Ok(())
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
+        self.serialize_f64(f64::from(v))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Self::Ok, Self::Error> {
+        self.encoder.out.push(FLOAT);
+        self.encoder.out.extend_from_slice(&v.to_le_bytes());
+        Ok(())
+    }
+
+    fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
+        self.serialize_str(v.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
+        match self.role {
+            Role::FieldKey if v == "kind" => {
+                self.encoder.next = Next::Kind;
+                return Ok(());
+            }
+            Role::Key | Role::FieldKey if v == "offset" => self.encoder.next = Next::Offset,
+            _ => {}
+        }
+        self.encoder.write_string(v);
+        Ok(())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
+        self.encoder.out.push(BYTES);
+        write_varint(&mut self.encoder.out, v.len() as u64);
+        self.encoder.out.extend_from_slice(v);
+        Ok(())
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+        self.serialize_unit()
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+        self.encoder.out.push(NULL);
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<Self::Ok, Self::Error> {
+        let _ = name;
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok, Self::Error> {
+        let _ = name;
+        let _ = variant_index;
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let _ = name;
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let _ = name;
+        let _ = variant_index;
+        let encoder = self.begin_variant(variant);
+        value.serialize(BinarySerializer {
+            encoder: &mut *encoder,
+            role: Role::Value,
+        })?;
+        encoder.out.push(END);
+        Ok(())
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        let _ = len;
+        self.encoder.out.push(SEQ);
+        Ok(Compound {
+            encoder: self.encoder,
+            variant: false,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        let _ = name;
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        let _ = name;
+        let _ = variant_index;
+        let _ = len;
+        let encoder = self.begin_variant(variant);
+        encoder.out.push(SEQ);
+        Ok(Compound {
+            encoder,
+            variant: true,
+        })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        let _ = len;
+        self.encoder.out.push(MAP);
+        Ok(Compound {
+            encoder: self.encoder,
+            variant: false,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        let _ = name;
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        let _ = name;
+        let _ = variant_index;
+        let _ = len;
+        let encoder = self.begin_variant(variant);
+        encoder.out.push(MAP);
+        Ok(Compound {
+            encoder,
+            variant: true,
+        })
+    }
+}
+
+struct Compound<'a> {
+    encoder: &'a mut Encoder,
+    // Whether the sequence or map is the content of an enum variant, which is
+    // wrapped in a map of one entry.
+    variant: bool,
+}
+
+impl<'a> Compound<'a> {
+    fn element<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(BinarySerializer {
+            encoder: &mut *self.encoder,
+            role: Role::Value,
+        })
+    }
+
+    fn key<T>(&mut self, key: &T) -> Result<(), Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        key.serialize(BinarySerializer {
+            encoder: &mut *self.encoder,
+            role: Role::Key,
+        })
+    }
+
+    fn value<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let role = self.encoder.value_role();
+        value.serialize(BinarySerializer {
+            encoder: &mut *self.encoder,
+            role,
+        })
+    }
+
+    fn finish(self) {
+        self.encoder.out.push(END);
+        if self.variant {
+            self.encoder.out.push(END);
+        }
+    }
+}
+
+impl<'a> SerializeSeq for Compound<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Self::Error> {
+        self.finish();
+        Ok(())
+    }
+}
+
+impl<'a> SerializeTuple for Compound<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Self::Error> {
+        self.finish();
+        Ok(())
+    }
+}
+
+impl<'a> SerializeTupleStruct for Compound<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Self::Error> {
+        self.finish();
+        Ok(())
+    }
+}
+
+impl<'a> SerializeTupleVariant for Compound<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Self::Error> {
+        self.finish();
+        Ok(())
+    }
+}
+
+impl<'a> SerializeMap for Compound<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.key(key)
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.value(value)
+    }
+
+    fn end(self) -> Result<(), Self::Error> {
+        self.finish();
+        Ok(())
+    }
+}
+
+impl<'a> SerializeStruct for Compound<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.key(key)?;
+        self.value(value)
+    }
+
+    fn end(self) -> Result<(), Self::Error> {
+        self.finish();
+        Ok(())
+    }
+}
+
+impl<'a> SerializeStructVariant for Compound<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.key(key)?;
+        self.value(value)
+    }
+
+    fn end(self) -> Result<(), Self::Error> {
+        self.finish();
+        Ok(())
+    }
+}
+
+pub(crate) struct Reader<'de> {
+    bytes: &'de [u8],
+    index: usize,
+    strings: Vec<&'de str>,
+    kinds: Vec<&'de str>,
+    last_offset: u64,
+    remaining_depth: u8,
+}
+
+impl<'de> Reader<'de> {
+    pub(crate) fn new(bytes: &'de [u8]) -> Self {
+        Reader {
+            bytes,
+            index: 0,
+            strings: Vec::new(),
+            kinds: Vec::new(),
+            last_offset: 0,
+            remaining_depth: RECURSION_LIMIT,
+        }
+    }
+
+    // The input after what has been read so far.
+    #[cfg(feature = "driver")]
+    pub(crate) fn rest(&self) -> &'de [u8] {
+        &self.bytes[self.index..]
+    }
+
+    pub(crate) fn read_varint(&mut self) -> Result<u64, Error> {
+        let mut n = 0;
+        let mut shift = 0;
+        loop {
+            let byte = self.read_byte()?;
+            if shift == 63 && byte > 1 {
+                return Err(self.error("varint out of range"));
+            }
+            n |= u64::from(byte & 0x7f) << shift;
+            if byte < 0x80 {
+                return Ok(n);
+            }
+            shift += 7;
+        }
+    }
+
+    pub(crate) fn read_str(&mut self) -> Result<&'de str, Error> {
+        let bytes = self.read_bytes()?;
+        str::from_utf8(bytes).map_err(|_| self.error("invalid UTF-8 in string"))
+    }
+
+    fn read_bytes(&mut self) -> Result<&'de [u8], Error> {
+        let len = usize::try_from(self.read_varint()?).unwrap_or(usize::MAX);
+        if len > self.bytes.len() - self.index {
+            return Err(self.error("unexpected end of input"));
+        }
+        let bytes = &self.bytes[self.index..self.index + len];
+        self.index += len;
+        Ok(bytes)
+    }
+
+    fn read_byte(&mut self) -> Result<u8, Error> {
+        let Some(&byte) = self.bytes.get(self.index) else {
+            return Err(self.error("unexpected end of input"));
+        };
+        self.index += 1;
+        Ok(byte)
+    }
+
+    fn read_string(&mut self, tag: u8) -> Result<&'de str, Error> {
+        if tag == STRING {
+            let string = self.read_str()?;
+            self.strings.push(string);
+            return Ok(string);
+        }
+        let i = self.read_varint()?;
+        match usize::try_from(i).ok().and_then(|i| self.strings.get(i)) {
+            Some(string) => Ok(string),
+            None => Err(self.error("invalid string reference")),
+        }
+    }
+
+    fn read_offset(&mut self) -> Result<u64, Error> {
+        let zigzag = self.read_varint()?;
+        let delta = zigzag >> 1 ^ (zigzag & 1).wrapping_neg();
+        self.last_offset = self.last_offset.wrapping_add(delta);
+        Ok(self.last_offset)
+    }
+
+    fn read_kind(&mut self) -> Result<AnyKind<'de>, Error> {
+        let i = match self.read_varint()? {
+            0 => return Ok(AnyKind::Kind(Kind::null)),
+            n => usize::try_from(n - 1).unwrap_or(usize::MAX),
+        };
+        let kind = if let Some(kind) = self.kinds.get(i) {
+            kind
+        } else if i == self.kinds.len() {
+            let kind = self.read_str()?;
+            self.kinds.push(kind);
+            kind
+        } else {
+            return Err(self.error("invalid kind reference"));
+        };
+        AnyKind::deserialize(BorrowedStrDeserializer::<Error>::new(kind))
+    }
+
+    fn read_node<T>(&mut self) -> Result<(Node<T>, u64), Error>
+    where
+        T: Deserialize<'de>,
+    {
+        let id = Id::from_u64(self.read_varint()?);
+        let kind = self.read_kind()?;
+
+        let mut inner = Vec::<Node<T>>::new();
+        let mut fields = Fields { de: &mut *self };
+        let kind = T::deserialize(NodeDeserializer::new(&kind, &mut inner, &mut fields))?;
+        while let Some(IgnoredAny) = fields.next_key()? {
+            let IgnoredAny = fields.next_value()?;
+        }
+        self.expect_end()?;
+
+        let children = self.read_varint()?;
+        let inner = Vec::new();
+        Ok((Node { id, kind, inner }, children))
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.index).copied()
+    }
+
+    fn expect_end(&mut self) -> Result<(), Error> {
+        if self.peek() == Some(END) {
+            self.index += 1;
+            Ok(())
+        } else {
+            Err(self.error("expected end of fields, sequence or map"))
+        }
+    }
+
+    fn end(&self) -> Result<(), Error> {
+        if self.index == self.bytes.len() {
+            Ok(())
+        } else {
+            Err(self.error("trailing bytes"))
+        }
+    }
+
+    fn enter(&mut self) -> Result<(), Error> {
+        self.remaining_depth -= 1;
+        if self.remaining_depth == 0 {
+            Err(self.error("recursion limit exceeded"))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn leave(&mut self) {
+        self.remaining_depth += 1;
+    }
+
+    fn error(&self, msg: impl Display) -> Error {
+        Error::custom(format_args!("{} at byte {}", msg, self.index))
+    }
+}
+
+impl<'de, 'a> Deserializer<'de> for &'a mut Reader<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.read_byte()? {
+            NULL => visitor.visit_unit(),
+            FALSE => visitor.visit_bool(false),
+            TRUE => visitor.visit_bool(true),
+            UNSIGNED => visitor.visit_u64(self.read_varint()?),
+            NEGATIVE => match i64::try_from(self.read_varint()?) {
+                Ok(n) => visitor.visit_i64(!n),
+                Err(_) => Err(self.error("integer out of range")),
+            },
+            FLOAT => {
+                let Some(bytes) = self.bytes.get(self.index..self.index + 8) else {
+                    return Err(self.error("unexpected end of input"));
+                };
+                self.index += 8;
+                visitor.visit_f64(f64::from_le_bytes(bytes.try_into().unwrap()))
+            }
+            tag @ (STRING | STRING_REF) => visitor.visit_borrowed_str(self.read_string(tag)?),
+            BYTES => visitor.visit_borrowed_bytes(self.read_bytes()?),
+            OFFSET => visitor.visit_u64(self.read_offset()?),
+            SEQ => {
+                self.enter()?;
+                let value = visitor.visit_seq(Seq { de: &mut *self });
+                self.leave();
+                let value = value?;
+                self.expect_end()?;
+                Ok(value)
+            }
+            MAP => {
+                self.enter()?;
+                let value = visitor.visit_map(Map { de: &mut *self });
+                self.leave();
+                let value = value?;
+                self.expect_end()?;
+                Ok(value)
+            }
+            _ => {
+                self.index -= 1;
+                Err(self.error("invalid tag"))
+            }
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        if self.peek() == Some(NULL) {
+            self.index += 1;
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let _ = name;
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let _ = name;
+        let _ = variants;
+        match self.peek() {
+            Some(STRING | STRING_REF) => visitor.visit_enum(UnitVariant { de: self }),
+            Some(MAP) => {
+                self.index += 1;
+                self.enter()?;
+                let value = visitor.visit_enum(Variant { de: &mut *self });
+                self.leave();
+                let value = value?;
+                self.expect_end()?;
+                Ok(value)
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        // Skips without recursion, no matter how deeply nested, but still
+        // keeping track of new strings and offsets.
+        let mut depth = 0usize;
+        loop {
+            match self.read_byte()? {
+                NULL | FALSE | TRUE => {}
+                UNSIGNED | NEGATIVE => drop(self.read_varint()?),
+                FLOAT => {
+                    if self.bytes.len() - self.index < 8 {
+                        return Err(self.error("unexpected end of input"));
+                    }
+                    self.index += 8;
+                }
+                tag @ (STRING | STRING_REF) => drop(self.read_string(tag)?),
+                BYTES => drop(self.read_bytes()?),
+                OFFSET => drop(self.read_offset()?),
+                SEQ | MAP => depth += 1,
+                END if depth > 0 => depth -= 1,
+                _ => {
+                    self.index -= 1;
+                    return Err(self.error("invalid tag"));
+                }
+            }
+            if depth == 0 {
+                return visitor.visit_unit();
+            }
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier
+    }
+}
+
+// The fields of a node, up to but not including the END which closes them.
+struct Fields<'a, 'de> {
+    de: &'a mut Reader<'de>,
+}
+
+impl<'a, 'de> MapAccess<'de> for Fields<'a, 'de> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        if self.de.peek() == Some(END) {
+            return Ok(None);
+        }
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        seed.deserialize(&mut *self.de)
+    }
+}
+
+struct Seq<'a, 'de> {
+    de: &'a mut Reader<'de>,
+}
+
+impl<'a, 'de> SeqAccess<'de> for Seq<'a, 'de> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        if self.de.peek() == Some(END) {
+            return Ok(None);
+        }
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+}
+
+struct Map<'a, 'de> {
+    de: &'a mut Reader<'de>,
+}
+
+impl<'a, 'de> MapAccess<'de> for Map<'a, 'de> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        if self.de.peek() == Some(END) {
+            return Ok(None);
+        }
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        seed.deserialize(&mut *self.de)
+    }
+}
+
+struct UnitVariant<'a, 'de> {
+    de: &'a mut Reader<'de>,
+}
+
+impl<'a, 'de> EnumAccess<'de> for UnitVariant<'a, 'de> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant), Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let variant = seed.deserialize(&mut *self.de)?;
+        Ok((variant, self))
+    }
+}
+
+impl<'a, 'de> VariantAccess<'de> for UnitVariant<'a, 'de> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let _ = seed;
+        Err(Error::invalid_type(
+            Unexpected::UnitVariant,
+            &"newtype variant",
+        ))
+    }
+
+    fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let _ = len;
+        let _ = visitor;
+        Err(Error::invalid_type(
+            Unexpected::UnitVariant,
+            &"tuple variant",
+        ))
+    }
+
+    fn struct_variant<V>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let _ = fields;
+        let _ = visitor;
+        Err(Error::invalid_type(
+            Unexpected::UnitVariant,
+            &"struct variant",
+        ))
+    }
+}
+
+struct Variant<'a, 'de> {
+    de: &'a mut Reader<'de>,
+}
+
+impl<'a, 'de> EnumAccess<'de> for Variant<'a, 'de> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant), Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let variant = seed.deserialize(&mut *self.de)?;
+        Ok((variant, self))
+    }
+}
+
+impl<'a, 'de> VariantAccess<'de> for Variant<'a, 'de> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        Deserialize::deserialize(self.de)
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        seed.deserialize(self.de)
+    }
+
+    fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let _ = len;
+        self.de.deserialize_seq(visitor)
+    }
+
+    fn struct_variant<V>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let _ = fields;
+        self.de.deserialize_map(visitor)
+    }
+}
@@ -0,0 +1,334 @@
+use crate::binary::{self, Reader};
+use crate::driver::CompileCommand;
+use crate::include::IncludeGraph;
+use crate::intern;
+use crate::json::{self, Scanner};
+use crate::Node;
+use foldhash::HashMap;
+use serde::de::DeserializeOwned;
+use serde::ser::Serialize;
+use std::any;
+use std::fs;
+use std::io;
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+
+// Identifies the format of a cache entry. Bump the last byte whenever the
+// format changes.
+const MAGIC: &[u8; 16] = b"clang-ast cache\x03";
+
+static TMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Directory of previously deserialized syntax trees, for skipping clang and
+/// deserialization entirely when nothing has changed.
+///
+/// Available with the `driver` feature.
+///
+/// Each entry holds one `Node<T>` in the encoding of
+/// [`to_binary`](crate::to_binary). It is keyed by the compile command, the
+/// `--version` output of the compiler which the command runs, the version of
+/// this crate and the type `T`, and is valid only as long as the content of
+/// every file which it depends on is unchanged. That is checked by hashing the
+/// files again on every lookup.
+///
+/// For entries stored by [`Driver::run_cached`](crate::Driver::run_cached),
+/// the files depended on are all the ones which the compiler reports having
+/// read, through `-MD`, including headers which only affect preprocessing,
+/// such as a `config.h` used only in `#if`. For entries stored by
+/// [`store`](Cache::store), they are only the files listed in the include
+/// graph passed to it.
+///
+/// `T` must serialize into something it can deserialize from again, the same
+/// as for a round trip through serde_json. In particular it must serialize the
+/// `"kind"` of the node. Entries which fail to deserialize are treated as
+/// missing.
+///
+/// ```no_run
+/// # use serde_derive::{Deserialize, Serialize};
+/// #
+/// # #[derive(Deserialize, Serialize)]
+/// # enum Clang {
+/// #     Other,
+/// # }
+/// #
+/// use clang_ast::{Cache, CompilationDatabase, Driver};
+///
+/// fn main() -> Result<(), clang_ast::DriverError> {
+///     let database = CompilationDatabase::open("build/compile_commands.json")?;
+///     let cache = Cache::new("target/ast-cache");
+///     let results = Driver::new().run_cached::<Clang>(database.commands(), &cache);
+///     for result in results {
+///         let node = result?;
+///         # let _ = node;
+///     }
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Cache {
+    dir: PathBuf,
+    // The `--version` output of each compiler looked up so far, so that it is
+    // run once per compiler rather than once per lookup.
+    versions: Arc<Mutex<HashMap<String, String>>>,
+}
+
+impl Cache {
+    /// Cache stored in the given directory, which is created when the first
+    /// entry is stored if it does not exist yet.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Cache {
+            dir: dir.into(),
+            versions: Arc::default(),
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The syntax tree stored for this command, if any, provided that none of
+    /// the files it depends on have changed since.
+    pub fn load<T>(&self, command: &CompileCommand) -> Option<Node<T>>
+    where
+        T: DeserializeOwned,
+    {
+        let key = key::<T>(command, &self.version(command));
+        let entry = fs::read(self.path(&key)).ok()?;
+        let payload = check(&entry, &key, &command.directory)?;
+        binary::from_binary(payload).ok()
+    }
+
+    /// Stores the syntax tree of this command, along with the hashes of the
+    /// files in `includes`.
+    ///
+    /// The include graph must list every file which can affect the syntax
+    /// tree. It is best collected from the dump while skipping over it with
+    /// a `T` which deserializes every location, rather than while
+    /// deserializing `node` if its `T` leaves out some locations. Even then it
+    /// misses headers which only affect preprocessing, since those have no
+    /// locations in the dump, so the entry is not invalidated when one of them
+    /// changes.
+    pub fn store<T>(
+        &self,
+        command: &CompileCommand,
+        node: &Node<T>,
+        includes: &IncludeGraph,
+    ) -> io::Result<()>
+    where
+        T: Serialize,
+    {
+        let files = includes
+            .files()
+            .iter()
+            .map(|source_file| &*source_file.file)
+            .collect();
+        self.store_files(command, node, files)
+    }
+
+    pub(crate) fn store_files<T>(
+        &self,
+        command: &CompileCommand,
+        node: &Node<T>,
+        files: Vec<&str>,
+    ) -> io::Result<()>
+    where
+        T: Serialize,
+    {
+        let key = key::<T>(command, &self.version(command));
+        let payload =
+            binary::to_binary(node).map_err(|error| io::Error::new(io::ErrorKind::Other, error))?;
+
+        let mut entry = MAGIC.to_vec();
+        binary::write_str(&mut entry, &key);
+        let main_file = command.file.to_string_lossy();
+        let mut files: Vec<&str> = files;
+        if !files.contains(&&*main_file) {
+            files.push(&main_file);
+        }
+        binary::write_varint(&mut entry, files.len() as u64);
+        for file in files {
+            binary::write_str(&mut entry, file);
+            match fingerprint(&command.directory.join(file)) {
+                Some((len, hash)) => {
+                    binary::write_varint(&mut entry, 1);
+                    binary::write_varint(&mut entry, len);
+                    binary::write_varint(&mut entry, hash);
+                }
+                None => binary::write_varint(&mut entry, 0),
+            }
+        }
+        binary::write_varint(&mut entry, hash(&payload));
+        entry.extend_from_slice(&payload);
+
+        // Write to a temporary file and rename it into place, so that readers
+        // in other processes never observe an incomplete entry.
+        let path = self.path(&key);
+        let tmp = self.temp_path()?;
+        fs::write(&tmp, entry)?;
+        fs::rename(&tmp, &path).map_err(|error| {
+            let _ = fs::remove_file(&tmp);
+            error
+        })
+    }
+
+    // Identifies the compiler which the command runs, so that upgrading it in
+    // place invalidates the entries it produced. Empty if it cannot be run.
+    fn version(&self, command: &CompileCommand) -> String {
+        let Some(program) = command.arguments.first() else {
+            return String::new();
+        };
+        let mut versions = self.versions.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(version) = versions.get(program) {
+            return version.clone();
+        }
+        let version = Command::new(program)
+            .arg("--version")
+            .current_dir(&command.directory)
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map_or_else(String::new, |output| {
+                String::from_utf8_lossy(&output.stdout).into_owned()
+            });
+        versions.insert(program.clone(), version.clone());
+        version
+    }
+
+    // Path in the cache directory for a temporary file. The name is unique to
+    // this call, since other threads of the same process may be storing the
+    // same entry at once.
+    pub(crate) fn temp_path(&self) -> io::Result<PathBuf> {
+        fs::create_dir_all(&self.dir)?;
+        Ok(self.dir.join(format!(
+            "{}-{}.tmp",
+            process::id(),
+            TMP_COUNTER.fetch_add(1, Ordering::Relaxed),
+        )))
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{:016x}.ast", hash(key.as_bytes())))
+    }
+}
+
+// Everything about a command which determines the resulting Node<T>, other
+// than the content of the files.
+fn key<T>(command: &CompileCommand, version: &str) -> String {
+    let mut key = format!(
+        "{}\0{}\0{}\0{}\0{}",
+        env!("CARGO_PKG_VERSION"),
+        version,
+        any::type_name::<T>(),
+        command.directory.display(),
+        command.file.display(),
+    );
+    for arg in &command.arguments {
+        key.push('\0');
+        key.push_str(arg);
+    }
+    key
+}
+
+// Validates an entry against the key it was looked up by and the current state
+// of the files, returning the encoded syntax tree if it is still up to date.
+fn check<'a>(entry: &'a [u8], key: &str, directory: &Path) -> Option<&'a [u8]> {
+    let entry = entry.strip_prefix(MAGIC)?;
+    let mut reader = Reader::new(entry);
+    if reader.read_str().ok()? != key {
+        return None;
+    }
+    for _ in 0..reader.read_varint().ok()? {
+        let file = reader.read_str().ok()?;
+        let expected = match reader.read_varint().ok()? {
+            0 => None,
+            _ => Some((reader.read_varint().ok()?, reader.read_varint().ok()?)),
+        };
+        if fingerprint(&directory.join(file)) != expected {
+            return None;
+        }
+    }
+    let payload_hash = reader.read_varint().ok()?;
+    let payload = reader.rest();
+    if hash(payload) != payload_hash {
+        return None;
+    }
+    Some(payload)
+}
+
+// Length and hash of a file's content, or None if it cannot be read, as for
+// the `<built-in>` and `<scratch space>` buffers which are not real files.
+fn fingerprint(path: &Path) -> Option<(u64, u64)> {
+    let content = fs::read(path).ok()?;
+    Some((content.len() as u64, hash(&content)))
+}
+
+// 64-bit FNV-1a. The hashes are persisted in cache entries and their file
+// names, so the algorithm must not change with the version of a dependency.
+fn hash(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for &byte in bytes {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
+
+// Every file which any location in the dump refers to, regardless of which
+// locations the caller's T deserializes, together with every file listed in
+// the dependency file written by clang's `-MD -MF`. The latter includes the
+// headers which only affect preprocessing and so have no locations.
+pub(crate) fn dependencies(json: &[u8], depfile: &str) -> Vec<String> {
+    let ((), includes) = IncludeGraph::collect(|| {
+        let _intern = intern::activate();
+        let mut scanner = Scanner::new(json);
+        let _ = json::skip_node(&mut scanner);
+    });
+    let mut files: Vec<String> = includes
+        .files()
+        .iter()
+        .map(|source_file| source_file.file.to_string())
+        .collect();
+    for file in prerequisites(depfile) {
+        if !files.contains(&file) {
+            files.push(file);
+        }
+    }
+    files
+}
+
+// The files after the colon of the Makefile rule in a dependency file. Clang
+// escapes spaces and `#` in them with a backslash, and `$` as `$$`, and breaks
+// long lines with a backslash before the newline.
+fn prerequisites(depfile: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut chars = depfile.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' if matches!(chars.peek(), Some(' ' | '#')) => word.push(chars.next().unwrap()),
+            '\\' if matches!(chars.peek(), Some('\n' | '\r')) => {}
+            '$' if chars.peek() == Some(&'$') => {
+                chars.next();
+                word.push('$');
+            }
+            ' ' | '\t' | '\n' | '\r' => {
+                if !word.is_empty() {
+                    words.push(mem::take(&mut word));
+                }
+            }
+            _ => word.push(ch),
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    match words.iter().position(|word| word.ends_with(':')) {
+        Some(target) => words.split_off(target + 1),
+        None => Vec::new(),
+    }
+}
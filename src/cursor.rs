@@ -0,0 +1,242 @@
+use crate::{Kind, Node};
+use std::slice;
+
+/// Types from which the [`Kind`] of a node can be read.
+///
+/// This is what allows [`Cursor::enclosing`] and [`Ancestors::of_kind`] to
+/// filter nodes by kind regardless of how the caller's `T` is laid out. For a
+/// `T` that is a struct with a `kind: Kind` field, the impl is one line.
+///
+/// ```
+/// # use clang_ast::{HasKind, Kind};
+/// # use serde_derive::Deserialize;
+/// #
+/// #[derive(Deserialize)]
+/// struct Clang {
+///     kind: Kind,
+/// }
+///
+/// impl HasKind for Clang {
+///     fn kind(&self) -> Kind {
+///         self.kind
+///     }
+/// }
+/// ```
+pub trait HasKind {
+    fn kind(&self) -> Kind;
+}
+
+impl HasKind for Kind {
+    fn kind(&self) -> Kind {
+        *self
+    }
+}
+
+/// Position within a borrowed syntax tree, with access to the ancestors and
+/// siblings of the current node.
+///
+/// `Node<T>` has no parent pointers. A cursor remembers the path by which it
+/// reached the current node, so questions like "which function is this
+/// expression in" can be answered without rebuilding the tree.
+///
+/// ```
+/// # use clang_ast::{Cursor, HasKind, Kind, Node};
+/// #
+/// fn enclosing_function<'ast, T: HasKind>(cursor: &Cursor<'ast, T>) -> Option<&'ast Node<T>> {
+///     cursor.enclosing(Kind::FunctionDecl)
+/// }
+/// ```
+///
+/// A cursor is obtained from [`Node::cursor`] at the root of a tree and then
+/// moved with the `goto_*` methods, or from
+/// [`IdIndex::cursor`](crate::IdIndex::cursor) directly at any node with an
+/// id.
+pub struct Cursor<'ast, T> {
+    node: &'ast Node<T>,
+    // Each ancestor of the current node, outermost first, together with the
+    // index within that ancestor's `inner` of the next node along the path.
+    path: Vec<(&'ast Node<T>, usize)>,
+}
+
+impl<T> Node<T> {
+    /// Cursor positioned at this node, which is treated as the root.
+    pub fn cursor(&self) -> Cursor<'_, T> {
+        Cursor {
+            node: self,
+            path: Vec::new(),
+        }
+    }
+}
+
+impl<'ast, T> Cursor<'ast, T> {
+    pub(crate) fn from_path(node: &'ast Node<T>, path: Vec<(&'ast Node<T>, usize)>) -> Self {
+        Cursor { node, path }
+    }
+
+    /// The node at which the cursor is positioned.
+    pub fn node(&self) -> &'ast Node<T> {
+        self.node
+    }
+
+    /// Number of ancestors of the current node. The root has depth 0.
+    pub fn depth(&self) -> usize {
+        self.path.len()
+    }
+
+    /// The node whose `inner` contains the current node, or `None` at the
+    /// root.
+    pub fn parent(&self) -> Option<&'ast Node<T>> {
+        let (parent, _index) = self.path.last()?;
+        Some(parent)
+    }
+
+    /// Index of the current node within its parent's `inner`, or `None` at
+    /// the root.
+    pub fn child_index(&self) -> Option<usize> {
+        let (_parent, index) = self.path.last()?;
+        Some(*index)
+    }
+
+    /// All children of the current node's parent, including the current node.
+    /// At the root this is just the root.
+    pub fn siblings(&self) -> &'ast [Node<T>] {
+        match self.path.last() {
+            Some((parent, _index)) => &parent.inner,
+            None => slice::from_ref(self.node),
+        }
+    }
+
+    /// The sibling immediately before the current node.
+    pub fn prev_sibling(&self) -> Option<&'ast Node<T>> {
+        let (parent, index) = self.path.last()?;
+        parent.inner.get(index.checked_sub(1)?)
+    }
+
+    /// The sibling immediately after the current node.
+    pub fn next_sibling(&self) -> Option<&'ast Node<T>> {
+        let (parent, index) = self.path.last()?;
+        parent.inner.get(index + 1)
+    }
+
+    /// Iterator over the ancestors of the current node, starting with its
+    /// parent and ending with the root.
+    pub fn ancestors(&self) -> Ancestors<'_, 'ast, T> {
+        Ancestors {
+            iter: self.path.iter(),
+        }
+    }
+
+    /// The nearest ancestor of the given kind or of any subclass of it, as
+    /// determined by [`Kind::is_a`].
+    pub fn enclosing(&self, kind: Kind) -> Option<&'ast Node<T>>
+    where
+        T: HasKind,
+    {
+        self.ancestors().of_kind(kind).next()
+    }
+
+    /// Moves to the parent of the current node. Returns false, without
+    /// moving, if the cursor is at the root.
+    pub fn goto_parent(&mut self) -> bool {
+        match self.path.pop() {
+            Some((parent, _index)) => {
+                self.node = parent;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Moves to the child of the current node at the given index. Returns
+    /// false, without moving, if there is no such child.
+    pub fn goto_child(&mut self, index: usize) -> bool {
+        match self.node.inner.get(index) {
+            Some(child) => {
+                self.path.push((self.node, index));
+                self.node = child;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Moves to the first child of the current node. Returns false, without
+    /// moving, if the current node has no children.
+    pub fn goto_first_child(&mut self) -> bool {
+        self.goto_child(0)
+    }
+
+    /// Moves to the sibling immediately before the current node. Returns
+    /// false, without moving, if there is none.
+    pub fn goto_prev_sibling(&mut self) -> bool {
+        self.goto_sibling(|index| index.checked_sub(1))
+    }
+
+    /// Moves to the sibling immediately after the current node. Returns false,
+    /// without moving, if there is none.
+    pub fn goto_next_sibling(&mut self) -> bool {
+        self.goto_sibling(|index| index.checked_add(1))
+    }
+
+    fn goto_sibling(&mut self, step: fn(usize) -> Option<usize>) -> bool {
+        let Some((parent, index)) = self.path.last_mut() else {
+            return false;
+        };
+        let Some(i) = step(*index) else {
+            return false;
+        };
+        let Some(sibling) = parent.inner.get(i) else {
+            return false;
+        };
+        *index = i;
+        self.node = sibling;
+        true
+    }
+}
+
+impl<'ast, T> Clone for Cursor<'ast, T> {
+    fn clone(&self) -> Self {
+        Cursor {
+            node: self.node,
+            path: self.path.clone(),
+        }
+    }
+}
+
+/// Iterator over the ancestors of a node, returned by [`Cursor::ancestors`].
+pub struct Ancestors<'a, 'ast, T> {
+    iter: slice::Iter<'a, (&'ast Node<T>, usize)>,
+}
+
+impl<'a, 'ast, T> Ancestors<'a, 'ast, T> {
+    /// Restricts the iterator to ancestors of the given kind or of any
+    /// subclass of it, as determined by [`Kind::is_a`].
+    pub fn of_kind(self, kind: Kind) -> impl Iterator<Item = &'ast Node<T>> + 'a
+    where
+        T: HasKind,
+    {
+        self.filter(move |node| node.kind.kind().is_a(kind))
+    }
+}
+
+impl<'a, 'ast, T> Iterator for Ancestors<'a, 'ast, T> {
+    type Item = &'ast Node<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (node, _index) = self.iter.next_back()?;
+        Some(node)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a, 'ast, T> DoubleEndedIterator for Ancestors<'a, 'ast, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let (node, _index) = self.iter.next()?;
+        Some(node)
+    }
+}
+
+impl<'a, 'ast, T> ExactSizeIterator for Ancestors<'a, 'ast, T> {}
@@ -0,0 +1,608 @@
+use crate::cache::{self, Cache};
+use crate::error::Error;
+use crate::intern;
+use crate::json::Scanner;
+use crate::Node;
+use serde::de::{
+    Deserialize, DeserializeOwned, Deserializer, Error as _, IgnoredAny, MapAccess, Visitor,
+};
+use serde::ser::Serialize;
+use std::error::Error as StdError;
+use std::ffi::{OsStr, OsString};
+use std::fmt::{self, Debug, Display};
+use std::fs;
+use std::io::ErrorKind;
+use std::num::NonZeroUsize;
+use std::panic;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+// Executable names to try, newest first. Keep in sync with the list in
+// tests/clone/build.rs.
+static CLANG: &[&str] = &[
+    "clang++-20",
+    "clang++-19",
+    "clang++-18",
+    "clang++-17",
+    "clang++-16",
+    "clang++-15",
+    "clang++-14",
+    "clang++-13",
+    "clang++-12",
+    "clang++-11",
+    "clang++",
+];
+
+/// The entries of a `compile_commands.json` [compilation database], as written
+/// by CMake's `CMAKE_EXPORT_COMPILE_COMMANDS` or by Bear.
+///
+/// [compilation database]: https://clang.llvm.org/docs/JSONCompilationDatabase.html
+#[derive(Clone, Debug, Default)]
+pub struct CompilationDatabase {
+    commands: Vec<CompileCommand>,
+}
+
+/// One entry of a [`CompilationDatabase`]: how to compile one source file.
+#[derive(Clone, Debug)]
+pub struct CompileCommand {
+    /// The working directory of the compilation. Relative paths in the other
+    /// fields and in the arguments are relative to this.
+    pub directory: PathBuf,
+    /// The source file being compiled.
+    pub file: PathBuf,
+    /// The compiler executable followed by its arguments. For entries which
+    /// give a shell `"command"` string instead, this is the result of
+    /// splitting it the way a shell would.
+    pub arguments: Vec<String>,
+    /// The output file of the compilation, if recorded in the database.
+    pub output: Option<PathBuf>,
+}
+
+impl CompilationDatabase {
+    /// Reads a `compile_commands.json` file.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, DriverError> {
+        let path = path.as_ref();
+        let json = fs::read(path).map_err(|error| {
+            DriverError::new(format!("failed to read {}", path.display()))
+                .with_source(Box::new(error))
+        })?;
+        CompilationDatabase::from_slice(&json).map_err(|error| {
+            DriverError::new(format!("failed to parse {}", path.display()))
+                .with_source(Box::new(error))
+        })
+    }
+
+    /// Parses the contents of a `compile_commands.json` file.
+    pub fn from_slice(bytes: &[u8]) -> Result<Self, Error> {
+        let mut scanner = Scanner::new(bytes);
+        let commands = Vec::deserialize(&mut scanner)?;
+        scanner.end()?;
+        Ok(CompilationDatabase { commands })
+    }
+
+    pub fn commands(&self) -> &[CompileCommand] {
+        &self.commands
+    }
+}
+
+impl CompileCommand {
+    /// Arguments with which to run clang in place of this command so that it
+    /// prints the AST of the file instead of compiling it, not including the
+    /// name of the executable.
+    ///
+    /// Output files are dropped, namely `-c` and `-o` as well as dependency
+    /// file options such as `-MD` and `-MF`, while everything which affects
+    /// the meaning of the source, such as defines, include paths and the
+    /// language standard, is kept.
+    ///
+    /// The arguments are meant for `clang++`, which would otherwise parse C
+    /// and Objective-C files as C++ and Objective-C++. For those files they
+    /// begin by setting the language with `-x`, which an `-x` from the
+    /// original command still overrides.
+    pub fn ast_dump_arguments(&self) -> Vec<String> {
+        let mut args = vec![
+            "-fsyntax-only".to_owned(),
+            "-Xclang".to_owned(),
+            "-ast-dump=json".to_owned(),
+        ];
+        if let Some(language) = language(&self.file) {
+            args.push("-x".to_owned());
+            args.push(language.to_owned());
+        }
+        let mut original = self.arguments.iter().skip(1);
+        while let Some(arg) = original.next() {
+            match arg.as_str() {
+                "-c" | "-S" | "-E" | "-fsyntax-only" | "-M" | "-MM" | "-MD" | "-MMD" | "-MP"
+                | "-MG" => {}
+                "-o" | "-MF" | "-MT" | "-MQ" => {
+                    original.next();
+                }
+                _ if is_joined_output(arg)
+                    || ["-MF", "-MT", "-MQ"]
+                        .iter()
+                        .any(|flag| arg.starts_with(flag)) => {}
+                _ => args.push(arg.clone()),
+            }
+        }
+        args
+    }
+}
+
+// The language to pass to clang++ for source files which it would not parse
+// the way clang does based on their extension.
+fn language(file: &Path) -> Option<&'static str> {
+    match file.extension()?.to_str()? {
+        "c" => Some("c"),
+        "i" => Some("cpp-output"),
+        "m" => Some("objective-c"),
+        "mi" => Some("objective-c-cpp-output"),
+        _ => None,
+    }
+}
+
+// Whether clang parses the argument as `-o` with the output file joined to it,
+// as opposed to one of its longer options which begin with `-o`, like
+// `-objcmt-migrate-literals`, `-object-file-name=` and `-order_file`.
+fn is_joined_output(arg: &str) -> bool {
+    arg.len() > 2
+        && arg.starts_with("-o")
+        && !arg.starts_with("-obj")
+        && !arg.starts_with("-order_file")
+}
+
+impl<'de> Deserialize<'de> for CompileCommand {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_map(CompileCommandVisitor)
+    }
+}
+
+struct CompileCommandVisitor;
+
+impl<'de> Visitor<'de> for CompileCommandVisitor {
+    type Value = CompileCommand;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("compile command")
+    }
+
+    fn visit_map<M>(self, mut map: M) -> Result<Self::Value, M::Error>
+    where
+        M: MapAccess<'de>,
+    {
+        let mut directory = None;
+        let mut file = None;
+        let mut arguments = None;
+        let mut command = None;
+        let mut output = None;
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "directory" => directory = Some(map.next_value::<PathBuf>()?),
+                "file" => file = Some(map.next_value::<PathBuf>()?),
+                "arguments" => arguments = Some(map.next_value::<Vec<String>>()?),
+                "command" => command = Some(map.next_value::<String>()?),
+                "output" => output = Some(map.next_value::<PathBuf>()?),
+                _ => drop(map.next_value::<IgnoredAny>()?),
+            }
+        }
+        let arguments = match (arguments, command) {
+            (Some(arguments), _) => arguments,
+            (None, Some(command)) => split_command(&command),
+            (None, None) => return Err(M::Error::missing_field("arguments")),
+        };
+        Ok(CompileCommand {
+            directory: directory.ok_or_else(|| M::Error::missing_field("directory"))?,
+            file: file.ok_or_else(|| M::Error::missing_field("file"))?,
+            arguments,
+            output,
+        })
+    }
+}
+
+// Splits a command line into words following the quoting rules of a POSIX
+// shell, which is how the "command" of a compilation database is specified.
+fn split_command(command: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = None::<String>;
+    let mut chars = command.chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            ' ' | '\t' | '\n' | '\r' => words.extend(word.take()),
+            '\\' => {
+                let word = word.get_or_insert_with(String::new);
+                match chars.next() {
+                    Some('\n') | None => {}
+                    Some(ch) => word.push(ch),
+                }
+            }
+            '\'' => {
+                let word = word.get_or_insert_with(String::new);
+                word.extend(chars.by_ref().take_while(|&ch| ch != '\''));
+            }
+            '"' => {
+                let word = word.get_or_insert_with(String::new);
+                while let Some(ch) = chars.next() {
+                    match ch {
+                        '"' => break,
+                        '\\' => match chars.next() {
+                            Some(ch @ ('"' | '\\' | '$' | '`')) => word.push(ch),
+                            Some('\n') | None => {}
+                            Some(ch) => {
+                                word.push('\\');
+                                word.push(ch);
+                            }
+                        },
+                        _ => word.push(ch),
+                    }
+                }
+            }
+            _ => word.get_or_insert_with(String::new).push(ch),
+        }
+    }
+    words.extend(word);
+    words
+}
+
+/// Runs clang over the entries of a [`CompilationDatabase`] and deserializes
+/// the AST of each file.
+///
+/// Available with the `driver` feature.
+///
+/// ```no_run
+/// # use serde_derive::Deserialize;
+/// #
+/// # #[derive(Deserialize)]
+/// # enum Clang {
+/// #     Other,
+/// # }
+/// #
+/// use clang_ast::{CompilationDatabase, Driver};
+///
+/// fn main() -> Result<(), clang_ast::DriverError> {
+///     let database = CompilationDatabase::open("build/compile_commands.json")?;
+///     let results = Driver::new().jobs(4).run::<Clang>(database.commands());
+///     for (command, result) in database.commands().iter().zip(results) {
+///         let node = result?;
+///         println!("{}: {} top-level nodes", command.file.display(), node.inner.len());
+///     }
+///     Ok(())
+/// }
+/// ```
+///
+/// File paths in all of the resulting syntax trees are interned together, as
+/// by an [`Interner`](crate::Interner), so that each header included by
+/// several files is allocated once.
+#[derive(Clone, Debug, Default)]
+pub struct Driver {
+    clang: Option<PathBuf>,
+    jobs: Option<usize>,
+    args: Vec<String>,
+}
+
+impl Driver {
+    pub fn new() -> Self {
+        Driver::default()
+    }
+
+    /// The clang executable to run.
+    ///
+    /// By default the newest of `clang++-20` down to `clang++-11` which is
+    /// found on the `PATH` and runs successfully is used, or else `clang++`.
+    /// The compiler named in the database is not used because it may not be
+    /// clang.
+    pub fn clang(&mut self, clang: impl Into<PathBuf>) -> &mut Self {
+        self.clang = Some(clang.into());
+        self
+    }
+
+    /// The maximum number of clang processes to run at once. The default is
+    /// the number of available cores.
+    pub fn jobs(&mut self, jobs: usize) -> &mut Self {
+        self.jobs = Some(jobs.max(1));
+        self
+    }
+
+    /// An additional argument to pass to every clang invocation, after the
+    /// ones from the database.
+    pub fn arg(&mut self, arg: impl Into<String>) -> &mut Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    /// Dumps and deserializes the AST of each command's file.
+    ///
+    /// The results are in the same order as the commands. A failure to run
+    /// clang on one file, or to deserialize its output, does not prevent the
+    /// other files from being processed.
+    pub fn run<'a, T>(
+        &self,
+        commands: impl IntoIterator<Item = &'a CompileCommand>,
+    ) -> Vec<Result<Node<T>, DriverError>>
+    where
+        T: DeserializeOwned + Send,
+    {
+        self.run_each(commands, |clang, command| {
+            let json = self.dump(clang, command, None)?;
+            deserialize(&json, command)
+        })
+    }
+
+    /// Like [`run`](Driver::run), but takes the syntax tree of each file from
+    /// the cache if it is up to date, and otherwise stores it there after
+    /// dumping it.
+    ///
+    /// Failures to write to the cache are ignored, other than that the file
+    /// will be dumped again next time.
+    pub fn run_cached<'a, T>(
+        &self,
+        commands: impl IntoIterator<Item = &'a CompileCommand>,
+        cache: &Cache,
+    ) -> Vec<Result<Node<T>, DriverError>>
+    where
+        T: Serialize + DeserializeOwned + Send,
+    {
+        self.run_each(commands, |clang, command| {
+            let invocation = self.invocation(clang, command);
+            if let Some(node) = cache.load(&invocation) {
+                return Ok(node);
+            }
+            // Clang lists every file it read in the dependency file, including
+            // headers which have no locations in the dump.
+            let depfile = cache.temp_path();
+            let json = self.dump(clang, command, depfile.as_deref().ok());
+            let depfile = depfile.ok().and_then(|depfile| {
+                let contents = fs::read_to_string(&depfile);
+                let _ = fs::remove_file(&depfile);
+                contents.ok()
+            });
+            let json = json?;
+            let node = deserialize(&json, command)?;
+            if let Some(depfile) = depfile {
+                let files = cache::dependencies(&json, &depfile);
+                let files = files.iter().map(String::as_str).collect();
+                let _ = cache.store_files(&invocation, &node, files);
+            }
+            Ok(node)
+        })
+    }
+
+    fn run_each<'a, T>(
+        &self,
+        commands: impl IntoIterator<Item = &'a CompileCommand>,
+        f: impl Fn(&OsStr, &CompileCommand) -> Result<Node<T>, DriverError> + Sync,
+    ) -> Vec<Result<Node<T>, DriverError>>
+    where
+        T: Send,
+    {
+        let commands: Vec<&CompileCommand> = commands.into_iter().collect();
+        let clang = match &self.clang {
+            Some(clang) => clang.clone().into_os_string(),
+            None => match find_clang() {
+                Ok(clang) => clang,
+                Err(error) => {
+                    return commands
+                        .iter()
+                        .map(|command| Err(error.clone_for(command)))
+                        .collect();
+                }
+            },
+        };
+
+        let jobs = self
+            .jobs
+            .unwrap_or_else(|| thread::available_parallelism().map_or(1, NonZeroUsize::get));
+        let interner = intern::installed().unwrap_or_default();
+        let next = AtomicUsize::new(0);
+        let mut results: Vec<_> = thread::scope(|scope| {
+            let workers: Vec<_> = (0..jobs.min(commands.len()))
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut results = Vec::new();
+                        loop {
+                            let i = next.fetch_add(1, Ordering::Relaxed);
+                            let Some(command) = commands.get(i) else {
+                                return results;
+                            };
+                            let node = interner.install(|| f(&clang, command));
+                            results.push((i, node));
+                        }
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .flat_map(|worker| match worker.join() {
+                    Ok(results) => results,
+                    Err(panic) => panic::resume_unwind(panic),
+                })
+                .collect()
+        });
+
+        results.sort_by_key(|(i, _node)| *i);
+        results.into_iter().map(|(_i, node)| node).collect()
+    }
+
+    // The command which is actually run in place of the one from the database.
+    fn invocation(&self, clang: &OsStr, command: &CompileCommand) -> CompileCommand {
+        let mut arguments = vec![clang.to_string_lossy().into_owned()];
+        arguments.extend(command.ast_dump_arguments());
+        arguments.extend(self.args.iter().cloned());
+        CompileCommand {
+            directory: command.directory.clone(),
+            file: command.file.clone(),
+            arguments,
+            output: None,
+        }
+    }
+
+    // Runs clang, returning the JSON it prints. If `depfile` is given, clang
+    // also writes the files it read to there.
+    fn dump(
+        &self,
+        clang: &OsStr,
+        command: &CompileCommand,
+        depfile: Option<&Path>,
+    ) -> Result<Vec<u8>, DriverError> {
+        let file = command.file.display();
+        let program = Path::new(clang).display();
+        let mut cmd = Command::new(clang);
+        cmd.args(command.ast_dump_arguments()).args(&self.args);
+        if let Some(depfile) = depfile {
+            cmd.args(["-MD", "-MF"]).arg(depfile);
+        }
+        let output = cmd
+            .current_dir(&command.directory)
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|error| {
+                DriverError::new(format!("failed to run {} on {}", program, file))
+                    .with_file(&command.file)
+                    .with_source(Box::new(error))
+            })?;
+        if !output.status.success() {
+            let mut error = DriverError::new(format!(
+                "{} failed on {} with {}",
+                program, file, output.status,
+            ))
+            .with_file(&command.file);
+            error.err.stderr = Some(String::from_utf8_lossy(&output.stderr).into_owned());
+            return Err(error);
+        }
+        Ok(output.stdout)
+    }
+}
+
+fn deserialize<T>(json: &[u8], command: &CompileCommand) -> Result<Node<T>, DriverError>
+where
+    T: DeserializeOwned,
+{
+    crate::from_slice(json).map_err(|error| {
+        DriverError::new(format!(
+            "failed to deserialize the AST of {}",
+            command.file.display(),
+        ))
+        .with_file(&command.file)
+        .with_source(Box::new(error))
+    })
+}
+
+fn find_clang() -> Result<OsString, DriverError> {
+    for clang in CLANG {
+        match Command::new(clang)
+            .arg("--version")
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+        {
+            Ok(status) if status.success() => return Ok(OsString::from(clang)),
+            Ok(_status) => {}
+            Err(error) if error.kind() == ErrorKind::NotFound => {}
+            Err(error) => {
+                return Err(DriverError::new(format!("failed to run {}", clang))
+                    .with_source(Box::new(error)));
+            }
+        }
+    }
+    Err(DriverError::new(format!(
+        "clang not found, tried {}",
+        CLANG.join(", "),
+    )))
+}
+
+/// Error produced by [`Driver`] and [`CompilationDatabase::open`].
+pub struct DriverError {
+    err: Box<DriverErrorImpl>,
+}
+
+struct DriverErrorImpl {
+    msg: String,
+    file: Option<PathBuf>,
+    stderr: Option<String>,
+    source: Option<Box<dyn StdError + Send + Sync>>,
+}
+
+impl DriverError {
+    /// The source file whose AST was being dumped.
+    pub fn file(&self) -> Option<&Path> {
+        self.err.file.as_deref()
+    }
+
+    /// What clang printed to stderr, if the error is that clang exited
+    /// unsuccessfully.
+    pub fn stderr(&self) -> Option<&str> {
+        self.err.stderr.as_deref()
+    }
+
+    fn new(msg: String) -> Self {
+        DriverError {
+            err: Box::new(DriverErrorImpl {
+                msg,
+                file: None,
+                stderr: None,
+                source: None,
+            }),
+        }
+    }
+
+    fn with_file(mut self, file: &Path) -> Self {
+        self.err.file = Some(file.to_owned());
+        self
+    }
+
+    fn with_source(mut self, source: Box<dyn StdError + Send + Sync>) -> Self {
+        self.err.source = Some(source);
+        self
+    }
+
+    // The same error reported for each command, such as when there is no
+    // clang to run.
+    fn clone_for(&self, command: &CompileCommand) -> Self {
+        let mut error = DriverError::new(self.err.msg.clone()).with_file(&command.file);
+        if let Some(source) = &self.err.source {
+            error = error.with_source(source.to_string().into());
+        }
+        error
+    }
+}
+
+impl StdError for DriverError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match &self.err.source {
+            Some(source) => Some(&**source),
+            None => None,
+        }
+    }
+}
+
+impl Display for DriverError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(&self.err.msg)?;
+        if let Some(source) = &self.err.source {
+            write!(formatter, ": {}", source)?;
+        }
+        if let Some(stderr) = &self.err.stderr {
+            let stderr = stderr.trim_end();
+            if !stderr.is_empty() {
+                write!(formatter, "\n{}", stderr)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Debug for DriverError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter
+            .debug_struct("DriverError")
+            .field("msg", &self.err.msg)
+            .field("file", &self.err.file)
+            .field("stderr", &self.err.stderr)
+            .field("source", &self.err.source)
+            .finish()
+    }
+}
@@ -0,0 +1,105 @@
+use std::fmt::{self, Debug, Display};
+
+/// Error produced by [`from_slice`](crate::from_slice) and the other entry
+/// points which read the JSON representation of an AST dump directly.
+pub struct Error {
+    err: Box<ErrorImpl>,
+}
+
+struct ErrorImpl {
+    msg: Box<str>,
+    line: usize,
+    column: usize,
+}
+
+impl Error {
+    /// One-based line number of the input at which the error was detected,
+    /// or 0 if the error is not associated with a position in the input.
+    pub fn line(&self) -> usize {
+        self.err.line
+    }
+
+    /// One-based column number of the input at which the error was detected,
+    /// or 0 if the error is not associated with a position in the input.
+    pub fn column(&self) -> usize {
+        self.err.column
+    }
+
+    // Error caused by the byte at `offset`, or by the end of input if `offset`
+    // is the length of the input.
+    pub(crate) fn syntax(msg: &str, bytes: &[u8], offset: usize) -> Self {
+        Error::new(msg.to_owned(), 0, 0).at(bytes, offset + 1)
+    }
+
+    // Attaches a position to an error which does not have one yet. The
+    // position is that of the last byte before `end`, matching the positions
+    // reported by serde_json.
+    pub(crate) fn at(mut self, bytes: &[u8], end: usize) -> Self {
+        if self.err.line == 0 {
+            let prefix = &bytes[..end.min(bytes.len())];
+            let mut line = 1;
+            let mut line_start = 0;
+            for (i, byte) in prefix.iter().enumerate() {
+                if *byte == b'\n' {
+                    line += 1;
+                    line_start = i + 1;
+                }
+            }
+            self.err.line = line;
+            self.err.column = prefix.len() - line_start;
+        }
+        self
+    }
+
+    fn new(msg: String, line: usize, column: usize) -> Self {
+        Error {
+            err: Box::new(ErrorImpl {
+                msg: msg.into_boxed_str(),
+                line,
+                column,
+            }),
+        }
+    }
+}
+
+impl serde::de::Error for Error {
+    fn custom<T>(msg: T) -> Self
+    where
+        T: Display,
+    {
+        Error::new(msg.to_string(), 0, 0)
+    }
+}
+
+impl serde::ser::Error for Error {
+    fn custom<T>(msg: T) -> Self
+    where
+        T: Display,
+    {
+        Error::new(msg.to_string(), 0, 0)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl Display for Error {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let ErrorImpl { msg, line, column } = &*self.err;
+        if *line == 0 {
+            formatter.write_str(msg)
+        } else {
+            write!(formatter, "{} at line {} column {}", msg, line, column)
+        }
+    }
+}
+
+impl Debug for Error {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let ErrorImpl { msg, line, column } = &*self.err;
+        write!(
+            formatter,
+            "Error({:?}, line: {}, column: {})",
+            msg, line, column,
+        )
+    }
+}
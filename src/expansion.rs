@@ -0,0 +1,101 @@
+use crate::loc::{BareSourceLocation, SourceLocation, SourceRange};
+
+impl SourceLocation {
+    /// Whether this location is inside the expansion of a macro, either in the
+    /// macro's definition or in one of the arguments of its invocation.
+    ///
+    /// Clang prints separate `spellingLoc` and `expansionLoc` exactly for such
+    /// locations.
+    pub fn is_from_macro(&self) -> bool {
+        match (&self.spelling_loc, &self.expansion_loc) {
+            (Some(spelling), Some(expansion)) => !same_position(spelling, expansion),
+            _ => false,
+        }
+    }
+
+    /// Whether this location is inside an argument of a macro invocation, as
+    /// opposed to in the body of the macro's definition.
+    pub fn is_macro_arg(&self) -> bool {
+        self.expansion_loc
+            .as_ref()
+            .map_or(false, |expansion| expansion.is_macro_arg_expansion)
+    }
+
+    /// The location in the file that the user wrote which this location
+    /// corresponds to, like Clang's `SourceManager::getFileLoc`.
+    ///
+    /// For a location in a macro argument this is where the argument was
+    /// spelled. For a location in a macro's definition this is where the
+    /// macro was invoked. For a location not involving macros this is just
+    /// the location.
+    pub fn file_loc(&self) -> Option<&BareSourceLocation> {
+        if self.is_macro_arg() {
+            self.spelling_loc.as_ref().or(self.expansion_loc.as_ref())
+        } else {
+            self.expansion_loc.as_ref().or(self.spelling_loc.as_ref())
+        }
+    }
+
+    /// Whether both locations are inside the same invocation of a macro.
+    pub fn same_expansion(&self, other: &SourceLocation) -> bool {
+        match (&self.expansion_loc, &other.expansion_loc) {
+            (Some(expansion), Some(other_expansion)) => {
+                self.is_from_macro()
+                    && other.is_from_macro()
+                    && same_position(expansion, other_expansion)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl SourceRange {
+    /// The range of source text in a single file which this range corresponds
+    /// to, similar to Clang's `Lexer::makeFileCharRange`.
+    ///
+    /// Each end of the range is mapped to its [file
+    /// location](SourceLocation::file_loc), so a range which begins or ends
+    /// inside a macro's definition is widened to cover the macro invocation.
+    /// Returns `None` if the range is empty or if the two ends do not map into
+    /// the same file in order, such as for a range which begins inside a macro
+    /// argument and ends outside the invocation.
+    ///
+    /// Unlike the rest of Clang's source ranges but like `makeFileCharRange`,
+    /// the result never involves macros. The end is still the start of the
+    /// last token, whose length is `tok_len`. The dump only records the
+    /// length of a macro invocation's name, so for a range ending in an
+    /// invocation of a function-like macro, the end is the macro's name
+    /// rather than the closing parenthesis of its arguments.
+    pub fn file_range(&self) -> Option<SourceRange> {
+        let (begin, end) = self.file_locs()?;
+        Some(SourceRange {
+            begin: file_location(begin),
+            end: file_location(end),
+        })
+    }
+
+    // The file locations of the beginning and end, without cloning them.
+    pub(crate) fn file_locs(&self) -> Option<(&BareSourceLocation, &BareSourceLocation)> {
+        let begin = self.begin.file_loc()?;
+        let end = self.end.file_loc()?;
+        if begin.file != end.file || begin.offset > end.offset {
+            return None;
+        }
+        Some((begin, end))
+    }
+}
+
+fn same_position(a: &BareSourceLocation, b: &BareSourceLocation) -> bool {
+    a.offset == b.offset && a.file == b.file
+}
+
+pub(crate) fn file_location(loc: &BareSourceLocation) -> SourceLocation {
+    let loc = BareSourceLocation {
+        is_macro_arg_expansion: false,
+        ..loc.clone()
+    };
+    SourceLocation {
+        spelling_loc: Some(loc.clone()),
+        expansion_loc: Some(loc),
+    }
+}
@@ -0,0 +1,58 @@
+use std::cell::Cell;
+
+thread_local! {
+    static FAITHFUL: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Runs `f` with every syntax tree serialized on the current thread written
+/// exactly the way Clang prints it.
+///
+/// By default, every serialized node has an `"id"`, including the ones whose
+/// id is [`Id::NULL`] because none was present in the input, such as the
+/// placeholder `{}` which Clang prints in place of an absent child. Those are
+/// written as `"0x0"`. Within `faithful`, a null id is omitted as Clang omits
+/// it, so that re-serializing a dump deserialized into a model which keeps
+/// every field, like `serde_json::Map`, reproduces the original.
+///
+/// Source locations are likewise written the way Clang writes them within
+/// `faithful`. A location in the main file whose spelling and expansion are
+/// the same is written once rather than as a separate `"spellingLoc"` and
+/// `"expansionLoc"`, and a line is omitted only when it is the same as the
+/// previous location's line in the same file.
+///
+/// [`Id::NULL`]: crate::Id::NULL
+///
+/// ```no_run
+/// # use serde_derive::{Deserialize, Serialize};
+/// #
+/// # #[derive(Deserialize, Serialize)]
+/// # struct Clang;
+/// #
+/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let json = std::fs::read("ast.json")?;
+///     let node: clang_ast::Node<Clang> = clang_ast::from_slice(&json)?;
+///     let json = clang_ast::faithful(|| serde_json::to_vec(&node))?;
+///     # let _ = json;
+///     Ok(())
+/// }
+/// ```
+pub fn faithful<R>(f: impl FnOnce() -> R) -> R {
+    struct Restore {
+        faithful: bool,
+    }
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            FAITHFUL.with(|faithful| faithful.set(self.faithful));
+        }
+    }
+
+    let _restore = Restore {
+        faithful: FAITHFUL.with(|faithful| faithful.replace(true)),
+    };
+    f()
+}
+
+pub(crate) fn enabled() -> bool {
+    FAITHFUL.with(Cell::get)
+}
@@ -0,0 +1,246 @@
+use crate::{Id, Node};
+use foldhash::HashMap;
+use std::collections::VecDeque;
+use std::fmt::{self, Display};
+
+/// Renders the shape of a syntax tree as a graph, for viewing with Graphviz or
+/// Mermaid.
+///
+/// Every node becomes a box with a caller-provided label, connected to its
+/// parent by an arrow. Optionally, references from one node to another by
+/// [`Id`], such as from a `DeclRefExpr` to the declaration it refers to, are
+/// drawn as dashed arrows.
+///
+/// Nodes are included breadth-first, so when the graph is cut off by
+/// [`max_depth`][Graph::max_depth] or [`max_nodes`][Graph::max_nodes], what
+/// remains are the nodes nearest the root. Wherever some of a node's children
+/// are left out, the graph says how many.
+///
+/// ```
+/// # use clang_ast::{HasKind, Kind, Node};
+/// #
+/// # struct Clang;
+/// #
+/// # impl HasKind for Clang {
+/// #     fn kind(&self) -> Kind {
+/// #         Kind::null
+/// #     }
+/// # }
+/// #
+/// use clang_ast::Graph;
+///
+/// fn write_dot(node: &Node<Clang>) -> std::io::Result<()> {
+///     let graph = Graph::new(node, |node| node.kind.kind().to_string()).max_depth(4);
+///     std::fs::write("ast.dot", graph.to_string())
+/// }
+/// ```
+pub struct Graph<'a, T> {
+    node: &'a Node<T>,
+    label: Label<'a, T>,
+    references: Option<References<'a, T>>,
+    format: GraphFormat,
+    max_depth: usize,
+    max_nodes: usize,
+}
+
+type Label<'a, T> = Box<dyn Fn(&Node<T>) -> String + 'a>;
+type References<'a, T> = Box<dyn Fn(&Node<T>) -> Vec<Id> + 'a>;
+
+/// Output language of a [`Graph`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum GraphFormat {
+    /// Graphviz DOT, as in `dot -Tsvg ast.dot > ast.svg`.
+    Dot,
+    /// Mermaid flowchart, which renders inline in Markdown on GitHub and
+    /// elsewhere.
+    Mermaid,
+}
+
+impl<'a, T> Graph<'a, T> {
+    /// Graph of the tree rooted at `node`, labeling each node with the result
+    /// of `label`. Labels may contain newlines.
+    pub fn new(node: &'a Node<T>, label: impl Fn(&Node<T>) -> String + 'a) -> Self {
+        Graph {
+            node,
+            label: Box::new(label),
+            references: None,
+            format: GraphFormat::Dot,
+            max_depth: usize::MAX,
+            max_nodes: usize::MAX,
+        }
+    }
+
+    /// Draws a dashed arrow from each node to each of the nodes whose ids are
+    /// returned by `references`, if those nodes are in the graph.
+    #[must_use]
+    pub fn references(mut self, references: impl Fn(&Node<T>) -> Vec<Id> + 'a) -> Self {
+        self.references = Some(Box::new(references));
+        self
+    }
+
+    /// Output language. The default is DOT.
+    #[must_use]
+    pub fn format(mut self, format: GraphFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Leaves out nodes nested more than this many levels below the root.
+    #[must_use]
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Leaves out nodes beyond the first this many.
+    #[must_use]
+    pub fn max_nodes(mut self, max_nodes: usize) -> Self {
+        self.max_nodes = max_nodes;
+        self
+    }
+}
+
+struct Vertex<'a, T> {
+    node: &'a Node<T>,
+    parent: Option<usize>,
+    // How many of the node's children are left out of the graph.
+    omitted: usize,
+}
+
+impl<'a, T> Display for Graph<'a, T> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let mut vertices = Vec::<Vertex<T>>::new();
+        let mut queue = VecDeque::from([(self.node, None::<usize>, 0)]);
+        while let Some((node, parent, depth)) = queue.pop_front() {
+            if vertices.len() == self.max_nodes {
+                if let Some(parent) = parent {
+                    vertices[parent].omitted += 1;
+                }
+                continue;
+            }
+            let index = vertices.len();
+            let mut omitted = 0;
+            if depth == self.max_depth {
+                omitted = node.inner.len();
+            } else {
+                for child in &node.inner {
+                    queue.push_back((child, Some(index), depth + 1));
+                }
+            }
+            vertices.push(Vertex {
+                node,
+                parent,
+                omitted,
+            });
+        }
+
+        match self.format {
+            GraphFormat::Dot => formatter.write_str("digraph {\n  node [shape=box];\n")?,
+            GraphFormat::Mermaid => formatter.write_str("flowchart TD\n")?,
+        }
+        for (index, vertex) in vertices.iter().enumerate() {
+            let label = (self.label)(vertex.node);
+            match self.format {
+                GraphFormat::Dot => {
+                    writeln!(formatter, "  n{} [label=\"{}\"];", index, DotEscape(&label))?;
+                }
+                GraphFormat::Mermaid => {
+                    writeln!(formatter, "  n{}[\"{}\"]", index, MermaidEscape(&label))?;
+                }
+            }
+            if let Some(parent) = vertex.parent {
+                match self.format {
+                    GraphFormat::Dot => writeln!(formatter, "  n{} -> n{};", parent, index)?,
+                    GraphFormat::Mermaid => writeln!(formatter, "  n{} --> n{}", parent, index)?,
+                }
+            }
+        }
+        for (index, vertex) in vertices.iter().enumerate() {
+            if vertex.omitted == 0 {
+                continue;
+            }
+            let n = vertex.omitted;
+            match self.format {
+                GraphFormat::Dot => writeln!(
+                    formatter,
+                    "  m{} [label=\"{} more\", shape=plaintext];\n  n{} -> m{} [style=dotted];",
+                    index, n, index, index,
+                )?,
+                GraphFormat::Mermaid => writeln!(
+                    formatter,
+                    "  m{}([\"{} more\"])\n  n{} -.- m{}",
+                    index, n, index, index,
+                )?,
+            }
+        }
+
+        if let Some(references) = &self.references {
+            let mut index_of = HashMap::default();
+            for (index, vertex) in vertices.iter().enumerate() {
+                if vertex.node.id != Id::NULL {
+                    index_of.insert(vertex.node.id, index);
+                }
+            }
+            for (index, vertex) in vertices.iter().enumerate() {
+                for id in references(vertex.node) {
+                    let Some(target) = index_of.get(&id) else {
+                        continue;
+                    };
+                    match self.format {
+                        // Leave references out of the layout of the tree.
+                        GraphFormat::Dot => writeln!(
+                            formatter,
+                            "  n{} -> n{} [style=dashed, constraint=false];",
+                            index, target,
+                        )?,
+                        GraphFormat::Mermaid => {
+                            writeln!(formatter, "  n{} -.-> n{}", index, target)?;
+                        }
+                    }
+                }
+            }
+        }
+
+        match self.format {
+            GraphFormat::Dot => formatter.write_str("}\n"),
+            GraphFormat::Mermaid => Ok(()),
+        }
+    }
+}
+
+// Label as the content of a DOT string literal.
+struct DotEscape<'a>(&'a str);
+
+impl<'a> Display for DotEscape<'a> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        for ch in self.0.chars() {
+            match ch {
+                '"' => formatter.write_str("\\\"")?,
+                '\\' => formatter.write_str("\\\\")?,
+                '\n' => formatter.write_str("\\n")?,
+                _ => fmt::Write::write_char(formatter, ch)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+// Label as the content of a quoted Mermaid node text, in which HTML is
+// interpreted and `#…;` is an entity code.
+struct MermaidEscape<'a>(&'a str);
+
+impl<'a> Display for MermaidEscape<'a> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        for ch in self.0.chars() {
+            match ch {
+                '"' => formatter.write_str("#quot;")?,
+                '#' => formatter.write_str("#35;")?,
+                '<' => formatter.write_str("#lt;")?,
+                '>' => formatter.write_str("#gt;")?,
+                '\n' => formatter.write_str("<br>")?,
+                _ => fmt::Write::write_char(formatter, ch)?,
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,388 @@
+use crate::Kind;
+
+/// Broad grouping of [`Kind`]s, according to which of Clang's AST class
+/// hierarchies the kind belongs to.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[non_exhaustive]
+pub enum Category {
+    /// Subclasses of `clang::Decl`.
+    Decl,
+    /// Subclasses of `clang::Stmt`, other than expressions.
+    Stmt,
+    /// Subclasses of `clang::Expr`.
+    Expr,
+    /// Subclasses of `clang::Type`.
+    Type,
+    /// Subclasses of `clang::Attr`.
+    Attr,
+    /// Subclasses of `clang::comments::Comment`.
+    Comment,
+    /// Everything else, such as `CXXCtorInitializer` and `TemplateArgument`.
+    Other,
+}
+
+impl Kind {
+    /// The base class of this kind in Clang's AST class hierarchy, as given by
+    /// DeclNodes.td, StmtNodes.td, TypeNodes.td, CommentNodes.td and Attr.td.
+    ///
+    /// Returns `None` for the roots of those hierarchies (`Decl`, `Stmt`,
+    /// `Type`, `Attr`, `Comment`) and for kinds which are not part of any of
+    /// them.
+    ///
+    /// ```
+    /// # use clang_ast::Kind;
+    /// #
+    /// assert_eq!(Kind::CXXMethodDecl.parent(), Some(Kind::FunctionDecl));
+    /// assert_eq!(Kind::FunctionDecl.parent(), Some(Kind::DeclaratorDecl));
+    /// assert_eq!(Kind::Decl.parent(), None);
+    /// ```
+    #[allow(clippy::too_many_lines)]
+    pub fn parent(&self) -> Option<Kind> {
+        match self {
+            Kind::Attr
+            | Kind::CXXCtorInitializer
+            | Kind::Comment
+            | Kind::CompoundRequirement
+            | Kind::Decl
+            | Kind::NestedRequirement
+            | Kind::QualType
+            | Kind::SimpleRequirement
+            | Kind::Stmt
+            | Kind::TemplateArgument
+            | Kind::Type
+            | Kind::TypeRequirement
+            | Kind::null => None,
+            Kind::AccessSpecDecl
+            | Kind::ClassScopeFunctionSpecializationDecl
+            | Kind::EmptyDecl
+            | Kind::FileScopeAsmDecl
+            | Kind::FriendDecl
+            | Kind::ImplicitConceptSpecializationDecl
+            | Kind::LinkageSpecDecl
+            | Kind::NamedDecl
+            | Kind::PragmaCommentDecl
+            | Kind::PragmaDetectMismatchDecl
+            | Kind::StaticAssertDecl
+            | Kind::TranslationUnitDecl => Some(Kind::Decl),
+            Kind::BaseUsingDecl
+            | Kind::NamespaceAliasDecl
+            | Kind::NamespaceDecl
+            | Kind::TemplateDecl
+            | Kind::TypeDecl
+            | Kind::UnresolvedUsingIfExistsDecl
+            | Kind::UsingDirectiveDecl
+            | Kind::UsingShadowDecl
+            | Kind::ValueDecl => Some(Kind::NamedDecl),
+            Kind::UsingDecl | Kind::UsingEnumDecl => Some(Kind::BaseUsingDecl),
+            Kind::ConstructorUsingShadowDecl => Some(Kind::UsingShadowDecl),
+            Kind::BuiltinTemplateDecl
+            | Kind::ConceptDecl
+            | Kind::RedeclarableTemplateDecl
+            | Kind::TemplateTemplateParmDecl => Some(Kind::TemplateDecl),
+            Kind::ClassTemplateDecl
+            | Kind::FunctionTemplateDecl
+            | Kind::TypeAliasTemplateDecl
+            | Kind::VarTemplateDecl => Some(Kind::RedeclarableTemplateDecl),
+            Kind::TagDecl
+            | Kind::TemplateTypeParmDecl
+            | Kind::TypedefNameDecl
+            | Kind::UnresolvedUsingTypenameDecl => Some(Kind::TypeDecl),
+            Kind::TypeAliasDecl | Kind::TypedefDecl => Some(Kind::TypedefNameDecl),
+            Kind::EnumDecl | Kind::RecordDecl => Some(Kind::TagDecl),
+            Kind::CXXRecordDecl => Some(Kind::RecordDecl),
+            Kind::ClassTemplateSpecializationDecl => Some(Kind::CXXRecordDecl),
+            Kind::ClassTemplatePartialSpecializationDecl => {
+                Some(Kind::ClassTemplateSpecializationDecl)
+            }
+            Kind::BindingDecl
+            | Kind::DeclaratorDecl
+            | Kind::EnumConstantDecl
+            | Kind::IndirectFieldDecl
+            | Kind::UnresolvedUsingValueDecl => Some(Kind::ValueDecl),
+            Kind::FieldDecl
+            | Kind::FunctionDecl
+            | Kind::NonTypeTemplateParmDecl
+            | Kind::VarDecl => Some(Kind::DeclaratorDecl),
+            Kind::CXXDeductionGuideDecl | Kind::CXXMethodDecl => Some(Kind::FunctionDecl),
+            Kind::CXXConstructorDecl | Kind::CXXConversionDecl | Kind::CXXDestructorDecl => {
+                Some(Kind::CXXMethodDecl)
+            }
+            Kind::DecompositionDecl | Kind::ParmVarDecl | Kind::VarTemplateSpecializationDecl => {
+                Some(Kind::VarDecl)
+            }
+            Kind::VarTemplatePartialSpecializationDecl => Some(Kind::VarTemplateSpecializationDecl),
+            Kind::AsmStmt
+            | Kind::BreakStmt
+            | Kind::CXXCatchStmt
+            | Kind::CXXForRangeStmt
+            | Kind::CXXTryStmt
+            | Kind::CompoundStmt
+            | Kind::ContinueStmt
+            | Kind::DeclStmt
+            | Kind::DoStmt
+            | Kind::ForStmt
+            | Kind::GotoStmt
+            | Kind::IfStmt
+            | Kind::IndirectGotoStmt
+            | Kind::NullStmt
+            | Kind::ReturnStmt
+            | Kind::SwitchCase
+            | Kind::SwitchStmt
+            | Kind::ValueStmt
+            | Kind::WhileStmt => Some(Kind::Stmt),
+            Kind::GCCAsmStmt => Some(Kind::AsmStmt),
+            Kind::CaseStmt | Kind::DefaultStmt => Some(Kind::SwitchCase),
+            Kind::AttributedStmt | Kind::Expr | Kind::LabelStmt => Some(Kind::ValueStmt),
+            Kind::AbstractConditionalOperator
+            | Kind::ArrayInitIndexExpr
+            | Kind::ArrayInitLoopExpr
+            | Kind::ArraySubscriptExpr
+            | Kind::ArrayTypeTraitExpr
+            | Kind::AtomicExpr
+            | Kind::BinaryOperator
+            | Kind::CXXBindTemporaryExpr
+            | Kind::CXXBoolLiteralExpr
+            | Kind::CXXConstructExpr
+            | Kind::CXXDefaultArgExpr
+            | Kind::CXXDefaultInitExpr
+            | Kind::CXXDeleteExpr
+            | Kind::CXXDependentScopeMemberExpr
+            | Kind::CXXFoldExpr
+            | Kind::CXXInheritedCtorInitExpr
+            | Kind::CXXNewExpr
+            | Kind::CXXNoexceptExpr
+            | Kind::CXXNullPtrLiteralExpr
+            | Kind::CXXPseudoDestructorExpr
+            | Kind::CXXRewrittenBinaryOperator
+            | Kind::CXXScalarValueInitExpr
+            | Kind::CXXThisExpr
+            | Kind::CXXThrowExpr
+            | Kind::CXXTypeidExpr
+            | Kind::CXXUnresolvedConstructExpr
+            | Kind::CallExpr
+            | Kind::CastExpr
+            | Kind::CharacterLiteral
+            | Kind::CompoundLiteralExpr
+            | Kind::ConceptSpecializationExpr
+            | Kind::ConvertVectorExpr
+            | Kind::DeclRefExpr
+            | Kind::DependentScopeDeclRefExpr
+            | Kind::DesignatedInitExpr
+            | Kind::FloatingLiteral
+            | Kind::FullExpr
+            | Kind::GNUNullExpr
+            | Kind::ImplicitValueInitExpr
+            | Kind::InitListExpr
+            | Kind::IntegerLiteral
+            | Kind::LambdaExpr
+            | Kind::MaterializeTemporaryExpr
+            | Kind::MemberExpr
+            | Kind::OffsetOfExpr
+            | Kind::OpaqueValueExpr
+            | Kind::OverloadExpr
+            | Kind::PackExpansionExpr
+            | Kind::ParenExpr
+            | Kind::ParenListExpr
+            | Kind::PredefinedExpr
+            | Kind::RecoveryExpr
+            | Kind::RequiresExpr
+            | Kind::ShuffleVectorExpr
+            | Kind::SizeOfPackExpr
+            | Kind::SourceLocExpr
+            | Kind::StmtExpr
+            | Kind::StringLiteral
+            | Kind::SubstNonTypeTemplateParmExpr
+            | Kind::TypeTraitExpr
+            | Kind::UnaryExprOrTypeTraitExpr
+            | Kind::UnaryOperator => Some(Kind::Expr),
+            Kind::BinaryConditionalOperator | Kind::ConditionalOperator => {
+                Some(Kind::AbstractConditionalOperator)
+            }
+            Kind::CompoundAssignOperator => Some(Kind::BinaryOperator),
+            Kind::CXXTemporaryObjectExpr => Some(Kind::CXXConstructExpr),
+            Kind::CXXMemberCallExpr | Kind::CXXOperatorCallExpr | Kind::UserDefinedLiteral => {
+                Some(Kind::CallExpr)
+            }
+            Kind::ExplicitCastExpr | Kind::ImplicitCastExpr => Some(Kind::CastExpr),
+            Kind::BuiltinBitCastExpr
+            | Kind::CStyleCastExpr
+            | Kind::CXXFunctionalCastExpr
+            | Kind::CXXNamedCastExpr => Some(Kind::ExplicitCastExpr),
+            Kind::CXXConstCastExpr
+            | Kind::CXXDynamicCastExpr
+            | Kind::CXXReinterpretCastExpr
+            | Kind::CXXStaticCastExpr => Some(Kind::CXXNamedCastExpr),
+            Kind::ConstantExpr | Kind::ExprWithCleanups => Some(Kind::FullExpr),
+            Kind::UnresolvedLookupExpr | Kind::UnresolvedMemberExpr => Some(Kind::OverloadExpr),
+            Kind::AdjustedType
+            | Kind::ArrayType
+            | Kind::AtomicType
+            | Kind::AttributedType
+            | Kind::BlockPointerType
+            | Kind::BuiltinType
+            | Kind::ComplexType
+            | Kind::DecltypeType
+            | Kind::DeducedType
+            | Kind::FunctionType
+            | Kind::InjectedClassNameType
+            | Kind::MemberPointerType
+            | Kind::PackExpansionType
+            | Kind::ParenType
+            | Kind::PointerType
+            | Kind::ReferenceType
+            | Kind::SubstTemplateTypeParmPackType
+            | Kind::SubstTemplateTypeParmType
+            | Kind::TagType
+            | Kind::TemplateSpecializationType
+            | Kind::TemplateTypeParmType
+            | Kind::TypeOfExprType
+            | Kind::TypeWithKeyword
+            | Kind::TypedefType
+            | Kind::UnaryTransformType
+            | Kind::UnresolvedUsingType
+            | Kind::UsingType
+            | Kind::VectorType => Some(Kind::Type),
+            Kind::DecayedType => Some(Kind::AdjustedType),
+            Kind::ConstantArrayType | Kind::DependentSizedArrayType | Kind::IncompleteArrayType => {
+                Some(Kind::ArrayType)
+            }
+            Kind::AutoType => Some(Kind::DeducedType),
+            Kind::FunctionProtoType => Some(Kind::FunctionType),
+            Kind::LValueReferenceType | Kind::RValueReferenceType => Some(Kind::ReferenceType),
+            Kind::EnumType | Kind::RecordType => Some(Kind::TagType),
+            Kind::DependentNameType
+            | Kind::DependentTemplateSpecializationType
+            | Kind::ElaboratedType => Some(Kind::TypeWithKeyword),
+            Kind::BlockContentComment
+            | Kind::FullComment
+            | Kind::InlineContentComment
+            | Kind::VerbatimBlockLineComment => Some(Kind::Comment),
+            Kind::BlockCommandComment | Kind::ParagraphComment => Some(Kind::BlockContentComment),
+            Kind::ParamCommandComment
+            | Kind::TParamCommandComment
+            | Kind::VerbatimBlockComment
+            | Kind::VerbatimLineComment => Some(Kind::BlockCommandComment),
+            Kind::HTMLTagComment | Kind::InlineCommandComment | Kind::TextComment => {
+                Some(Kind::InlineContentComment)
+            }
+            Kind::HTMLEndTagComment | Kind::HTMLStartTagComment => Some(Kind::HTMLTagComment),
+            Kind::AbiTagAttr
+            | Kind::AliasAttr
+            | Kind::AlignValueAttr
+            | Kind::InheritableAttr
+            | Kind::ModeAttr
+            | Kind::NoEscapeAttr
+            | Kind::StmtAttr => Some(Kind::Attr),
+            Kind::AlignedAttr
+            | Kind::AllocAlignAttr
+            | Kind::AllocSizeAttr
+            | Kind::AsmLabelAttr
+            | Kind::AvailabilityAttr
+            | Kind::AvailableOnlyInDefaultEvalMethodAttr
+            | Kind::BuiltinAttr
+            | Kind::CXX11NoReturnAttr
+            | Kind::CallbackAttr
+            | Kind::ColdAttr
+            | Kind::ConstAttr
+            | Kind::DLLImportAttr
+            | Kind::DeclOrStmtAttr
+            | Kind::DeclOrTypeAttr
+            | Kind::DeprecatedAttr
+            | Kind::DiagnoseIfAttr
+            | Kind::DisableTailCallsAttr
+            | Kind::EnableIfAttr
+            | Kind::ExcludeFromExplicitInstantiationAttr
+            | Kind::FinalAttr
+            | Kind::FormatArgAttr
+            | Kind::FormatAttr
+            | Kind::GNUInlineAttr
+            | Kind::InheritableParamAttr
+            | Kind::InternalLinkageAttr
+            | Kind::MSAllocatorAttr
+            | Kind::MSConstexprAttr
+            | Kind::MSNoVTableAttr
+            | Kind::MSVtorDispAttr
+            | Kind::MaxFieldAlignmentAttr
+            | Kind::MayAliasAttr
+            | Kind::MinVectorWidthAttr
+            | Kind::NoAliasAttr
+            | Kind::NoDebugAttr
+            | Kind::NoSanitizeAttr
+            | Kind::NoThrowAttr
+            | Kind::NoUniqueAddressAttr
+            | Kind::OverrideAttr
+            | Kind::OwnerAttr
+            | Kind::PackedAttr
+            | Kind::PointerAttr
+            | Kind::PreferredNameAttr
+            | Kind::PureAttr
+            | Kind::RestrictAttr
+            | Kind::ReturnsNonNullAttr
+            | Kind::ReturnsTwiceAttr
+            | Kind::SelectAnyAttr
+            | Kind::StandaloneDebugAttr
+            | Kind::SwiftAttrAttr
+            | Kind::TargetAttr
+            | Kind::TypeNullableAttr
+            | Kind::TypeVisibilityAttr
+            | Kind::UnavailableAttr
+            | Kind::UnusedAttr
+            | Kind::UsingIfExistsAttr
+            | Kind::VTablePointerAuthenticationAttr
+            | Kind::VisibilityAttr
+            | Kind::WarnUnusedResultAttr
+            | Kind::WeakAttr
+            | Kind::WeakImportAttr
+            | Kind::WeakRefAttr => Some(Kind::InheritableAttr),
+            Kind::AlwaysInlineAttr | Kind::NoInlineAttr => Some(Kind::DeclOrStmtAttr),
+            Kind::LifetimeBoundAttr => Some(Kind::DeclOrTypeAttr),
+            Kind::NonNullAttr => Some(Kind::InheritableParamAttr),
+            Kind::FallThroughAttr | Kind::LikelyAttr | Kind::UnlikelyAttr => Some(Kind::StmtAttr),
+        }
+    }
+
+    /// Whether this kind is `ancestor` or a subclass of it.
+    ///
+    /// Abstract classes like `Expr`, `TagDecl` or `CastExpr` never appear as
+    /// the kind of a node in a dump, but are useful as the argument here.
+    ///
+    /// ```
+    /// # use clang_ast::Kind;
+    /// #
+    /// assert!(Kind::CXXRecordDecl.is_a(Kind::TagDecl));
+    /// assert!(Kind::ImplicitCastExpr.is_a(Kind::Expr));
+    /// assert!(!Kind::ImplicitCastExpr.is_a(Kind::ExplicitCastExpr));
+    /// ```
+    pub fn is_a(&self, ancestor: Kind) -> bool {
+        let mut kind = *self;
+        loop {
+            if kind == ancestor {
+                return true;
+            }
+            match kind.parent() {
+                Some(parent) => kind = parent,
+                None => return false,
+            }
+        }
+    }
+
+    /// Which of Clang's AST class hierarchies this kind belongs to.
+    pub fn category(&self) -> Category {
+        let mut kind = *self;
+        loop {
+            match kind {
+                Kind::Decl => return Category::Decl,
+                Kind::Stmt => return Category::Stmt,
+                Kind::Expr => return Category::Expr,
+                Kind::Type => return Category::Type,
+                Kind::Attr => return Category::Attr,
+                Kind::Comment => return Category::Comment,
+                _ => {}
+            }
+            match kind.parent() {
+                Some(parent) => kind = parent,
+                None => return Category::Other,
+            }
+        }
+    }
+}
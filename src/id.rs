@@ -9,6 +9,14 @@ pub struct Id {
 
 impl Id {
     pub const NULL: Id = Id { id: 0 };
+
+    pub(crate) const fn from_u64(id: u64) -> Self {
+        Id { id }
+    }
+
+    pub(crate) const fn as_u64(self) -> u64 {
+        self.id
+    }
 }
 
 impl Display for Id {
@@ -0,0 +1,150 @@
+use crate::loc::IncludedFrom;
+use foldhash::HashMap;
+use std::cell::RefCell;
+use std::sync::Arc;
+
+thread_local! {
+    static COLLECTING: RefCell<Option<IncludeGraph>> = const { RefCell::new(None) };
+}
+
+/// Files of a translation unit and which files include them, as observed in
+/// the source locations of an AST dump.
+///
+/// The graph is collected while deserializing, from every location that the
+/// deserializer passes through, so it reflects exactly the locations which
+/// the caller's kind type `T` deserializes. Clang prints the immediate
+/// includer of a file on every location in that file.
+///
+/// ```no_run
+/// # use serde_derive::Deserialize;
+/// #
+/// # #[derive(Deserialize)]
+/// # enum Clang {
+/// #     Other,
+/// # }
+/// #
+/// fn main() -> Result<(), clang_ast::Error> {
+///     let json = std::fs::read("ast.json").unwrap();
+///     let (node, includes) =
+///         clang_ast::IncludeGraph::collect(|| clang_ast::from_slice::<Clang>(&json));
+///     let node = node?;
+///     for file in includes.files() {
+///         for includer in &file.includers {
+///             println!("{} -> {}", includer, file.file);
+///         }
+///     }
+///     # let _ = node;
+///     Ok(())
+/// }
+/// ```
+#[derive(Default, Clone, Debug)]
+pub struct IncludeGraph {
+    files: Vec<SourceFile>,
+    index: HashMap<Arc<str>, usize>,
+}
+
+/// One file in an [`IncludeGraph`].
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct SourceFile {
+    pub file: Arc<str>,
+    /// Files containing an `#include` of this file, in the order in which
+    /// they were first seen. Empty for the main file.
+    pub includers: Vec<Arc<str>>,
+    /// Offset within this file of the first location in it seen in the dump,
+    /// or `None` for a file which was only ever seen as the includer of
+    /// another file.
+    pub first_offset: Option<usize>,
+}
+
+impl IncludeGraph {
+    /// Runs `f`, which is expected to deserialize an AST dump on the current
+    /// thread, and returns its result together with the include graph of all
+    /// the source locations deserialized during it.
+    pub fn collect<R>(f: impl FnOnce() -> R) -> (R, IncludeGraph) {
+        struct Restore {
+            outer: Option<IncludeGraph>,
+        }
+
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                let outer = self.outer.take();
+                COLLECTING.with(|collecting| *collecting.borrow_mut() = outer);
+            }
+        }
+
+        let restore = Restore {
+            outer: COLLECTING.with(|collecting| collecting.replace(Some(IncludeGraph::default()))),
+        };
+        let result = f();
+        let graph = COLLECTING.with(|collecting| collecting.borrow_mut().take());
+        drop(restore);
+        (result, graph.unwrap_or_default())
+    }
+
+    /// Number of distinct files.
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Whether no file was seen.
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Every file seen, in the order in which they were first seen.
+    pub fn files(&self) -> &[SourceFile] {
+        &self.files
+    }
+
+    pub fn get(&self, file: &str) -> Option<&SourceFile> {
+        let i = *self.index.get(file)?;
+        Some(&self.files[i])
+    }
+
+    /// Files which are included directly by `file`.
+    pub fn includes<'a>(&'a self, file: &'a str) -> impl Iterator<Item = &'a SourceFile> + 'a {
+        self.files.iter().filter(move |source_file| {
+            source_file
+                .includers
+                .iter()
+                .any(|includer| **includer == *file)
+        })
+    }
+
+    fn record(&mut self, file: &Arc<str>, offset: usize, included_from: Option<&IncludedFrom>) {
+        let i = self.entry(file);
+        let source_file = &mut self.files[i];
+        source_file.first_offset.get_or_insert(offset);
+        if let Some(included_from) = included_from {
+            if !source_file.includers.contains(&included_from.file) {
+                source_file.includers.push(Arc::clone(&included_from.file));
+            }
+            self.entry(&included_from.file);
+        }
+    }
+
+    fn entry(&mut self, file: &Arc<str>) -> usize {
+        if let Some(i) = self.index.get(file) {
+            return *i;
+        }
+        let i = self.files.len();
+        self.files.push(SourceFile {
+            file: Arc::clone(file),
+            includers: Vec::new(),
+            first_offset: None,
+        });
+        self.index.insert(Arc::clone(file), i);
+        i
+    }
+}
+
+pub(crate) fn record(file: &Arc<str>, offset: usize, included_from: Option<&IncludedFrom>) {
+    if file.is_empty() {
+        return;
+    }
+    COLLECTING.with(|collecting| {
+        if let Some(graph) = &mut *collecting.borrow_mut() {
+            graph.record(file, offset, included_from);
+        }
+    });
+}
@@ -0,0 +1,178 @@
+use crate::{Cursor, Flow, Id, Node, Visit};
+use foldhash::HashMap;
+use std::collections::hash_map::Entry;
+use std::ops::Index;
+
+/// Lookup table from [`Id`] to the node with that id.
+///
+/// Clang refers to declarations elsewhere in the tree by id, in fields such as
+/// `referencedMemberDecl`, `previousDecl` and `parentDeclContextId`. An
+/// `IdIndex` built once from the root of the tree resolves those ids back to
+/// nodes, and also knows every node's parent and its path from the root.
+///
+/// ```
+/// # use clang_ast::{Id, IdIndex, Node};
+/// # use serde_derive::Deserialize;
+/// #
+/// #[derive(Deserialize)]
+/// enum Clang {
+///     CXXMethodDecl {
+///         #[serde(rename = "previousDecl")]
+///         previous_decl: Option<Id>,
+///     },
+///     Other,
+/// }
+///
+/// fn previous_decl<'ast>(
+///     index: &IdIndex<'ast, Clang>,
+///     node: &Node<Clang>,
+/// ) -> Option<&'ast Node<Clang>> {
+///     match node.kind {
+///         Clang::CXXMethodDecl { previous_decl } => index.resolve(previous_decl),
+///         Clang::Other => None,
+///     }
+/// }
+/// ```
+///
+/// Nodes whose id is null are not indexed, but still appear in the paths of
+/// their descendants. If several nodes have the same id, the first one in
+/// depth-first order is the one found by lookups.
+pub struct IdIndex<'ast, T> {
+    nodes: Vec<IndexedNode<'ast, T>>,
+    map: HashMap<Id, usize>,
+}
+
+struct IndexedNode<'ast, T> {
+    node: &'ast Node<T>,
+    parent: Option<usize>,
+    child_index: usize,
+}
+
+impl<'ast, T> IdIndex<'ast, T> {
+    /// Indexes `root` and all of its descendants.
+    pub fn new(root: &'ast Node<T>) -> Self {
+        let mut builder = Builder {
+            index: IdIndex {
+                nodes: Vec::new(),
+                map: HashMap::default(),
+            },
+            children: Vec::new(),
+        };
+        root.visit(&mut builder);
+        builder.index
+    }
+
+    /// Number of distinct ids in the index.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Whether the tree contains no node with a non-null id.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Whether some node in the tree has the given id.
+    pub fn contains(&self, id: Id) -> bool {
+        self.map.contains_key(&id)
+    }
+
+    /// The node with the given id.
+    pub fn get(&self, id: Id) -> Option<&'ast Node<T>> {
+        let i = *self.map.get(&id)?;
+        Some(self.nodes[i].node)
+    }
+
+    /// The node with the given id, for optional references such as
+    /// `previousDecl` which are absent on most nodes.
+    pub fn resolve(&self, id: Option<Id>) -> Option<&'ast Node<T>> {
+        self.get(id?)
+    }
+
+    /// The node whose `inner` contains the node with the given id.
+    ///
+    /// Returns `None` if the id is not in the tree, or refers to the root.
+    pub fn parent(&self, id: Id) -> Option<&'ast Node<T>> {
+        let i = *self.map.get(&id)?;
+        let parent = self.nodes[i].parent?;
+        Some(self.nodes[parent].node)
+    }
+
+    /// The nodes from the root down to and including the node with the given
+    /// id.
+    pub fn path(&self, id: Id) -> Option<Vec<&'ast Node<T>>> {
+        let mut i = *self.map.get(&id)?;
+        let mut path = vec![self.nodes[i].node];
+        while let Some(parent) = self.nodes[i].parent {
+            path.push(self.nodes[parent].node);
+            i = parent;
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    /// Cursor positioned at the node with the given id, with the root of the
+    /// indexed tree as its outermost ancestor.
+    pub fn cursor(&self, id: Id) -> Option<Cursor<'ast, T>> {
+        let i = *self.map.get(&id)?;
+        let mut path = Vec::new();
+        let mut child = i;
+        while let Some(parent) = self.nodes[child].parent {
+            path.push((self.nodes[parent].node, self.nodes[child].child_index));
+            child = parent;
+        }
+        path.reverse();
+        Some(Cursor::from_path(self.nodes[i].node, path))
+    }
+}
+
+impl<'ast, T> Index<Id> for IdIndex<'ast, T> {
+    type Output = Node<T>;
+
+    /// # Panics
+    ///
+    /// Panics if no node in the tree has the given id.
+    fn index(&self, id: Id) -> &Self::Output {
+        match self.get(id) {
+            Some(node) => node,
+            None => panic!("no node with id {}", id),
+        }
+    }
+}
+
+struct Builder<'ast, T> {
+    index: IdIndex<'ast, T>,
+    // For each ancestor of the next node: its position in `nodes`, and how
+    // many of its children have been entered so far.
+    children: Vec<(usize, usize)>,
+}
+
+impl<'ast, T> Visit<'ast, T> for Builder<'ast, T> {
+    fn enter(&mut self, node: &'ast Node<T>, _depth: usize) -> Flow {
+        let i = self.index.nodes.len();
+        let (parent, child_index) = match self.children.last_mut() {
+            Some((parent, count)) => {
+                *count += 1;
+                (Some(*parent), *count - 1)
+            }
+            None => (None, 0),
+        };
+        self.index.nodes.push(IndexedNode {
+            node,
+            parent,
+            child_index,
+        });
+        if node.id != Id::NULL {
+            if let Entry::Vacant(entry) = self.index.map.entry(node.id) {
+                entry.insert(i);
+            }
+        }
+        self.children.push((i, 0));
+        Flow::Continue
+    }
+
+    fn leave(&mut self, _node: &'ast Node<T>, _depth: usize) -> Flow {
+        self.children.pop();
+        Flow::Continue
+    }
+}
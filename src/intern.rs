@@ -1,12 +1,127 @@
 use foldhash::HashSet;
 use serde::de::{DeserializeSeed, Deserializer, Error, Visitor};
 use std::cell::{Cell, RefCell};
-use std::fmt;
-use std::sync::Arc;
+use std::fmt::{self, Debug};
+use std::mem;
+use std::sync::{Arc, Mutex, PoisonError};
 
 thread_local! {
     static REFCOUNT: Cell<usize> = const { Cell::new(0) };
     static INTERN: RefCell<HashSet<Arc<str>>> = RefCell::new(HashSet::default());
+    // The interner installed by `Interner::install`, if any. While it is
+    // installed, INTERN caches the strings this thread has taken from it.
+    static SHARED: RefCell<Option<Interner>> = const { RefCell::new(None) };
+}
+
+/// Set of strings shared by the source locations of any number of dumps,
+/// across threads.
+///
+/// By default, file paths are interned only within one deserialization, so
+/// the same header included by two translation units is allocated twice and
+/// the two `Arc<str>` are not pointer-equal. Deserializing inside of
+/// [`install`](Interner::install) instead makes every occurrence of a path,
+/// in any dump deserialized by any thread through the same `Interner`, share
+/// one allocation.
+///
+/// Cloning an `Interner` produces another handle to the same set.
+///
+/// ```no_run
+/// # use serde_derive::Deserialize;
+/// # use std::thread;
+/// #
+/// # #[derive(Deserialize)]
+/// # enum Clang {
+/// #     Other,
+/// # }
+/// #
+/// fn main() -> Result<(), clang_ast::Error> {
+///     let interner = clang_ast::Interner::new();
+///     let [a, b] = ["a.json", "b.json"].map(|path| {
+///         let interner = interner.clone();
+///         thread::spawn(move || {
+///             let json = std::fs::read(path).unwrap();
+///             interner.install(|| clang_ast::from_slice::<Clang>(&json))
+///         })
+///     });
+///     let a = a.join().unwrap()?;
+///     let b = b.join().unwrap()?;
+///     # let _ = (a, b);
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub struct Interner {
+    strings: Arc<Mutex<HashSet<Arc<str>>>>,
+}
+
+impl Interner {
+    /// Creates an interner with no strings in it.
+    pub fn new() -> Self {
+        Interner::default()
+    }
+
+    /// The shared allocation of the given string, allocating it if this is
+    /// the first time it is seen.
+    pub fn intern(&self, string: &str) -> Arc<str> {
+        let mut strings = self.strings.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(arc) = strings.get(string) {
+            Arc::clone(arc)
+        } else {
+            let arc: Arc<str> = Arc::from(string);
+            strings.insert(Arc::clone(&arc));
+            arc
+        }
+    }
+
+    /// Number of distinct strings interned.
+    pub fn len(&self) -> usize {
+        self.strings
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+
+    /// Whether no string has been interned yet.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Runs `f` with this interner used for every source location deserialized
+    /// on the current thread, including by `serde_json` or by iterating
+    /// [`top_level`](crate::top_level).
+    pub fn install<R>(&self, f: impl FnOnce() -> R) -> R {
+        struct Restore {
+            shared: Option<Interner>,
+            intern: HashSet<Arc<str>>,
+        }
+
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                SHARED.with(|shared| *shared.borrow_mut() = self.shared.take());
+                INTERN.with(|intern| *intern.borrow_mut() = mem::take(&mut self.intern));
+            }
+        }
+
+        let _restore = Restore {
+            shared: SHARED.with(|shared| shared.replace(Some(self.clone()))),
+            intern: INTERN.with(|intern| mem::take(&mut *intern.borrow_mut())),
+        };
+        f()
+    }
+}
+
+impl Debug for Interner {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter
+            .debug_struct("Interner")
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+// The interner installed on the current thread by `Interner::install`.
+pub(crate) fn installed() -> Option<Interner> {
+    SHARED.with(|shared| shared.borrow().clone())
 }
 
 fn borrowed(string: &str) -> Arc<str> {
@@ -23,7 +138,10 @@ fn do_intern(string: impl AsRef<str> + Into<Arc<str>>) -> Arc<str> {
         if let Some(arc) = intern.get(string.as_ref()) {
             Arc::clone(arc)
         } else {
-            let arc: Arc<str> = string.into();
+            let arc: Arc<str> = SHARED.with(|shared| match &*shared.borrow() {
+                Some(shared) => shared.intern(string.as_ref()),
+                None => string.into(),
+            });
             intern.insert(Arc::clone(&arc));
             arc
         }
@@ -0,0 +1,1065 @@
+use crate::deserializer::NodeDeserializer;
+use crate::error::Error;
+use crate::id::Id;
+use crate::intern;
+use crate::kind::{AnyKind, Kind, SometimesBorrowedStrDeserializer};
+use crate::loc::{self, SourceLocation, SourceRange};
+use crate::Node;
+use serde::de::{
+    self, Deserialize, DeserializeSeed, Deserializer, EnumAccess, Error as _, IgnoredAny,
+    MapAccess, SeqAccess, Unexpected, VariantAccess, Visitor,
+};
+use serde::forward_to_deserialize_any;
+use std::fmt;
+use std::str;
+
+/// Deserializes a syntax tree from the bytes of a `-ast-dump=json` dump.
+///
+/// Deserializing through `serde_json::from_slice::<Node<T>>` recurses once per
+/// level of `"inner"` nesting, and dumps containing long chains of nested
+/// expressions can overflow the stack of an ordinary thread. This function
+/// produces the same `Node<T>` but keeps track of the nesting on the heap, so
+/// any dump can be deserialized regardless of how deep it is.
+///
+/// The `"inner"` field must come after all other fields of a node, which is
+/// always the case in the output of Clang.
+///
+/// ```no_run
+/// # use serde_derive::Deserialize;
+/// #
+/// # #[derive(Deserialize)]
+/// # enum Clang {
+/// #     Other,
+/// # }
+/// #
+/// fn main() -> Result<(), clang_ast::Error> {
+///     let json = std::fs::read("ast.json").unwrap();
+///     let node = clang_ast::from_slice::<Clang>(&json)?;
+///     # let _ = node;
+///     Ok(())
+/// }
+/// ```
+pub fn from_slice<'de, T>(bytes: &'de [u8]) -> Result<Node<T>, Error>
+where
+    T: Deserialize<'de>,
+{
+    let _intern = intern::activate();
+    let mut scanner = Scanner::new(bytes);
+    let node = parse_node(&mut scanner, None)?;
+    scanner.end()?;
+    Ok(node)
+}
+
+/// Deserializes a syntax tree from the text of a `-ast-dump=json` dump.
+///
+/// See [`from_slice`].
+pub fn from_str<'de, T>(string: &'de str) -> Result<Node<T>, Error>
+where
+    T: Deserialize<'de>,
+{
+    from_slice(string.as_bytes())
+}
+
+/// Deserializes a syntax tree, leaving out every node whose `"loc"` is
+/// rejected by `filter`, together with all of that node's descendants.
+///
+/// Most of a typical dump consists of declarations from system headers. This
+/// skips over them without building them into `Node<T>`, while still taking
+/// the skipped `"loc"` and `"range"` fields into account for resolving the
+/// "same as previous" file and line of later locations, which is what makes it
+/// impossible to do the same filtering correctly after deserialization.
+///
+/// The filter is applied to every node below the root which has a `"loc"`
+/// field, which mostly means declarations. Nodes without one, such as
+/// statements and types, are kept unless an ancestor was skipped. Implicit
+/// declarations have an empty `"loc"`, with both `spelling_loc` and
+/// `expansion_loc` set to `None`.
+///
+/// ```no_run
+/// # use serde_derive::Deserialize;
+/// #
+/// # #[derive(Deserialize)]
+/// # enum Clang {
+/// #     Other,
+/// # }
+/// #
+/// fn main() -> Result<(), clang_ast::Error> {
+///     let json = std::fs::read("ast.json").unwrap();
+///     let node = clang_ast::from_slice_filtered::<Clang>(&json, |loc| {
+///         loc.expansion_loc
+///             .as_ref()
+///             .map_or(true, |loc| !loc.file.starts_with("/usr/include/"))
+///     })?;
+///     # let _ = node;
+///     Ok(())
+/// }
+/// ```
+pub fn from_slice_filtered<'de, T>(
+    bytes: &'de [u8],
+    filter: impl Fn(&SourceLocation) -> bool,
+) -> Result<Node<T>, Error>
+where
+    T: Deserialize<'de>,
+{
+    let _intern = intern::activate();
+    let mut scanner = Scanner::new(bytes);
+    let node = parse_node(&mut scanner, Some(&filter))?;
+    scanner.end()?;
+    Ok(node)
+}
+
+// Parses one node including all of its descendants, using an explicit stack of
+// the ancestors whose "inner" array is still being parsed. Descendants whose
+// loc is rejected by the filter are skipped, along with their own descendants.
+pub(crate) fn parse_node<'de, T>(
+    scanner: &mut Scanner<'de>,
+    filter: Option<&dyn Fn(&SourceLocation) -> bool>,
+) -> Result<Node<T>, Error>
+where
+    T: Deserialize<'de>,
+{
+    let (root, has_inner) = begin_node(scanner)?;
+    if !has_inner {
+        return Ok(root);
+    }
+
+    let mut stack = vec![root];
+    let mut first = true;
+    loop {
+        if scanner.eat(b']') {
+            scanner.end_node()?;
+            let node = stack.pop().unwrap();
+            match stack.last_mut() {
+                Some(parent) => parent.inner.push(node),
+                None => return Ok(node),
+            }
+            first = false;
+            continue;
+        }
+        if !first {
+            scanner.expect(b',', "expected `,` or `]`")?;
+        }
+        first = false;
+        if let Some(filter) = filter {
+            if !peek_loc(scanner)?.map_or(true, |loc| filter(&loc)) {
+                skip_node(scanner)?;
+                continue;
+            }
+        }
+        let (node, has_inner) = begin_node(scanner)?;
+        if has_inner {
+            stack.push(node);
+            first = true;
+        } else {
+            stack.last_mut().unwrap().inner.push(node);
+        }
+    }
+}
+
+// Parses the fields of a node up to and including the opening bracket of its
+// "inner" array, if any. Returns the node with empty `inner`, and whether there
+// is an "inner" array to parse.
+pub(crate) fn begin_node<'de, T>(scanner: &mut Scanner<'de>) -> Result<(Node<T>, bool), Error>
+where
+    T: Deserialize<'de>,
+{
+    scanner.expect(b'{', "expected clang syntax tree node")?;
+    let mut map = NodeFields::new(scanner);
+    match deserialize_node(&mut map) {
+        Ok((id, kind)) => {
+            let has_inner = map.has_inner;
+            let inner = Vec::new();
+            Ok((Node { id, kind, inner }, has_inner))
+        }
+        Err(error) => Err(error.at(map.de.bytes, map.de.index)),
+    }
+}
+
+fn deserialize_node<'de, T>(map: &mut NodeFields<'_, 'de>) -> Result<(Id, T), Error>
+where
+    T: Deserialize<'de>,
+{
+    let mut id = None;
+    let kind = loop {
+        match map.next_key()? {
+            None if map.has_inner => return Err(Error::missing_field("kind")),
+            None => break AnyKind::Kind(Kind::null),
+            Some(FirstField::Id) => {
+                if id.is_some() {
+                    return Err(Error::duplicate_field("id"));
+                }
+                id = Some(map.next_value()?);
+            }
+            Some(FirstField::Kind) => break map.next_value()?,
+        }
+    };
+
+    let mut inner = Vec::<Node<T>>::new();
+    let kind = T::deserialize(NodeDeserializer::new(&kind, &mut inner, &mut *map))?;
+    while let Some(IgnoredAny) = map.next_key()? {
+        let IgnoredAny = map.next_value()?;
+    }
+
+    let id = id.unwrap_or_default();
+    Ok((id, kind))
+}
+
+// Reads the "loc" of the node at the current position, if it has one, without
+// consuming any input or affecting how later locations are resolved.
+fn peek_loc(scanner: &mut Scanner) -> Result<Option<SourceLocation>, Error> {
+    let index = scanner.index;
+    let state = loc::thread_local_snapshot();
+    let loc = read_loc(scanner);
+    scanner.index = index;
+    loc::thread_local_rollback(state);
+    loc
+}
+
+fn read_loc(scanner: &mut Scanner) -> Result<Option<SourceLocation>, Error> {
+    scanner.expect(b'{', "expected clang syntax tree node")?;
+    let mut map = NodeFields::new(scanner);
+    while let Some(field) = map.next_key()? {
+        match field {
+            SkipField::Loc => return map.next_value().map(Some),
+            SkipField::Range | SkipField::Other => {
+                let IgnoredAny = map.next_value()?;
+            }
+        }
+    }
+    Ok(None)
+}
+
+// Consumes the node at the current position and all of its descendants,
+// deserializing only their "loc" and "range" for the sake of resolving later
+// locations.
+pub(crate) fn skip_node(scanner: &mut Scanner) -> Result<(), Error> {
+    let mut depth = 0;
+    loop {
+        scanner.expect(b'{', "expected clang syntax tree node")?;
+        let mut map = NodeFields::new(scanner);
+        while let Some(field) = map.next_key()? {
+            match field {
+                SkipField::Loc => drop(map.next_value::<SourceLocation>()?),
+                SkipField::Range => drop(map.next_value::<SourceRange>()?),
+                SkipField::Other => {
+                    let IgnoredAny = map.next_value()?;
+                }
+            }
+        }
+        if map.has_inner {
+            depth += 1;
+            if !scanner.eat(b']') {
+                continue;
+            }
+            scanner.end_node()?;
+            depth -= 1;
+        }
+
+        loop {
+            if depth == 0 {
+                return Ok(());
+            }
+            if scanner.eat(b',') {
+                break;
+            }
+            scanner.expect(b']', "expected `,` or `]`")?;
+            scanner.end_node()?;
+            depth -= 1;
+        }
+    }
+}
+
+enum SkipField {
+    Loc,
+    Range,
+    Other,
+}
+
+impl<'de> Deserialize<'de> for SkipField {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct SkipFieldVisitor;
+
+        impl<'de> Visitor<'de> for SkipFieldVisitor {
+            type Value = SkipField;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("field identifier")
+            }
+
+            fn visit_str<E>(self, field: &str) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                Ok(match field {
+                    "loc" => SkipField::Loc,
+                    "range" => SkipField::Range,
+                    _ => SkipField::Other,
+                })
+            }
+        }
+
+        deserializer.deserialize_identifier(SkipFieldVisitor)
+    }
+}
+
+enum FirstField {
+    Id,
+    Kind,
+}
+
+impl<'de> Deserialize<'de> for FirstField {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct FirstFieldVisitor;
+
+        impl<'de> Visitor<'de> for FirstFieldVisitor {
+            type Value = FirstField;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("field identifier")
+            }
+
+            fn visit_str<E>(self, field: &str) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                static FIELDS: &[&str] = &["id", "kind", "inner"];
+                match field {
+                    "id" => Ok(FirstField::Id),
+                    "kind" => Ok(FirstField::Kind),
+                    _ => Err(E::unknown_field(field, FIELDS)),
+                }
+            }
+        }
+
+        deserializer.deserialize_identifier(FirstFieldVisitor)
+    }
+}
+
+// The fields of a syntax tree node, excluding "inner". Reaching the "inner"
+// field ends the map, with the scanner positioned at the first child node.
+struct NodeFields<'a, 'de> {
+    de: &'a mut Scanner<'de>,
+    first: bool,
+    done: bool,
+    has_inner: bool,
+}
+
+impl<'a, 'de> NodeFields<'a, 'de> {
+    fn new(de: &'a mut Scanner<'de>) -> Self {
+        NodeFields {
+            de,
+            first: true,
+            done: false,
+            has_inner: false,
+        }
+    }
+}
+
+impl<'a, 'de> MapAccess<'de> for NodeFields<'a, 'de> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        if self.done {
+            return Ok(None);
+        }
+        if self.de.eat(b'}') {
+            self.done = true;
+            return Ok(None);
+        }
+        if !self.first {
+            self.de.expect(b',', "expected `,` or `}`")?;
+        }
+        self.first = false;
+        let key = match self.de.parse_key()? {
+            Str::Borrowed("inner") => {
+                self.de.expect(b':', "expected `:`")?;
+                self.de.expect(b'[', "expected `[`")?;
+                self.done = true;
+                self.has_inner = true;
+                return Ok(None);
+            }
+            Str::Borrowed(key) => seed.deserialize(SometimesBorrowedStrDeserializer::borrowed(key)),
+            Str::Copied(key) => seed.deserialize(SometimesBorrowedStrDeserializer::transient(key)),
+        }?;
+        self.de.expect(b':', "expected `:`")?;
+        Ok(Some(key))
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        seed.deserialize(&mut *self.de)
+    }
+}
+
+enum Str<'de, 's> {
+    Borrowed(&'de str),
+    Copied(&'s str),
+}
+
+// Depth of nested arrays and objects permitted within a field of a node, to
+// guard against stack overflow. Nesting of nodes is not limited.
+const RECURSION_LIMIT: u8 = 128;
+
+pub(crate) struct Scanner<'de> {
+    bytes: &'de [u8],
+    index: usize,
+    scratch: Vec<u8>,
+    remaining_depth: u8,
+}
+
+impl<'de> Scanner<'de> {
+    pub(crate) fn new(bytes: &'de [u8]) -> Self {
+        Scanner {
+            bytes,
+            index: 0,
+            scratch: Vec::new(),
+            remaining_depth: RECURSION_LIMIT,
+        }
+    }
+
+    // Offset of the next non-whitespace byte.
+    pub(crate) fn position(&mut self) -> usize {
+        self.peek();
+        self.index
+    }
+
+    pub(crate) fn seek(&mut self, index: usize) {
+        self.index = index;
+    }
+
+    fn error(&self, msg: &str) -> Error {
+        Error::syntax(msg, self.bytes, self.index)
+    }
+
+    fn eof_or(&self, msg: &str) -> Error {
+        if self.index == self.bytes.len() {
+            self.error("EOF while parsing clang syntax tree node")
+        } else {
+            self.error(msg)
+        }
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        while let Some(b' ' | b'\n' | b'\t' | b'\r') = self.bytes.get(self.index) {
+            self.index += 1;
+        }
+        self.bytes.get(self.index).copied()
+    }
+
+    pub(crate) fn eat(&mut self, byte: u8) -> bool {
+        if self.peek() == Some(byte) {
+            self.index += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub(crate) fn expect(&mut self, byte: u8, msg: &str) -> Result<(), Error> {
+        if self.eat(byte) {
+            Ok(())
+        } else {
+            Err(self.eof_or(msg))
+        }
+    }
+
+    // Checks that nothing but whitespace follows the root node.
+    pub(crate) fn end(&mut self) -> Result<(), Error> {
+        if self.peek().is_none() {
+            Ok(())
+        } else {
+            Err(self.error("trailing characters"))
+        }
+    }
+
+    // Called after the closing bracket of a node's "inner" array.
+    pub(crate) fn end_node(&mut self) -> Result<(), Error> {
+        if self.peek() == Some(b',') {
+            Err(self.error("unexpected field after `inner`"))
+        } else {
+            self.expect(b'}', "expected `}`")
+        }
+    }
+
+    fn enter(&mut self) -> Result<(), Error> {
+        self.index += 1;
+        self.remaining_depth -= 1;
+        if self.remaining_depth == 0 {
+            Err(self.error("recursion limit exceeded"))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn leave(&mut self) {
+        self.remaining_depth += 1;
+    }
+
+    fn parse_key<'s>(&'s mut self) -> Result<Str<'de, 's>, Error> {
+        match self.peek() {
+            Some(b'"') => self.parse_str(),
+            _ => Err(self.eof_or("key must be a string")),
+        }
+    }
+
+    fn parse_ident(&mut self, ident: &[u8]) -> Result<(), Error> {
+        for expected in ident {
+            match self.bytes.get(self.index) {
+                Some(byte) if byte == expected => self.index += 1,
+                Some(_) => return Err(self.error("expected ident")),
+                None => return Err(self.error("EOF while parsing a value")),
+            }
+        }
+        Ok(())
+    }
+
+    // Parses a string beginning at the current position. Strings without
+    // escape sequences are borrowed from the input.
+    fn parse_str<'s>(&'s mut self) -> Result<Str<'de, 's>, Error> {
+        let bytes = self.bytes;
+        self.index += 1;
+        self.scratch.clear();
+        let mut start = self.index;
+        loop {
+            let Some(len) = bytes[self.index..]
+                .iter()
+                .position(|&byte| byte == b'"' || byte == b'\\' || byte < 0x20)
+            else {
+                self.index = bytes.len();
+                return Err(self.error("EOF while parsing a string"));
+            };
+            self.index += len;
+            match bytes[self.index] {
+                b'"' => {
+                    let tail = &bytes[start..self.index];
+                    self.index += 1;
+                    return if self.scratch.is_empty() {
+                        match str::from_utf8(tail) {
+                            Ok(string) => Ok(Str::Borrowed(string)),
+                            Err(error) => Err(self.utf8_error(start + error.valid_up_to())),
+                        }
+                    } else {
+                        self.scratch.extend_from_slice(tail);
+                        match str::from_utf8(&self.scratch) {
+                            Ok(string) => Ok(Str::Copied(string)),
+                            Err(_) => Err(Error::syntax(
+                                "invalid unicode code point",
+                                bytes,
+                                self.index - 1,
+                            )),
+                        }
+                    };
+                }
+                b'\\' => {
+                    self.scratch.extend_from_slice(&bytes[start..self.index]);
+                    self.index += 1;
+                    self.parse_escape()?;
+                    start = self.index;
+                }
+                _ => {
+                    return Err(self.error(
+                        "control character (\\u0000-\\u001F) found while parsing a string",
+                    ));
+                }
+            }
+        }
+    }
+
+    fn utf8_error(&self, offset: usize) -> Error {
+        Error::syntax("invalid unicode code point", self.bytes, offset)
+    }
+
+    fn parse_escape(&mut self) -> Result<(), Error> {
+        let Some(&byte) = self.bytes.get(self.index) else {
+            return Err(self.error("EOF while parsing a string"));
+        };
+        self.index += 1;
+        let ch = match byte {
+            b'"' => '"',
+            b'\\' => '\\',
+            b'/' => '/',
+            b'b' => '\x08',
+            b'f' => '\x0c',
+            b'n' => '\n',
+            b'r' => '\r',
+            b't' => '\t',
+            b'u' => {
+                let n = match self.decode_hex_escape()? {
+                    n @ 0xD800..=0xDBFF => {
+                        if self.bytes.get(self.index..self.index + 2) != Some(b"\\u") {
+                            return Err(self.error("lone leading surrogate in hex escape"));
+                        }
+                        self.index += 2;
+                        let n2 = self.decode_hex_escape()?;
+                        if !(0xDC00..=0xDFFF).contains(&n2) {
+                            return Err(self.error("lone leading surrogate in hex escape"));
+                        }
+                        ((n - 0xD800) << 10 | (n2 - 0xDC00)) + 0x1_0000
+                    }
+                    0xDC00..=0xDFFF => {
+                        return Err(self.error("lone leading surrogate in hex escape"));
+                    }
+                    n => n,
+                };
+                char::from_u32(n).unwrap()
+            }
+            _ => {
+                self.index -= 1;
+                return Err(self.error("invalid escape"));
+            }
+        };
+        self.scratch
+            .extend_from_slice(ch.encode_utf8(&mut [0; 4]).as_bytes());
+        Ok(())
+    }
+
+    fn decode_hex_escape(&mut self) -> Result<u32, Error> {
+        let mut n = 0;
+        for _ in 0..4 {
+            let Some(&byte) = self.bytes.get(self.index) else {
+                return Err(self.error("EOF while parsing a string"));
+            };
+            let Some(digit) = char::from(byte).to_digit(16) else {
+                return Err(self.error("invalid escape"));
+            };
+            n = n * 16 + digit;
+            self.index += 1;
+        }
+        Ok(n)
+    }
+
+    fn parse_number<V>(&mut self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        let start = self.index;
+        let negative = self.bytes[self.index] == b'-';
+        if negative {
+            self.index += 1;
+        }
+        match self.bytes.get(self.index) {
+            Some(b'0') => self.index += 1,
+            Some(b'1'..=b'9') => self.skip_digits(),
+            Some(_) => return Err(self.error("invalid number")),
+            None => return Err(self.error("EOF while parsing a value")),
+        }
+        let mut float = false;
+        if self.bytes.get(self.index) == Some(&b'.') {
+            float = true;
+            self.index += 1;
+            self.expect_digits()?;
+        }
+        if let Some(b'e' | b'E') = self.bytes.get(self.index) {
+            float = true;
+            self.index += 1;
+            if let Some(b'+' | b'-') = self.bytes.get(self.index) {
+                self.index += 1;
+            }
+            self.expect_digits()?;
+        }
+
+        let number = str::from_utf8(&self.bytes[start..self.index]).unwrap();
+        if !float {
+            if negative {
+                if let Ok(n) = number.parse() {
+                    return visitor.visit_i64(n);
+                }
+            } else if let Ok(n) = number.parse() {
+                return visitor.visit_u64(n);
+            }
+        }
+        match number.parse::<f64>() {
+            Ok(n) if n.is_finite() => visitor.visit_f64(n),
+            _ => Err(Error::syntax("number out of range", self.bytes, start)),
+        }
+    }
+
+    fn skip_digits(&mut self) {
+        while let Some(b'0'..=b'9') = self.bytes.get(self.index) {
+            self.index += 1;
+        }
+    }
+
+    fn expect_digits(&mut self) -> Result<(), Error> {
+        match self.bytes.get(self.index) {
+            Some(b'0'..=b'9') => {
+                self.skip_digits();
+                Ok(())
+            }
+            Some(_) => Err(self.error("invalid number")),
+            None => Err(self.error("EOF while parsing a value")),
+        }
+    }
+
+    // Skips over one value without recursing, no matter how deeply nested.
+    pub(crate) fn skip_value(&mut self) -> Result<(), Error> {
+        // One entry per array (false) or object (true) currently open.
+        let mut stack = Vec::new();
+        loop {
+            match self.peek() {
+                None => return Err(self.error("EOF while parsing a value")),
+                Some(b'n') => self.parse_ident(b"null")?,
+                Some(b't') => self.parse_ident(b"true")?,
+                Some(b'f') => self.parse_ident(b"false")?,
+                Some(b'-' | b'0'..=b'9') => self.parse_number(IgnoredAny).map(drop)?,
+                Some(b'"') => self.parse_str().map(drop)?,
+                Some(b'[') => {
+                    self.index += 1;
+                    if !self.eat(b']') {
+                        stack.push(false);
+                        continue;
+                    }
+                }
+                Some(b'{') => {
+                    self.index += 1;
+                    if !self.eat(b'}') {
+                        self.parse_key()?;
+                        self.expect(b':', "expected `:`")?;
+                        stack.push(true);
+                        continue;
+                    }
+                }
+                Some(_) => return Err(self.error("expected value")),
+            }
+
+            // A complete value has been skipped. Close any arrays or objects
+            // that end after it.
+            loop {
+                match stack.last() {
+                    None => return Ok(()),
+                    Some(false) => {
+                        if self.eat(b',') {
+                            break;
+                        }
+                        self.expect(b']', "expected `,` or `]`")?;
+                    }
+                    Some(true) => {
+                        if self.eat(b',') {
+                            self.parse_key()?;
+                            self.expect(b':', "expected `:`")?;
+                            break;
+                        }
+                        self.expect(b'}', "expected `,` or `}`")?;
+                    }
+                }
+                stack.pop();
+            }
+        }
+    }
+}
+
+impl<'de, 'a> Deserializer<'de> for &'a mut Scanner<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let value = match self.peek() {
+            None => return Err(self.error("EOF while parsing a value")),
+            Some(b'n') => {
+                self.parse_ident(b"null")?;
+                visitor.visit_unit()
+            }
+            Some(b't') => {
+                self.parse_ident(b"true")?;
+                visitor.visit_bool(true)
+            }
+            Some(b'f') => {
+                self.parse_ident(b"false")?;
+                visitor.visit_bool(false)
+            }
+            Some(b'-' | b'0'..=b'9') => self.parse_number(visitor),
+            Some(b'"') => match self.parse_str()? {
+                Str::Borrowed(string) => visitor.visit_borrowed_str(string),
+                Str::Copied(string) => visitor.visit_str(string),
+            },
+            Some(b'[') => {
+                self.enter()?;
+                let value = visitor.visit_seq(Seq {
+                    de: &mut *self,
+                    first: true,
+                });
+                self.leave();
+                value.and_then(|value| {
+                    self.expect(b']', "trailing characters")?;
+                    Ok(value)
+                })
+            }
+            Some(b'{') => {
+                self.enter()?;
+                let value = visitor.visit_map(Map {
+                    de: &mut *self,
+                    first: true,
+                });
+                self.leave();
+                value.and_then(|value| {
+                    self.expect(b'}', "trailing characters")?;
+                    Ok(value)
+                })
+            }
+            Some(_) => return Err(self.error("expected value")),
+        };
+        value.map_err(|error| error.at(self.bytes, self.index))
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        if self.peek() == Some(b'n') {
+            self.parse_ident(b"null")?;
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let _ = name;
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let _ = name;
+        let _ = variants;
+        let value = match self.peek() {
+            Some(b'"') => visitor.visit_enum(UnitVariant { de: &mut *self }),
+            Some(b'{') => {
+                self.enter()?;
+                let value = visitor.visit_enum(Variant { de: &mut *self });
+                self.leave();
+                value.and_then(|value| {
+                    self.expect(b'}', "expected `}`")?;
+                    Ok(value)
+                })
+            }
+            _ => return self.deserialize_any(visitor),
+        };
+        value.map_err(|error| error.at(self.bytes, self.index))
+    }
+
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.skip_value()?;
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier
+    }
+}
+
+struct Seq<'a, 'de> {
+    de: &'a mut Scanner<'de>,
+    first: bool,
+}
+
+impl<'a, 'de> SeqAccess<'de> for Seq<'a, 'de> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        if self.de.peek() == Some(b']') {
+            return Ok(None);
+        }
+        if !self.first {
+            self.de.expect(b',', "expected `,` or `]`")?;
+            if self.de.peek() == Some(b']') {
+                return Err(self.de.error("trailing comma"));
+            }
+        }
+        self.first = false;
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+}
+
+struct Map<'a, 'de> {
+    de: &'a mut Scanner<'de>,
+    first: bool,
+}
+
+impl<'a, 'de> MapAccess<'de> for Map<'a, 'de> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        if self.de.peek() == Some(b'}') {
+            return Ok(None);
+        }
+        if !self.first {
+            self.de.expect(b',', "expected `,` or `}`")?;
+            if self.de.peek() == Some(b'}') {
+                return Err(self.de.error("trailing comma"));
+            }
+        }
+        self.first = false;
+        let key = match self.de.parse_key()? {
+            Str::Borrowed(key) => seed.deserialize(SometimesBorrowedStrDeserializer::borrowed(key)),
+            Str::Copied(key) => seed.deserialize(SometimesBorrowedStrDeserializer::transient(key)),
+        }?;
+        self.de.expect(b':', "expected `:`")?;
+        Ok(Some(key))
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        seed.deserialize(&mut *self.de)
+    }
+}
+
+struct UnitVariant<'a, 'de> {
+    de: &'a mut Scanner<'de>,
+}
+
+impl<'a, 'de> EnumAccess<'de> for UnitVariant<'a, 'de> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant), Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let variant = seed.deserialize(&mut *self.de)?;
+        Ok((variant, self))
+    }
+}
+
+impl<'a, 'de> VariantAccess<'de> for UnitVariant<'a, 'de> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let _ = seed;
+        Err(Error::invalid_type(
+            Unexpected::UnitVariant,
+            &"newtype variant",
+        ))
+    }
+
+    fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let _ = len;
+        let _ = visitor;
+        Err(Error::invalid_type(
+            Unexpected::UnitVariant,
+            &"tuple variant",
+        ))
+    }
+
+    fn struct_variant<V>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let _ = fields;
+        let _ = visitor;
+        Err(Error::invalid_type(
+            Unexpected::UnitVariant,
+            &"struct variant",
+        ))
+    }
+}
+
+struct Variant<'a, 'de> {
+    de: &'a mut Scanner<'de>,
+}
+
+impl<'a, 'de> EnumAccess<'de> for Variant<'a, 'de> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant), Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let variant = match self.de.parse_key()? {
+            Str::Borrowed(key) => seed.deserialize(SometimesBorrowedStrDeserializer::borrowed(key)),
+            Str::Copied(key) => seed.deserialize(SometimesBorrowedStrDeserializer::transient(key)),
+        }?;
+        self.de.expect(b':', "expected `:`")?;
+        Ok((variant, self))
+    }
+}
+
+impl<'a, 'de> VariantAccess<'de> for Variant<'a, 'de> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        Deserialize::deserialize(self.de)
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        seed.deserialize(self.de)
+    }
+
+    fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let _ = len;
+        self.de.deserialize_seq(visitor)
+    }
+
+    fn struct_variant<V>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let _ = fields;
+        self.de.deserialize_map(visitor)
+    }
+}
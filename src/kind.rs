@@ -48,7 +48,9 @@ macro_rules! kind {
 
 kind! {
     AbiTagAttr,
+    AbstractConditionalOperator,
     AccessSpecDecl,
+    AdjustedType,
     AliasAttr,
     AlignValueAttr,
     AlignedAttr,
@@ -58,19 +60,24 @@ kind! {
     ArrayInitIndexExpr,
     ArrayInitLoopExpr,
     ArraySubscriptExpr,
+    ArrayType,
     ArrayTypeTraitExpr,
     AsmLabelAttr,
+    AsmStmt,
     AtomicExpr,
     AtomicType,
+    Attr,
     AttributedStmt,
     AttributedType,
     AutoType,
     AvailabilityAttr,
     AvailableOnlyInDefaultEvalMethodAttr,
+    BaseUsingDecl,
     BinaryConditionalOperator,
     BinaryOperator,
     BindingDecl,
     BlockCommandComment,
+    BlockContentComment,
     BlockPointerType,
     BreakStmt,
     BuiltinAttr,
@@ -100,6 +107,7 @@ kind! {
     CXXInheritedCtorInitExpr,
     CXXMemberCallExpr,
     CXXMethodDecl,
+    CXXNamedCastExpr,
     CXXNewExpr,
     CXXNoexceptExpr,
     CXXNullPtrLiteralExpr,
@@ -119,12 +127,14 @@ kind! {
     CallExpr,
     CallbackAttr,
     CaseStmt,
+    CastExpr,
     CharacterLiteral,
     ClassScopeFunctionSpecializationDecl,
     ClassTemplateDecl,
     ClassTemplatePartialSpecializationDecl,
     ClassTemplateSpecializationDecl,
     ColdAttr,
+    Comment,
     ComplexType,
     CompoundAssignOperator,
     CompoundLiteralExpr,
@@ -142,10 +152,14 @@ kind! {
     DLLImportAttr,
     DecayedType,
     Decl,
+    DeclOrStmtAttr,
+    DeclOrTypeAttr,
     DeclRefExpr,
     DeclStmt,
+    DeclaratorDecl,
     DecltypeType,
     DecompositionDecl,
+    DeducedType,
     DefaultStmt,
     DependentNameType,
     DependentScopeDeclRefExpr,
@@ -163,6 +177,8 @@ kind! {
     EnumDecl,
     EnumType,
     ExcludeFromExplicitInstantiationAttr,
+    ExplicitCastExpr,
+    Expr,
     ExprWithCleanups,
     FallThroughAttr,
     FieldDecl,
@@ -174,15 +190,18 @@ kind! {
     FormatAttr,
     FriendDecl,
     FullComment,
+    FullExpr,
     FunctionDecl,
     FunctionProtoType,
     FunctionTemplateDecl,
+    FunctionType,
     GCCAsmStmt,
     GNUInlineAttr,
     GNUNullExpr,
     GotoStmt,
     HTMLEndTagComment,
     HTMLStartTagComment,
+    HTMLTagComment,
     IfStmt,
     ImplicitCastExpr,
     ImplicitConceptSpecializationDecl,
@@ -190,9 +209,12 @@ kind! {
     IncompleteArrayType,
     IndirectFieldDecl,
     IndirectGotoStmt,
+    InheritableAttr,
+    InheritableParamAttr,
     InitListExpr,
     InjectedClassNameType,
     InlineCommandComment,
+    InlineContentComment,
     IntegerLiteral,
     InternalLinkageAttr,
     LValueReferenceType,
@@ -212,6 +234,7 @@ kind! {
     MemberPointerType,
     MinVectorWidthAttr,
     ModeAttr,
+    NamedDecl,
     NamespaceAliasDecl,
     NamespaceDecl,
     NestedRequirement,
@@ -227,6 +250,7 @@ kind! {
     NullStmt,
     OffsetOfExpr,
     OpaqueValueExpr,
+    OverloadExpr,
     OverrideAttr,
     OwnerAttr,
     PackExpansionExpr,
@@ -250,6 +274,8 @@ kind! {
     RecordDecl,
     RecordType,
     RecoveryExpr,
+    RedeclarableTemplateDecl,
+    ReferenceType,
     RequiresExpr,
     RestrictAttr,
     ReturnStmt,
@@ -262,30 +288,40 @@ kind! {
     SourceLocExpr,
     StandaloneDebugAttr,
     StaticAssertDecl,
+    Stmt,
+    StmtAttr,
     StmtExpr,
     StringLiteral,
     SubstNonTypeTemplateParmExpr,
     SubstTemplateTypeParmPackType,
     SubstTemplateTypeParmType,
     SwiftAttrAttr,
+    SwitchCase,
     SwitchStmt,
     TParamCommandComment,
+    TagDecl,
+    TagType,
     TargetAttr,
     TemplateArgument,
+    TemplateDecl,
     TemplateSpecializationType,
     TemplateTemplateParmDecl,
     TemplateTypeParmDecl,
     TemplateTypeParmType,
     TextComment,
     TranslationUnitDecl,
+    Type,
     TypeAliasDecl,
     TypeAliasTemplateDecl,
+    TypeDecl,
     TypeNullableAttr,
     TypeOfExprType,
     TypeRequirement,
     TypeTraitExpr,
     TypeVisibilityAttr,
+    TypeWithKeyword,
     TypedefDecl,
+    TypedefNameDecl,
     TypedefType,
     UnaryExprOrTypeTraitExpr,
     UnaryOperator,
@@ -307,6 +343,8 @@ kind! {
     UsingShadowDecl,
     UsingType,
     VTablePointerAuthenticationAttr,
+    ValueDecl,
+    ValueStmt,
     VarDecl,
     VarTemplateDecl,
     VarTemplatePartialSpecializationDecl,
@@ -344,6 +344,65 @@
 //!
 //! <br><br>
 //!
+//! # Borrowed strings
+//!
+//! Strings in the kind data, such as names and types, can borrow from the
+//! input instead of being copied. For a dump of hundreds of megabytes that is
+//! memory-mapped rather than read into memory, this avoids allocating a copy of
+//! every name in the translation unit.
+//!
+//! Use `&'a str` for strings which never contain escape sequences, or
+//! `Cow<'a, str>` with `#[serde(borrow)]` for strings which might, such as
+//! `"qualType"`, so that an escaped string falls back to an owned copy. Serde
+//! borrows a `Cow` only when it is the type of the field itself, so prefer
+//! `#[serde(default)]` over wrapping it in `Option`.
+//!
+//! ```
+//! # use serde_derive::Deserialize;
+//! use std::borrow::Cow;
+//!
+//! pub type Node<'a> = clang_ast::Node<Clang<'a>>;
+//!
+//! #[derive(Deserialize)]
+//! pub enum Clang<'a> {
+//!     VarDecl(#[serde(borrow)] VarDecl<'a>),
+//!     Other,
+//! }
+//!
+//! #[derive(Deserialize, Debug)]
+//! pub struct VarDecl<'a> {
+//!     pub name: &'a str,                //<--
+//!     #[serde(borrow, rename = "type")]
+//!     pub ty: Type<'a>,
+//! }
+//!
+//! #[derive(Deserialize, Debug)]
+//! pub struct Type<'a> {
+//!     #[serde(borrow, rename = "qualType")]
+//!     pub qual_type: Cow<'a, str>,      //<--
+//! }
+//! ```
+//!
+//! For source locations, [`BorrowedSourceRange`] and [`BorrowedSourceLocation`]
+//! are variants of `SourceRange` and `SourceLocation` whose filepaths borrow
+//! from the input wherever Clang printed them. Where Clang omitted a filepath
+//! because it is the same as the previous location's, or the path contains
+//! escape sequences, it is shared with other locations as an `Arc<str>`
+//! instead.
+//!
+//! ```
+//! # use serde_derive::Deserialize;
+//! #
+//! #[derive(Deserialize)]
+//! pub struct FunctionDecl<'a> {
+//!     pub name: &'a str,
+//!     #[serde(borrow)]
+//!     pub range: clang_ast::BorrowedSourceRange<'a>,     //<--
+//! }
+//! ```
+//!
+//! <br><br>
+//!
 //! # Node identifiers
 //!
 //! Every syntax tree node has an `"id"`. In JSON it's the memory address of
@@ -400,6 +459,7 @@
     clippy::elidable_lifetime_names,
     clippy::let_underscore_untyped,
     clippy::match_like_matches_macro,
+    clippy::missing_errors_doc,
     clippy::must_use_candidate,
     clippy::needless_lifetimes,
     clippy::ptr_arg,
@@ -407,13 +467,36 @@
     clippy::unnecessary_map_or
 )]
 
+mod binary;
+#[cfg(feature = "driver")]
+mod cache;
+mod cursor;
 mod dedup;
 mod deserializer;
+#[cfg(feature = "driver")]
+mod driver;
+mod error;
+mod expansion;
+mod fidelity;
+mod graph;
+mod hierarchy;
 mod id;
+mod include;
+mod index;
 mod intern;
+mod json;
 mod kind;
 mod loc;
+mod matching;
+mod parallel;
+mod position;
+mod presumed;
+mod project;
 mod serializer;
+mod source_map;
+mod stream;
+mod text;
+mod visit;
 
 extern crate serde_core as serde;
 
@@ -423,14 +506,114 @@ use crate::serializer::NodeSerializer;
 use serde::de::{Deserialize, Deserializer, MapAccess, Visitor};
 use serde::ser::{Serialize, SerializeMap, Serializer};
 use std::fmt;
+use std::hash::{Hash, Hasher};
 use std::marker::PhantomData;
+use std::mem;
+use std::slice;
 
+pub use crate::binary::{from_binary, to_binary};
+#[cfg(feature = "driver")]
+pub use crate::cache::Cache;
+pub use crate::cursor::{Ancestors, Cursor, HasKind};
+#[cfg(feature = "driver")]
+pub use crate::driver::{CompilationDatabase, CompileCommand, Driver, DriverError};
+pub use crate::error::Error;
+pub use crate::fidelity::faithful;
+pub use crate::graph::{Graph, GraphFormat};
+pub use crate::hierarchy::Category;
 pub use crate::id::Id;
+pub use crate::include::{IncludeGraph, SourceFile};
+pub use crate::index::IdIndex;
+pub use crate::intern::Interner;
+pub use crate::json::{from_slice, from_slice_filtered, from_str};
 pub use crate::kind::Kind;
-pub use crate::loc::{BareSourceLocation, IncludedFrom, SourceLocation, SourceRange};
+pub use crate::loc::{
+    BareSourceLocation, BorrowedBareSourceLocation, BorrowedSourceLocation, BorrowedSourceRange,
+    FilePath, IncludedFrom, SourceLocation, SourceRange,
+};
+pub use crate::parallel::from_slice_parallel;
+pub use crate::position::HasRange;
+pub use crate::presumed::PresumedLoc;
+pub use crate::project::{Declaration, HasDecl, Project, Symbol, SymbolKey};
+pub use crate::source_map::{SourceMap, SourceText};
+pub use crate::stream::{top_level, TopLevel};
+pub use crate::text::{TextAttributes, TextDump, TextWriter};
+pub use crate::visit::{Flow, Visit, VisitMut};
+
+pub mod matcher {
+    //! Composable predicates for finding patterns in a syntax tree, in the
+    //! style of Clang's AST matchers.
+    //!
+    //! A [`Matcher`] is built from the functions in this module and combined
+    //! with [`and`][Matcher::and], [`or`][Matcher::or] and [`not`]. Conditions
+    //! on the fields of the caller's `T` are written as closures with
+    //! [`node`]. Nodes of interest inside a match are given names with
+    //! [`bind`][Matcher::bind] and retrieved from the resulting [`Match`].
+    //!
+    //! ```
+    //! # use clang_ast::{HasKind, HasRange, Kind, SourceRange};
+    //! # use serde_derive::Deserialize;
+    //! #
+    //! # #[derive(Deserialize)]
+    //! # pub struct Clang {
+    //! #     kind: Kind,
+    //! #     #[serde(rename = "referencedDecl")]
+    //! #     referenced_decl: Option<Decl>,
+    //! #     #[serde(default)]
+    //! #     range: SourceRange,
+    //! # }
+    //! #
+    //! # #[derive(Deserialize)]
+    //! # pub struct Decl {
+    //! #     name: Option<String>,
+    //! # }
+    //! #
+    //! # impl HasKind for Clang {
+    //! #     fn kind(&self) -> Kind {
+    //! #         self.kind
+    //! #     }
+    //! # }
+    //! #
+    //! # impl HasRange for Clang {
+    //! #     fn range(&self) -> Option<&SourceRange> {
+    //! #         Some(&self.range)
+    //! #     }
+    //! # }
+    //! #
+    //! use clang_ast::matcher::{
+    //!     has_ancestor, has_descendant, is_expansion_in_main_file, kind, node,
+    //! };
+    //!
+    //! // Calls to a function named `exit` anywhere inside of a destructor.
+    //! fn exit_in_destructor(root: &clang_ast::Node<Clang>) {
+    //!     let matcher = kind(Kind::CallExpr)
+    //!         .and(is_expansion_in_main_file())
+    //!         .and(has_descendant(
+    //!             kind(Kind::DeclRefExpr)
+    //!                 .and(node(|clang: &Clang| {
+    //!                     clang.referenced_decl.as_ref().and_then(|decl| decl.name.as_deref())
+    //!                         == Some("exit")
+    //!                 })),
+    //!         ))
+    //!         .and(has_ancestor(kind(Kind::CXXDestructorDecl).bind("dtor")))
+    //!         .bind("call");
+    //!
+    //!     for found in matcher.find_all(root) {
+    //!         let call = found.get("call").unwrap();
+    //!         let dtor = found.get("dtor").unwrap();
+    //!         # let _ = (call, dtor);
+    //!     }
+    //! }
+    //! ```
+
+    pub use crate::matching::{
+        any, has_ancestor, has_child, has_descendant, in_file, is_expansion_in_main_file, kind,
+        node, not, Match, Matcher,
+    };
+}
 
 /// <font style="font-variant:small-caps">syntax tree root</font>
-#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+#[derive(Debug)]
 pub struct Node<T> {
     pub id: Id,
     pub kind: T,
@@ -550,7 +733,11 @@ where
     {
         let _dedup = dedup::activate();
         let mut map = serializer.serialize_map(None)?;
-        map.serialize_entry("id", &self.id)?;
+        // Clang prints no id for the placeholder `{}` it puts in place of an
+        // absent child, which deserializes as Id::NULL.
+        if self.id != Id::NULL || !fidelity::enabled() {
+            map.serialize_entry("id", &self.id)?;
+        }
         T::serialize(&self.kind, NodeSerializer::new(&mut map))?;
         if !self.inner.is_empty() {
             map.serialize_entry("inner", &self.inner)?;
@@ -558,3 +745,91 @@ where
         map.end()
     }
 }
+
+// The trait impls below avoid recursing through `inner`, so that dropping,
+// cloning, comparing or hashing a syntax tree uses a constant amount of stack
+// regardless of how deeply nested the tree is.
+
+impl<T> Drop for Node<T> {
+    fn drop(&mut self) {
+        let mut stack = mem::take(&mut self.inner);
+        while let Some(mut node) = stack.pop() {
+            stack.append(&mut node.inner);
+        }
+    }
+}
+
+impl<T> Clone for Node<T>
+where
+    T: Clone,
+{
+    fn clone(&self) -> Self {
+        struct Frame<'a, T> {
+            node: &'a Node<T>,
+            children: slice::Iter<'a, Node<T>>,
+            inner: Vec<Node<T>>,
+        }
+
+        impl<'a, T> Frame<'a, T> {
+            fn new(node: &'a Node<T>) -> Self {
+                Frame {
+                    node,
+                    children: node.inner.iter(),
+                    inner: Vec::with_capacity(node.inner.len()),
+                }
+            }
+        }
+
+        let mut stack = vec![Frame::new(self)];
+        loop {
+            let top = stack.last_mut().unwrap();
+            if let Some(child) = top.children.next() {
+                stack.push(Frame::new(child));
+                continue;
+            }
+            let frame = stack.pop().unwrap();
+            let node = Node {
+                id: frame.node.id,
+                kind: frame.node.kind.clone(),
+                inner: frame.inner,
+            };
+            match stack.last_mut() {
+                Some(parent) => parent.inner.push(node),
+                None => return node,
+            }
+        }
+    }
+}
+
+impl<T> PartialEq for Node<T>
+where
+    T: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        let mut stack = vec![(self, other)];
+        while let Some((a, b)) = stack.pop() {
+            if a.id != b.id || a.kind != b.kind || a.inner.len() != b.inner.len() {
+                return false;
+            }
+            stack.extend(a.inner.iter().zip(&b.inner));
+        }
+        true
+    }
+}
+
+impl<T> Eq for Node<T> where T: Eq {}
+
+impl<T> Hash for Node<T>
+where
+    T: Hash,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let mut stack = vec![self];
+        while let Some(node) = stack.pop() {
+            node.id.hash(state);
+            node.kind.hash(state);
+            node.inner.len().hash(state);
+            stack.extend(node.inner.iter().rev());
+        }
+    }
+}
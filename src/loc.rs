@@ -1,8 +1,16 @@
+use crate::fidelity;
+use crate::include;
 use crate::intern::InternVisitor;
-use serde::de::{Deserialize, Deserializer, Error, IgnoredAny, MapAccess, Visitor};
+use foldhash::HashMap;
+use serde::de::{
+    Deserialize, DeserializeSeed, Deserializer, Error, IgnoredAny, MapAccess, Visitor,
+};
 use serde::ser::{Serialize, SerializeMap, Serializer};
 use std::cell::{Cell, RefCell};
-use std::fmt::{self, Debug};
+use std::fmt::{self, Debug, Display};
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::ops::Deref;
 use std::sync::Arc;
 
 #[derive(Default, Clone, Eq, PartialEq, Hash)]
@@ -26,21 +34,98 @@ pub struct BareSourceLocation {
     pub presumed_line: Option<usize>,
     pub col: usize,
     pub tok_len: usize,
+    /// The chain of files by which this file was included, for every location
+    /// in a file other than the main file.
     pub included_from: Option<IncludedFrom>,
     pub is_macro_arg_expansion: bool,
 }
 
-#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+/// The file containing the `#include` through which a location's file was
+/// entered, and recursively the file which included that one.
+///
+/// Clang prints only the immediate includer. The rest of the chain is filled
+/// in during deserialization from where each of the includers was itself
+/// included, as printed earlier in the same dump.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 pub struct IncludedFrom {
     pub included_from: Option<Box<IncludedFrom>>,
     pub file: Arc<str>,
 }
 
+/// Variant of [`SourceRange`] whose file paths borrow from the input.
+///
+/// Use it in place of `SourceRange` in a node kind that borrows from the JSON,
+/// with `#[serde(borrow)]`. Clang prints each location's file only when it
+/// differs from the previous location's, so a path is borrowed only where it
+/// was printed and is otherwise shared with an earlier location, as in
+/// `SourceRange`. To fill in those omitted paths, deserialization still keeps
+/// one shared copy of each distinct path in the dump.
+#[derive(Default, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct BorrowedSourceRange<'a> {
+    pub begin: BorrowedSourceLocation<'a>,
+    pub end: BorrowedSourceLocation<'a>,
+}
+
+/// Variant of [`SourceLocation`] whose file paths borrow from the input.
+#[derive(Default, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct BorrowedSourceLocation<'a> {
+    pub spelling_loc: Option<BorrowedBareSourceLocation<'a>>,
+    pub expansion_loc: Option<BorrowedBareSourceLocation<'a>>,
+}
+
+/// Variant of [`BareSourceLocation`] whose file paths borrow from the input.
+///
+/// Both `file` and `presumed_file` are borrowed where they were printed.
+/// `included_from` is not, because all but the immediate includer are filled
+/// in from earlier locations.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct BorrowedBareSourceLocation<'a> {
+    pub offset: usize,
+    pub file: FilePath<'a>,
+    pub line: usize,
+    pub presumed_file: Option<FilePath<'a>>,
+    pub presumed_line: Option<usize>,
+    pub col: usize,
+    pub tok_len: usize,
+    pub included_from: Option<IncludedFrom>,
+    pub is_macro_arg_expansion: bool,
+}
+
+/// File path of a [`BorrowedBareSourceLocation`]. Dereferences to `str`.
+#[derive(Clone)]
+pub enum FilePath<'a> {
+    /// Path printed in this location, pointing into the input.
+    Borrowed(&'a str),
+    /// Path carried over from an earlier location, or one which could not be
+    /// borrowed because it contains escape sequences or the input is not a
+    /// borrowable slice.
+    Shared(Arc<str>),
+}
+
+// Shared form of an include chain, kept per file in INCLUDE_STACK so that the
+// chain is built once each time the file is entered from a different place.
+struct Chain {
+    file: Arc<str>,
+    included_from: Option<Arc<Chain>>,
+}
+
 thread_local! {
     static LAST_LOC_FILENAME: RefCell<Arc<str>> = RefCell::new(Arc::from(""));
     static LAST_LOC_LINE: Cell<usize> = const { Cell::new(0) };
+    // The effective location as adjusted by `#line` directives, which Clang
+    // also omits when unchanged since the previous location.
+    static LAST_LOC_PRESUMED_FILENAME: RefCell<Arc<str>> = RefCell::new(Arc::from(""));
+    static LAST_LOC_PRESUMED_LINE: Cell<usize> = const { Cell::new(0) };
+    // For each file, the chain of files through which it was most recently
+    // included, as reconstructed from the immediate includers printed by Clang.
+    static INCLUDE_STACK: RefCell<HashMap<Arc<str>, Arc<Chain>>> = RefCell::new(HashMap::default());
+    // While a snapshot is outstanding, the previous value of every entry of
+    // INCLUDE_STACK which has been overwritten since the snapshot was taken.
+    static INCLUDE_STACK_UNDO: RefCell<Option<IncludeStackUndo>> = const { RefCell::new(None) };
 }
 
+type IncludeStackUndo = Vec<(Arc<str>, Option<Arc<Chain>>)>;
+
 pub(crate) fn thread_local_reset() {
     LAST_LOC_FILENAME.with(|last_loc_filename| {
         let mut last_loc_filename = last_loc_filename.borrow_mut();
@@ -49,6 +134,161 @@ pub(crate) fn thread_local_reset() {
         }
     });
     LAST_LOC_LINE.with(|last_loc_line| last_loc_line.set(0));
+    LAST_LOC_PRESUMED_FILENAME.with(|last_loc_presumed_filename| {
+        let mut last_loc_presumed_filename = last_loc_presumed_filename.borrow_mut();
+        if !last_loc_presumed_filename.is_empty() {
+            *last_loc_presumed_filename = Arc::from("");
+        }
+    });
+    LAST_LOC_PRESUMED_LINE.with(|last_loc_presumed_line| last_loc_presumed_line.set(0));
+    INCLUDE_STACK.with(|include_stack| include_stack.borrow_mut().clear());
+}
+
+// The "same as previous" state, for deserializers which need to suspend and
+// later resume deserializing one dump while others may run on the thread.
+#[derive(Default, Clone)]
+pub(crate) struct ThreadLocalState {
+    filename: Option<Arc<str>>,
+    line: usize,
+    presumed_filename: Option<Arc<str>>,
+    presumed_line: usize,
+    include_stack: HashMap<Arc<str>, Arc<Chain>>,
+}
+
+pub(crate) fn thread_local_replace(state: ThreadLocalState) -> ThreadLocalState {
+    let filename = state.filename.unwrap_or_else(|| Arc::from(""));
+    let filename = LAST_LOC_FILENAME.with(|last_loc_filename| last_loc_filename.replace(filename));
+    let line = LAST_LOC_LINE.with(|last_loc_line| last_loc_line.replace(state.line));
+    let presumed_filename = state.presumed_filename.unwrap_or_else(|| Arc::from(""));
+    let presumed_filename = LAST_LOC_PRESUMED_FILENAME
+        .with(|last_loc_presumed_filename| last_loc_presumed_filename.replace(presumed_filename));
+    let presumed_line = LAST_LOC_PRESUMED_LINE
+        .with(|last_loc_presumed_line| last_loc_presumed_line.replace(state.presumed_line));
+    let include_stack =
+        INCLUDE_STACK.with(|include_stack| include_stack.replace(state.include_stack));
+    ThreadLocalState {
+        filename: Some(filename),
+        line,
+        presumed_filename: Some(presumed_filename),
+        presumed_line,
+        include_stack,
+    }
+}
+
+// Point to which the "same as previous" state can be rolled back, for reading
+// ahead in the input.
+pub(crate) struct Snapshot {
+    filename: Arc<str>,
+    line: usize,
+    presumed_filename: Arc<str>,
+    presumed_line: usize,
+}
+
+pub(crate) fn thread_local_snapshot() -> Snapshot {
+    let filename =
+        LAST_LOC_FILENAME.with(|last_loc_filename| Arc::clone(&last_loc_filename.borrow()));
+    let line = LAST_LOC_LINE.with(Cell::get);
+    let presumed_filename = LAST_LOC_PRESUMED_FILENAME
+        .with(|last_loc_presumed_filename| Arc::clone(&last_loc_presumed_filename.borrow()));
+    let presumed_line = LAST_LOC_PRESUMED_LINE.with(Cell::get);
+    INCLUDE_STACK_UNDO.with(|undo| *undo.borrow_mut() = Some(Vec::new()));
+    Snapshot {
+        filename,
+        line,
+        presumed_filename,
+        presumed_line,
+    }
+}
+
+pub(crate) fn thread_local_rollback(snapshot: Snapshot) {
+    LAST_LOC_FILENAME.with(|last_loc_filename| *last_loc_filename.borrow_mut() = snapshot.filename);
+    LAST_LOC_LINE.with(|last_loc_line| last_loc_line.set(snapshot.line));
+    LAST_LOC_PRESUMED_FILENAME.with(|last_loc_presumed_filename| {
+        *last_loc_presumed_filename.borrow_mut() = snapshot.presumed_filename;
+    });
+    LAST_LOC_PRESUMED_LINE
+        .with(|last_loc_presumed_line| last_loc_presumed_line.set(snapshot.presumed_line));
+    let undo = INCLUDE_STACK_UNDO.with(|undo| undo.borrow_mut().take().unwrap_or_default());
+    INCLUDE_STACK.with(|include_stack| {
+        let mut include_stack = include_stack.borrow_mut();
+        for (file, prev) in undo.into_iter().rev() {
+            match prev {
+                Some(prev) => include_stack.insert(file, prev),
+                None => include_stack.remove(&file),
+            };
+        }
+    });
+}
+
+// Extends the immediate includer of `file` printed by Clang into the complete
+// chain of includers, and remembers the chain for later locations.
+fn include_stack(file: &Arc<str>, included_from: IncludedFrom) -> IncludedFrom {
+    INCLUDE_STACK.with(|include_stack| {
+        let mut include_stack = include_stack.borrow_mut();
+        let chain = include_stack.get(&included_from.file).cloned();
+        if let Some(current) = include_stack.get(file) {
+            let same_chain = match (&current.included_from, &chain) {
+                (Some(current), Some(chain)) => Arc::ptr_eq(current, chain),
+                (None, None) => true,
+                (Some(_), None) | (None, Some(_)) => false,
+            };
+            if same_chain && current.file == included_from.file {
+                return current.to_included_from();
+            }
+        }
+        let chain = match chain {
+            // Headers which include themselves, directly or indirectly, would
+            // otherwise make the chain grow every time the cycle is printed.
+            Some(chain) if chain.contains(file) => chain.truncate(file),
+            chain => chain,
+        };
+        let current = Arc::new(Chain {
+            file: included_from.file,
+            included_from: chain,
+        });
+        let included_from = current.to_included_from();
+        let prev = include_stack.insert(Arc::clone(file), current);
+        INCLUDE_STACK_UNDO.with(|undo| {
+            if let Some(undo) = &mut *undo.borrow_mut() {
+                undo.push((Arc::clone(file), prev));
+            }
+        });
+        included_from
+    })
+}
+
+impl Chain {
+    fn to_included_from(&self) -> IncludedFrom {
+        IncludedFrom {
+            included_from: self
+                .included_from
+                .as_ref()
+                .map(|chain| Box::new(chain.to_included_from())),
+            file: Arc::clone(&self.file),
+        }
+    }
+
+    fn contains(&self, file: &str) -> bool {
+        *self.file == *file
+            || self
+                .included_from
+                .as_ref()
+                .map_or(false, |chain| chain.contains(file))
+    }
+
+    // Copy of the chain up to but not including the first link to `file`.
+    fn truncate(&self, file: &str) -> Option<Arc<Chain>> {
+        if *self.file == *file {
+            return None;
+        }
+        Some(Arc::new(Chain {
+            file: Arc::clone(&self.file),
+            included_from: self
+                .included_from
+                .as_ref()
+                .and_then(|chain| chain.truncate(file)),
+        }))
+    }
 }
 
 enum SourceLocationField {
@@ -65,165 +305,264 @@ enum SourceLocationField {
     IsMacroArgExpansion,
 }
 
-impl<'de> Deserialize<'de> for SourceLocation {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+// Shared by the owned and borrowed variants of the location types, which
+// differ only in how the file path is stored.
+trait DeBareSourceLocation<'de>: Sized + Clone {
+    fn de_rest<M>(map: M) -> Result<Self, M::Error>
     where
-        D: Deserializer<'de>,
+        M: MapAccess<'de>;
+}
+
+impl<'de> DeBareSourceLocation<'de> for BareSourceLocation {
+    fn de_rest<M>(map: M) -> Result<Self, M::Error>
+    where
+        M: MapAccess<'de>,
     {
-        struct SourceLocationVisitor;
+        let (loc, _borrowed) = de_rest_of_bare_source_location(map)?;
+        Ok(loc)
+    }
+}
 
-        impl<'de> Visitor<'de> for SourceLocationVisitor {
-            type Value = SourceLocation;
+impl<'de: 'a, 'a> DeBareSourceLocation<'de> for BorrowedBareSourceLocation<'a> {
+    fn de_rest<M>(map: M) -> Result<Self, M::Error>
+    where
+        M: MapAccess<'de>,
+    {
+        let (loc, borrowed) = de_rest_of_bare_source_location(map)?;
+        Ok(BorrowedBareSourceLocation::new(loc, borrowed))
+    }
+}
 
-            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                formatter.write_str("struct SourceLocation")
-            }
+struct SourceLocationVisitor<B>(PhantomData<B>);
 
-            fn visit_map<M>(self, mut map: M) -> Result<Self::Value, M::Error>
-            where
-                M: MapAccess<'de>,
-            {
+impl<'de, B> Visitor<'de> for SourceLocationVisitor<B>
+where
+    B: DeBareSourceLocation<'de>,
+{
+    type Value = (Option<B>, Option<B>);
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("struct SourceLocation")
+    }
+
+    fn visit_map<M>(self, mut map: M) -> Result<Self::Value, M::Error>
+    where
+        M: MapAccess<'de>,
+    {
+        match map.next_key()? {
+            None => Ok((None, None)),
+            Some(SourceLocationField::SpellingLoc) => {
+                let spelling_loc = map.next_value_seed(BareSourceLocationVisitor(PhantomData))?;
                 match map.next_key()? {
-                    None => Ok(SourceLocation::default()),
-                    Some(SourceLocationField::SpellingLoc) => {
-                        let spelling_loc: BareSourceLocation = map.next_value()?;
-                        match map.next_key()? {
-                            None => Err(Error::missing_field("expansionLoc")),
-                            Some(SourceLocationField::ExpansionLoc) => {
-                                let expansion_loc: BareSourceLocation = map.next_value()?;
-                                Ok(SourceLocation {
-                                    spelling_loc: Some(spelling_loc),
-                                    expansion_loc: Some(expansion_loc),
-                                })
-                            }
-                            Some(other) => Err(other.unexpected()),
-                        }
-                    }
-                    Some(SourceLocationField::Offset) => {
-                        let loc = de_rest_of_bare_source_location(map)?;
-                        Ok(SourceLocation {
-                            spelling_loc: Some(loc.clone()),
-                            expansion_loc: Some(loc),
-                        })
+                    None => Err(Error::missing_field("expansionLoc")),
+                    Some(SourceLocationField::ExpansionLoc) => {
+                        let expansion_loc =
+                            map.next_value_seed(BareSourceLocationVisitor(PhantomData))?;
+                        Ok((Some(spelling_loc), Some(expansion_loc)))
                     }
                     Some(other) => Err(other.unexpected()),
                 }
             }
+            Some(SourceLocationField::Offset) => {
+                let loc = B::de_rest(map)?;
+                Ok((Some(loc.clone()), Some(loc)))
+            }
+            Some(other) => Err(other.unexpected()),
         }
+    }
+}
+
+impl<'de, B> DeserializeSeed<'de> for SourceLocationVisitor<B>
+where
+    B: DeBareSourceLocation<'de>,
+{
+    type Value = (Option<B>, Option<B>);
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_map(self)
+    }
+}
 
-        deserializer.deserialize_map(SourceLocationVisitor)
+impl<'de> Deserialize<'de> for SourceLocation {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let (spelling_loc, expansion_loc) =
+            SourceLocationVisitor(PhantomData).deserialize(deserializer)?;
+        Ok(SourceLocation {
+            spelling_loc,
+            expansion_loc,
+        })
     }
 }
 
-impl<'de> Deserialize<'de> for BareSourceLocation {
+impl<'de: 'a, 'a> Deserialize<'de> for BorrowedSourceLocation<'a> {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: Deserializer<'de>,
     {
-        struct BareSourceLocationVisitor;
+        let (spelling_loc, expansion_loc) =
+            SourceLocationVisitor(PhantomData).deserialize(deserializer)?;
+        Ok(BorrowedSourceLocation {
+            spelling_loc,
+            expansion_loc,
+        })
+    }
+}
 
-        impl<'de> Visitor<'de> for BareSourceLocationVisitor {
-            type Value = BareSourceLocation;
+struct BareSourceLocationVisitor<B>(PhantomData<B>);
 
-            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                formatter.write_str("struct BareSourceLocation")
-            }
+impl<'de, B> Visitor<'de> for BareSourceLocationVisitor<B>
+where
+    B: DeBareSourceLocation<'de>,
+{
+    type Value = B;
 
-            fn visit_map<M>(self, mut map: M) -> Result<Self::Value, M::Error>
-            where
-                M: MapAccess<'de>,
-            {
-                match map.next_key()? {
-                    None => Err(Error::missing_field("offset")),
-                    Some(SourceLocationField::Offset) => de_rest_of_bare_source_location(map),
-                    Some(other) => Err(other.unexpected()),
-                }
-            }
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("struct BareSourceLocation")
+    }
+
+    fn visit_map<M>(self, mut map: M) -> Result<Self::Value, M::Error>
+    where
+        M: MapAccess<'de>,
+    {
+        match map.next_key()? {
+            None => Err(Error::missing_field("offset")),
+            Some(SourceLocationField::Offset) => B::de_rest(map),
+            Some(other) => Err(other.unexpected()),
         }
+    }
+}
+
+impl<'de, B> DeserializeSeed<'de> for BareSourceLocationVisitor<B>
+where
+    B: DeBareSourceLocation<'de>,
+{
+    type Value = B;
 
-        deserializer.deserialize_map(BareSourceLocationVisitor)
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_map(self)
     }
 }
 
-impl<'de> Deserialize<'de> for SourceRange {
+impl<'de> Deserialize<'de> for BareSourceLocation {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: Deserializer<'de>,
     {
-        enum SourceRangeField {
-            Begin,
-            End,
-        }
+        BareSourceLocationVisitor(PhantomData).deserialize(deserializer)
+    }
+}
 
-        struct SourceRangeFieldVisitor;
+impl<'de: 'a, 'a> Deserialize<'de> for BorrowedBareSourceLocation<'a> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        BareSourceLocationVisitor(PhantomData).deserialize(deserializer)
+    }
+}
 
-        impl<'de> Visitor<'de> for SourceRangeFieldVisitor {
-            type Value = SourceRangeField;
+enum SourceRangeField {
+    Begin,
+    End,
+}
 
-            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                formatter.write_str("field identifier")
-            }
+struct SourceRangeFieldVisitor;
 
-            fn visit_str<E>(self, field: &str) -> Result<Self::Value, E>
-            where
-                E: Error,
-            {
-                static FIELDS: &[&str] = &["begin", "end"];
-                match field {
-                    "begin" => Ok(SourceRangeField::Begin),
-                    "end" => Ok(SourceRangeField::End),
-                    _ => Err(E::unknown_field(field, FIELDS)),
-                }
-            }
-        }
+impl<'de> Visitor<'de> for SourceRangeFieldVisitor {
+    type Value = SourceRangeField;
 
-        impl<'de> Deserialize<'de> for SourceRangeField {
-            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-            where
-                D: Deserializer<'de>,
-            {
-                deserializer.deserialize_identifier(SourceRangeFieldVisitor)
-            }
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("field identifier")
+    }
+
+    fn visit_str<E>(self, field: &str) -> Result<Self::Value, E>
+    where
+        E: Error,
+    {
+        static FIELDS: &[&str] = &["begin", "end"];
+        match field {
+            "begin" => Ok(SourceRangeField::Begin),
+            "end" => Ok(SourceRangeField::End),
+            _ => Err(E::unknown_field(field, FIELDS)),
         }
+    }
+}
 
-        struct SourceRangeVisitor;
+impl<'de> Deserialize<'de> for SourceRangeField {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_identifier(SourceRangeFieldVisitor)
+    }
+}
 
-        impl<'de> Visitor<'de> for SourceRangeVisitor {
-            type Value = SourceRange;
+struct SourceRangeVisitor<L>(PhantomData<L>);
 
-            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                formatter.write_str("struct SourceRange")
-            }
+impl<'de, L> Visitor<'de> for SourceRangeVisitor<L>
+where
+    L: Deserialize<'de>,
+{
+    type Value = (L, L);
 
-            fn visit_map<M>(self, mut map: M) -> Result<Self::Value, M::Error>
-            where
-                M: MapAccess<'de>,
-            {
-                let mut begin = None;
-                let mut end = None;
-                while let Some(key) = map.next_key()? {
-                    match key {
-                        SourceRangeField::Begin => {
-                            if begin.is_some() {
-                                return Err(Error::duplicate_field("begin"));
-                            }
-                            begin = Some(map.next_value()?);
-                        }
-                        SourceRangeField::End => {
-                            if end.is_some() {
-                                return Err(Error::duplicate_field("end"));
-                            }
-                            end = Some(map.next_value()?);
-                        }
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("struct SourceRange")
+    }
+
+    fn visit_map<M>(self, mut map: M) -> Result<Self::Value, M::Error>
+    where
+        M: MapAccess<'de>,
+    {
+        let mut begin = None;
+        let mut end = None;
+        while let Some(key) = map.next_key()? {
+            match key {
+                SourceRangeField::Begin => {
+                    if begin.is_some() {
+                        return Err(Error::duplicate_field("begin"));
                     }
+                    begin = Some(map.next_value()?);
+                }
+                SourceRangeField::End => {
+                    if end.is_some() {
+                        return Err(Error::duplicate_field("end"));
+                    }
+                    end = Some(map.next_value()?);
                 }
-                let begin = begin.ok_or_else(|| Error::missing_field("begin"))?;
-                let end = end.ok_or_else(|| Error::missing_field("end"))?;
-                Ok(SourceRange { begin, end })
             }
         }
+        let begin = begin.ok_or_else(|| Error::missing_field("begin"))?;
+        let end = end.ok_or_else(|| Error::missing_field("end"))?;
+        Ok((begin, end))
+    }
+}
+
+impl<'de> Deserialize<'de> for SourceRange {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let (begin, end) = deserializer.deserialize_map(SourceRangeVisitor(PhantomData))?;
+        Ok(SourceRange { begin, end })
+    }
+}
 
-        deserializer.deserialize_map(SourceRangeVisitor)
+impl<'de: 'a, 'a> Deserialize<'de> for BorrowedSourceRange<'a> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let (begin, end) = deserializer.deserialize_map(SourceRangeVisitor(PhantomData))?;
+        Ok(BorrowedSourceRange { begin, end })
     }
 }
 
@@ -364,7 +703,11 @@ impl<'de> Deserialize<'de> for SourceLocationField {
     }
 }
 
-fn de_rest_of_bare_source_location<'de, M>(mut map: M) -> Result<BareSourceLocation, M::Error>
+// Also returns the file path if it was printed in this location and can be
+// borrowed from the input.
+fn de_rest_of_bare_source_location<'de, M>(
+    mut map: M,
+) -> Result<(BareSourceLocation, BorrowedPaths<'de>), M::Error>
 where
     M: MapAccess<'de>,
 {
@@ -373,6 +716,10 @@ where
     let mut file = None;
     let mut line = None;
     let mut presumed_file = None;
+    let mut borrowed = BorrowedPaths {
+        file: None,
+        presumed_file: None,
+    };
     let mut presumed_line = None;
     let mut col = None;
     let mut tok_len = None;
@@ -382,10 +729,16 @@ where
     while let Some(field) = map.next_key()? {
         match field {
             SourceLocationField::Offset => return Err(Error::duplicate_field("offset")),
-            SourceLocationField::File => file = Some(map.next_value_seed(InternVisitor)?),
+            SourceLocationField::File => {
+                let (interned, borrowed_file) = map.next_value_seed(FileVisitor)?;
+                file = Some(interned);
+                borrowed.file = borrowed_file;
+            }
             SourceLocationField::Line => line = Some(map.next_value()?),
             SourceLocationField::PresumedFile => {
-                presumed_file = Some(map.next_value_seed(InternVisitor)?);
+                let (interned, borrowed_file) = map.next_value_seed(FileVisitor)?;
+                presumed_file = Some(interned);
+                borrowed.presumed_file = borrowed_file;
             }
             SourceLocationField::PresumedLine => presumed_line = Some(map.next_value()?),
             SourceLocationField::Col => col = Some(map.next_value()?),
@@ -400,6 +753,12 @@ where
         }
     }
 
+    // Whether the physical file and line are both unchanged since the previous
+    // location, which is when the previous presumed location is most likely
+    // still in effect.
+    let same_file = file.is_none();
+    let same_line = same_file && line.is_none();
+
     let file = LAST_LOC_FILENAME.with(|last_loc_filename| match file {
         Some(file) => {
             *last_loc_filename.borrow_mut() = Arc::clone(&file);
@@ -416,10 +775,35 @@ where
         None => last_loc_line.get(),
     });
 
+    // Clang omits the presumed file and line when they are equal to either the
+    // physical ones or the previous presumed ones, without saying which.
+    let presumed_line = LAST_LOC_PRESUMED_LINE.with(|last_loc_presumed_line| {
+        let resolved = match presumed_line {
+            Some(presumed_line) => presumed_line,
+            None if same_line => last_loc_presumed_line.get(),
+            None => line,
+        };
+        last_loc_presumed_line.set(resolved);
+        Some(resolved).filter(|presumed_line| *presumed_line != line)
+    });
+    let presumed_file = LAST_LOC_PRESUMED_FILENAME.with(|last_loc_presumed_filename| {
+        let mut last_loc_presumed_filename = last_loc_presumed_filename.borrow_mut();
+        match presumed_file {
+            Some(presumed_file) => *last_loc_presumed_filename = presumed_file,
+            None if same_line || (same_file && presumed_line.is_some()) => {}
+            None => *last_loc_presumed_filename = Arc::clone(&file),
+        }
+        Some(Arc::clone(&last_loc_presumed_filename)).filter(|presumed_file| *presumed_file != file)
+    });
+
     let col = col.ok_or_else(|| Error::missing_field("col"))?;
     let tok_len = tok_len.ok_or_else(|| Error::missing_field("tokLen"))?;
+    // Clang prints the immediate includer on every location in an included
+    // file, independently of whether the file and line were omitted.
+    let included_from = included_from.map(|included_from| include_stack(&file, included_from));
+    include::record(&file, offset, included_from.as_ref());
 
-    Ok(BareSourceLocation {
+    let loc = BareSourceLocation {
         offset,
         file,
         line,
@@ -429,7 +813,59 @@ where
         tok_len,
         included_from,
         is_macro_arg_expansion,
-    })
+    };
+    Ok((loc, borrowed))
+}
+
+// The paths printed in a location, borrowed from the input if the
+// deserializer can lend them.
+struct BorrowedPaths<'de> {
+    file: Option<&'de str>,
+    presumed_file: Option<&'de str>,
+}
+
+// The interned file path, and the same path borrowed from the input if the
+// deserializer can lend it.
+struct FileVisitor;
+
+impl<'de> Visitor<'de> for FileVisitor {
+    type Value = (Arc<str>, Option<&'de str>);
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a string")
+    }
+
+    fn visit_borrowed_str<E>(self, string: &'de str) -> Result<Self::Value, E>
+    where
+        E: Error,
+    {
+        Ok((InternVisitor.visit_str(string)?, Some(string)))
+    }
+
+    fn visit_str<E>(self, string: &str) -> Result<Self::Value, E>
+    where
+        E: Error,
+    {
+        Ok((InternVisitor.visit_str(string)?, None))
+    }
+
+    fn visit_string<E>(self, string: String) -> Result<Self::Value, E>
+    where
+        E: Error,
+    {
+        Ok((InternVisitor.visit_string(string)?, None))
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for FileVisitor {
+    type Value = (Arc<str>, Option<&'de str>);
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_str(self)
+    }
 }
 
 impl SourceLocationField {
@@ -465,6 +901,141 @@ impl SourceLocationField {
     }
 }
 
+impl<'a> BorrowedBareSourceLocation<'a> {
+    fn new(loc: BareSourceLocation, borrowed: BorrowedPaths<'a>) -> Self {
+        let BareSourceLocation {
+            offset,
+            file,
+            line,
+            presumed_file,
+            presumed_line,
+            col,
+            tok_len,
+            included_from,
+            is_macro_arg_expansion,
+        } = loc;
+        BorrowedBareSourceLocation {
+            offset,
+            file: match borrowed.file {
+                Some(borrowed_file) => FilePath::Borrowed(borrowed_file),
+                None => FilePath::Shared(file),
+            },
+            line,
+            // A presumed file which was printed is the one which remains after
+            // comparing it against the physical file.
+            presumed_file: presumed_file.map(|presumed_file| match borrowed.presumed_file {
+                Some(borrowed_file) => FilePath::Borrowed(borrowed_file),
+                None => FilePath::Shared(presumed_file),
+            }),
+            presumed_line,
+            col,
+            tok_len,
+            included_from,
+            is_macro_arg_expansion,
+        }
+    }
+}
+
+impl From<BorrowedSourceRange<'_>> for SourceRange {
+    fn from(range: BorrowedSourceRange) -> Self {
+        SourceRange {
+            begin: SourceLocation::from(range.begin),
+            end: SourceLocation::from(range.end),
+        }
+    }
+}
+
+impl From<BorrowedSourceLocation<'_>> for SourceLocation {
+    fn from(loc: BorrowedSourceLocation) -> Self {
+        SourceLocation {
+            spelling_loc: loc.spelling_loc.map(BareSourceLocation::from),
+            expansion_loc: loc.expansion_loc.map(BareSourceLocation::from),
+        }
+    }
+}
+
+impl From<BorrowedBareSourceLocation<'_>> for BareSourceLocation {
+    fn from(loc: BorrowedBareSourceLocation) -> Self {
+        let BorrowedBareSourceLocation {
+            offset,
+            file,
+            line,
+            presumed_file,
+            presumed_line,
+            col,
+            tok_len,
+            included_from,
+            is_macro_arg_expansion,
+        } = loc;
+        BareSourceLocation {
+            offset,
+            file: file.into_shared(),
+            line,
+            presumed_file: presumed_file.map(FilePath::into_shared),
+            presumed_line,
+            col,
+            tok_len,
+            included_from,
+            is_macro_arg_expansion,
+        }
+    }
+}
+
+impl Deref for FilePath<'_> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        match self {
+            FilePath::Borrowed(file) => file,
+            FilePath::Shared(file) => file,
+        }
+    }
+}
+
+impl AsRef<str> for FilePath<'_> {
+    fn as_ref(&self) -> &str {
+        self
+    }
+}
+
+impl PartialEq for FilePath<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl Eq for FilePath<'_> {}
+
+impl PartialEq<str> for FilePath<'_> {
+    fn eq(&self, other: &str) -> bool {
+        **self == *other
+    }
+}
+
+impl<'b> PartialEq<&'b str> for FilePath<'_> {
+    fn eq(&self, other: &&'b str) -> bool {
+        **self == **other
+    }
+}
+
+impl Hash for FilePath<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state);
+    }
+}
+
+impl Display for FilePath<'_> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        Display::fmt(&**self, formatter)
+    }
+}
+
+impl Debug for FilePath<'_> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        Debug::fmt(&**self, formatter)
+    }
+}
+
 impl Serialize for SourceRange {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -482,108 +1053,227 @@ impl Serialize for SourceLocation {
     where
         S: Serializer,
     {
-        fn same_bare_source_location(
-            spelling_loc: &BareSourceLocation,
-            expansion_loc: &BareSourceLocation,
-        ) -> bool {
-            let BareSourceLocation {
-                offset: spelling_offset,
-                file: spelling_file,
-                line: spelling_line,
-                presumed_file: spelling_presumed_file,
-                presumed_line: spelling_presumed_line,
-                col: spelling_col,
-                tok_len: spelling_tok_len,
-                included_from: spelling_included_from,
-                is_macro_arg_expansion: spelling_is_macro_arg_expansion,
-            } = spelling_loc;
-            let BareSourceLocation {
-                offset: expansion_offset,
-                file: expansion_file,
-                line: expansion_line,
-                presumed_file: expansion_presumed_file,
-                presumed_line: expansion_presumed_line,
-                col: expansion_col,
-                tok_len: expansion_tok_len,
-                included_from: expansion_included_from,
-                is_macro_arg_expansion: expansion_is_macro_arg_expansion,
-            } = expansion_loc;
-            spelling_offset == expansion_offset
-                && spelling_file == expansion_file
-                && spelling_line == expansion_line
-                && spelling_presumed_file == expansion_presumed_file
-                && spelling_presumed_line == expansion_presumed_line
-                && spelling_col == expansion_col
-                && spelling_tok_len == expansion_tok_len
-                && same_opt_included_from(
-                    spelling_included_from.as_ref(),
-                    expansion_included_from.as_ref(),
-                )
-                && spelling_is_macro_arg_expansion == expansion_is_macro_arg_expansion
-        }
-
-        fn same_opt_included_from(
-            spelling_included_from: Option<&IncludedFrom>,
-            expansion_included_from: Option<&IncludedFrom>,
-        ) -> bool {
-            spelling_included_from
-                .zip(expansion_included_from)
-                .is_some_and(|(spelling_included_from, expansion_included_from)| {
-                    let IncludedFrom {
-                        included_from: spelling_included_from,
-                        file: spelling_file,
-                    } = spelling_included_from;
-                    let IncludedFrom {
-                        included_from: expansion_included_from,
-                        file: expansion_file,
-                    } = expansion_included_from;
-                    same_opt_included_from(
-                        spelling_included_from.as_ref().map(Box::as_ref),
-                        expansion_included_from.as_ref().map(Box::as_ref),
-                    ) && spelling_file == expansion_file
-                })
+        serialize_source_location(
+            self.spelling_loc.as_ref().map(BareSourceLocation::fields),
+            self.expansion_loc.as_ref().map(BareSourceLocation::fields),
+            serializer,
+        )
+    }
+}
+
+impl Serialize for BareSourceLocation {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.fields().serialize(serializer)
+    }
+}
+
+// The fields of either a BareSourceLocation or a BorrowedBareSourceLocation,
+// so that both are serialized by the same code without copying either into
+// the other.
+struct Fields<'a> {
+    offset: usize,
+    file: Path<'a>,
+    line: usize,
+    presumed_file: Option<Path<'a>>,
+    presumed_line: Option<usize>,
+    col: usize,
+    tok_len: usize,
+    included_from: Option<&'a IncludedFrom>,
+    is_macro_arg_expansion: bool,
+}
+
+#[derive(Copy, Clone)]
+enum Path<'a> {
+    Str(&'a str),
+    Shared(&'a Arc<str>),
+}
+
+impl<'a> Path<'a> {
+    fn as_str(self) -> &'a str {
+        match self {
+            Path::Str(path) => path,
+            Path::Shared(path) => path,
+        }
+    }
+
+    fn to_shared(self) -> Arc<str> {
+        match self {
+            Path::Str(path) => Arc::from(path),
+            Path::Shared(path) => Arc::clone(path),
+        }
+    }
+}
+
+impl FilePath<'_> {
+    fn into_shared(self) -> Arc<str> {
+        match self {
+            FilePath::Borrowed(path) => Arc::from(path),
+            FilePath::Shared(path) => path,
         }
+    }
 
-        let serialize_separately = self
-            .spelling_loc
-            .as_ref()
-            .zip(self.expansion_loc.as_ref())
-            .map_or(true, |(spelling_loc, expansion_loc)| {
-                !same_bare_source_location(spelling_loc, expansion_loc)
-            });
+    fn path(&self) -> Path<'_> {
+        match self {
+            FilePath::Borrowed(path) => Path::Str(path),
+            FilePath::Shared(path) => Path::Shared(path),
+        }
+    }
+}
 
-        if serialize_separately {
-            let mut map = serializer.serialize_map(None)?;
-            if let Some(spelling_loc) = &self.spelling_loc {
-                map.serialize_entry("spellingLoc", spelling_loc)?;
+impl BareSourceLocation {
+    fn fields(&self) -> Fields<'_> {
+        Fields {
+            offset: self.offset,
+            file: Path::Shared(&self.file),
+            line: self.line,
+            presumed_file: self.presumed_file.as_ref().map(Path::Shared),
+            presumed_line: self.presumed_line,
+            col: self.col,
+            tok_len: self.tok_len,
+            included_from: self.included_from.as_ref(),
+            is_macro_arg_expansion: self.is_macro_arg_expansion,
+        }
+    }
+}
+
+impl BorrowedBareSourceLocation<'_> {
+    fn fields(&self) -> Fields<'_> {
+        Fields {
+            offset: self.offset,
+            file: self.file.path(),
+            line: self.line,
+            presumed_file: self.presumed_file.as_ref().map(FilePath::path),
+            presumed_line: self.presumed_line,
+            col: self.col,
+            tok_len: self.tok_len,
+            included_from: self.included_from.as_ref(),
+            is_macro_arg_expansion: self.is_macro_arg_expansion,
+        }
+    }
+}
+
+fn serialize_source_location<S>(
+    spelling_loc: Option<Fields>,
+    expansion_loc: Option<Fields>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    fn same_bare_source_location(spelling_loc: &Fields, expansion_loc: &Fields) -> bool {
+        let Fields {
+            offset: spelling_offset,
+            file: spelling_file,
+            line: spelling_line,
+            presumed_file: spelling_presumed_file,
+            presumed_line: spelling_presumed_line,
+            col: spelling_col,
+            tok_len: spelling_tok_len,
+            included_from: spelling_included_from,
+            is_macro_arg_expansion: spelling_is_macro_arg_expansion,
+        } = spelling_loc;
+        let Fields {
+            offset: expansion_offset,
+            file: expansion_file,
+            line: expansion_line,
+            presumed_file: expansion_presumed_file,
+            presumed_line: expansion_presumed_line,
+            col: expansion_col,
+            tok_len: expansion_tok_len,
+            included_from: expansion_included_from,
+            is_macro_arg_expansion: expansion_is_macro_arg_expansion,
+        } = expansion_loc;
+        spelling_offset == expansion_offset
+            && spelling_file.as_str() == expansion_file.as_str()
+            && spelling_line == expansion_line
+            && spelling_presumed_file.map(Path::as_str) == expansion_presumed_file.map(Path::as_str)
+            && spelling_presumed_line == expansion_presumed_line
+            && spelling_col == expansion_col
+            && spelling_tok_len == expansion_tok_len
+            && match (spelling_included_from, expansion_included_from) {
+                // Neither is in a header, which is the case for every location
+                // in the main file. Clang writes those as a single location
+                // when spelling and expansion are the same.
+                (None, None) => fidelity::enabled(),
+                (spelling_included_from, expansion_included_from) => {
+                    same_opt_included_from(*spelling_included_from, *expansion_included_from)
+                }
             }
-            if let Some(expansion_loc) = &self.expansion_loc {
-                map.serialize_entry("expansionLoc", expansion_loc)?;
+            && spelling_is_macro_arg_expansion == expansion_is_macro_arg_expansion
+    }
+
+    fn same_opt_included_from(
+        spelling_included_from: Option<&IncludedFrom>,
+        expansion_included_from: Option<&IncludedFrom>,
+    ) -> bool {
+        match (spelling_included_from, expansion_included_from) {
+            (None, None) => true,
+            (Some(spelling_included_from), Some(expansion_included_from)) => {
+                let IncludedFrom {
+                    included_from: spelling_included_from,
+                    file: spelling_file,
+                } = spelling_included_from;
+                let IncludedFrom {
+                    included_from: expansion_included_from,
+                    file: expansion_file,
+                } = expansion_included_from;
+                same_opt_included_from(
+                    spelling_included_from.as_deref(),
+                    expansion_included_from.as_deref(),
+                ) && spelling_file == expansion_file
             }
-            map.end()
-        } else {
-            self.spelling_loc.serialize(serializer)
+            (Some(_), None) | (None, Some(_)) => false,
+        }
+    }
+
+    let serialize_separately = spelling_loc
+        .as_ref()
+        .zip(expansion_loc.as_ref())
+        .map_or(true, |(spelling_loc, expansion_loc)| {
+            !same_bare_source_location(spelling_loc, expansion_loc)
+        });
+
+    if serialize_separately {
+        let mut map = serializer.serialize_map(None)?;
+        if let Some(spelling_loc) = &spelling_loc {
+            map.serialize_entry("spellingLoc", spelling_loc)?;
+        }
+        if let Some(expansion_loc) = &expansion_loc {
+            map.serialize_entry("expansionLoc", expansion_loc)?;
         }
+        map.end()
+    } else {
+        spelling_loc.serialize(serializer)
     }
 }
 
-impl Serialize for BareSourceLocation {
+impl Serialize for Fields<'_> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
+        let file = self.file.as_str();
         let mut map = serializer.serialize_map(None)?;
         map.serialize_entry("offset", &self.offset)?;
         if LAST_LOC_FILENAME.with(|last_loc_filename| {
             let mut last_loc_filename = last_loc_filename.borrow_mut();
-            if *last_loc_filename == self.file {
+            if **last_loc_filename == *file {
                 false
             } else {
-                *last_loc_filename = Arc::clone(&self.file);
+                *last_loc_filename = self.file.to_shared();
                 true
             }
         }) {
-            map.serialize_entry("file", &*self.file)?;
+            if fidelity::enabled() {
+                // The line is relative to the new file from here on, which is
+                // how Clang decides whether to print the line of the next
+                // location.
+                LAST_LOC_LINE.with(|last_loc_line| last_loc_line.set(self.line));
+            }
+            map.serialize_entry("file", file)?;
             map.serialize_entry("line", &self.line)?;
         } else if LAST_LOC_LINE.with(|last_loc_line| {
             if last_loc_line.get() == self.line {
@@ -595,16 +1285,39 @@ impl Serialize for BareSourceLocation {
         }) {
             map.serialize_entry("line", &self.line)?;
         }
-        if let Some(presumed_file) = &self.presumed_file {
-            map.serialize_entry("presumedFile", &**presumed_file)?;
+        let presumed_file = self.presumed_file.unwrap_or(self.file);
+        if LAST_LOC_PRESUMED_FILENAME.with(|last_loc_presumed_filename| {
+            let mut last_loc_presumed_filename = last_loc_presumed_filename.borrow_mut();
+            if **last_loc_presumed_filename == *presumed_file.as_str() {
+                false
+            } else {
+                *last_loc_presumed_filename = presumed_file.to_shared();
+                presumed_file.as_str() != file
+            }
+        }) {
+            map.serialize_entry("presumedFile", presumed_file.as_str())?;
         }
-        if let Some(presumed_line) = &self.presumed_line {
-            map.serialize_entry("presumedLine", presumed_line)?;
+        let presumed_line = self.presumed_line.unwrap_or(self.line);
+        if LAST_LOC_PRESUMED_LINE.with(|last_loc_presumed_line| {
+            if last_loc_presumed_line.get() == presumed_line {
+                false
+            } else {
+                last_loc_presumed_line.set(presumed_line);
+                presumed_line != self.line
+            }
+        }) {
+            map.serialize_entry("presumedLine", &presumed_line)?;
         }
         map.serialize_entry("col", &self.col)?;
         map.serialize_entry("tokLen", &self.tok_len)?;
-        if let Some(included_from) = &self.included_from {
-            map.serialize_entry("includedFrom", included_from)?;
+        if let Some(included_from) = self.included_from {
+            // Like Clang, print only the immediate includer. The rest is
+            // reconstructed when deserializing.
+            let included_from = IncludedFrom {
+                included_from: None,
+                file: Arc::clone(&included_from.file),
+            };
+            map.serialize_entry("includedFrom", &included_from)?;
         }
         if self.is_macro_arg_expansion {
             map.serialize_entry("isMacroArgExpansion", &true)?;
@@ -620,13 +1333,53 @@ impl Serialize for IncludedFrom {
     {
         let mut map = serializer.serialize_map(None)?;
         if let Some(included_from) = &self.included_from {
-            map.serialize_entry("includedFrom", included_from)?;
+            map.serialize_entry("includedFrom", &**included_from)?;
         }
         map.serialize_entry("file", &*self.file)?;
         map.end()
     }
 }
 
+// Serialized by way of the owned types, so that a dump deserialized with
+// borrowed locations serializes identically.
+impl Serialize for BorrowedSourceRange<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("begin", &self.begin)?;
+        map.serialize_entry("end", &self.end)?;
+        map.end()
+    }
+}
+
+impl Serialize for BorrowedSourceLocation<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serialize_source_location(
+            self.spelling_loc
+                .as_ref()
+                .map(BorrowedBareSourceLocation::fields),
+            self.expansion_loc
+                .as_ref()
+                .map(BorrowedBareSourceLocation::fields),
+            serializer,
+        )
+    }
+}
+
+impl Serialize for BorrowedBareSourceLocation<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.fields().serialize(serializer)
+    }
+}
+
 impl Debug for SourceRange {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
         let SourceRange { begin, end } = self;
@@ -0,0 +1,341 @@
+use crate::loc::BareSourceLocation;
+use crate::{Cursor, HasKind, HasRange, Kind, Node};
+
+type Predicate<'m, T> =
+    Box<dyn for<'ast> Fn(&Cursor<'ast, T>, &mut Bindings<'ast, T>) -> bool + 'm>;
+
+type Bindings<'ast, T> = Vec<(&'static str, &'ast Node<T>)>;
+
+/// Predicate on a node and its surroundings in the syntax tree.
+///
+/// The lifetime `'m` is that of the closures the matcher was built from, and
+/// of any data borrowed by `T`.
+pub struct Matcher<'m, T> {
+    predicate: Predicate<'m, T>,
+}
+
+/// A node found by a [`Matcher`], together with the nodes bound by name in
+/// the course of matching it.
+pub struct Match<'ast, T> {
+    node: &'ast Node<T>,
+    bindings: Bindings<'ast, T>,
+}
+
+impl<'m, T> Matcher<'m, T>
+where
+    T: 'm,
+{
+    fn new(
+        predicate: impl for<'ast> Fn(&Cursor<'ast, T>, &mut Bindings<'ast, T>) -> bool + 'm,
+    ) -> Self {
+        Matcher {
+            predicate: Box::new(predicate),
+        }
+    }
+
+    /// Matches nodes which match both `self` and `other`.
+    #[must_use]
+    pub fn and(self, other: Matcher<'m, T>) -> Self {
+        Matcher::new(move |cursor, bindings| {
+            let len = bindings.len();
+            let matches = (self.predicate)(cursor, bindings) && (other.predicate)(cursor, bindings);
+            if !matches {
+                bindings.truncate(len);
+            }
+            matches
+        })
+    }
+
+    /// Matches nodes which match `self` or `other`. Only the bindings of the
+    /// first of the two which matches are kept.
+    #[must_use]
+    pub fn or(self, other: Matcher<'m, T>) -> Self {
+        Matcher::new(move |cursor, bindings| {
+            let len = bindings.len();
+            if (self.predicate)(cursor, bindings) {
+                return true;
+            }
+            bindings.truncate(len);
+            (other.predicate)(cursor, bindings)
+        })
+    }
+
+    /// Gives the matched node a name by which to retrieve it from the
+    /// [`Match`].
+    #[must_use]
+    pub fn bind(self, name: &'static str) -> Self {
+        Matcher::new(move |cursor, bindings| {
+            if !(self.predicate)(cursor, bindings) {
+                return false;
+            }
+            bindings.push((name, cursor.node()));
+            true
+        })
+    }
+
+    /// Whether the node at the cursor matches.
+    pub fn matches(&self, cursor: &Cursor<T>) -> bool {
+        (self.predicate)(cursor, &mut Vec::new())
+    }
+
+    /// Every node in the tree under `root`, including `root`, which matches,
+    /// in depth-first order.
+    pub fn find_all<'ast>(&self, root: &'ast Node<T>) -> Vec<Match<'ast, T>> {
+        let mut matches = Vec::new();
+        let mut cursor = root.cursor();
+        loop {
+            let mut bindings = Vec::new();
+            if (self.predicate)(&cursor, &mut bindings) {
+                matches.push(Match {
+                    node: cursor.node(),
+                    bindings,
+                });
+            }
+            if !preorder_next(&mut cursor, 0) {
+                return matches;
+            }
+        }
+    }
+}
+
+impl<'ast, T> Match<'ast, T> {
+    /// The node which matched.
+    pub fn node(&self) -> &'ast Node<T> {
+        self.node
+    }
+
+    /// The node bound to the given name. If the same name was bound more than
+    /// once, this is the last one.
+    pub fn get(&self, name: &str) -> Option<&'ast Node<T>> {
+        let (_name, node) = self
+            .bindings
+            .iter()
+            .rev()
+            .find(|(bound, _)| *bound == name)?;
+        Some(node)
+    }
+}
+
+/// Matches every node.
+pub fn any<'m, T>() -> Matcher<'m, T>
+where
+    T: 'm,
+{
+    Matcher::<'m, T>::new(|_cursor, _bindings| true)
+}
+
+/// Matches nodes which do not match `matcher`.
+pub fn not<'m, T>(matcher: Matcher<'m, T>) -> Matcher<'m, T>
+where
+    T: 'm,
+{
+    Matcher::<'m, T>::new(move |cursor, bindings| {
+        let len = bindings.len();
+        let found = (matcher.predicate)(cursor, bindings);
+        bindings.truncate(len);
+        !found
+    })
+}
+
+/// Matches nodes of the given kind or of any subclass of it, as determined by
+/// [`Kind::is_a`].
+pub fn kind<'m, T>(kind: Kind) -> Matcher<'m, T>
+where
+    T: HasKind + 'm,
+{
+    Matcher::<'m, T>::new(move |cursor, _bindings| cursor.node().kind.kind().is_a(kind))
+}
+
+/// Matches nodes for which the closure returns true, such as to check the
+/// fields of the caller's `T`.
+pub fn node<'m, T>(predicate: impl Fn(&T) -> bool + 'm) -> Matcher<'m, T>
+where
+    T: 'm,
+{
+    Matcher::<'m, T>::new(move |cursor, _bindings| predicate(&cursor.node().kind))
+}
+
+/// Matches nodes which have a child matching `matcher`.
+pub fn has_child<'m, T>(matcher: Matcher<'m, T>) -> Matcher<'m, T>
+where
+    T: 'm,
+{
+    Matcher::<'m, T>::new(move |cursor, bindings| {
+        let mut child = cursor.clone();
+        if !child.goto_first_child() {
+            return false;
+        }
+        loop {
+            let len = bindings.len();
+            if (matcher.predicate)(&child, bindings) {
+                return true;
+            }
+            bindings.truncate(len);
+            if !child.goto_next_sibling() {
+                return false;
+            }
+        }
+    })
+}
+
+/// Matches nodes which have a descendant at any depth matching `matcher`. The
+/// bindings are those of the first such descendant in depth-first order.
+pub fn has_descendant<'m, T>(matcher: Matcher<'m, T>) -> Matcher<'m, T>
+where
+    T: 'm,
+{
+    Matcher::<'m, T>::new(move |cursor, bindings| {
+        let mut descendant = cursor.clone();
+        let depth = cursor.depth();
+        if !descendant.goto_first_child() {
+            return false;
+        }
+        loop {
+            let len = bindings.len();
+            if (matcher.predicate)(&descendant, bindings) {
+                return true;
+            }
+            bindings.truncate(len);
+            if !preorder_next(&mut descendant, depth) {
+                return false;
+            }
+        }
+    })
+}
+
+/// Matches nodes which have an ancestor at any height matching `matcher`. The
+/// bindings are those of the nearest such ancestor.
+///
+/// Ancestors are only those between the node and the root of the tree that
+/// matching began at, such as the node passed to [`Matcher::find_all`].
+pub fn has_ancestor<'m, T>(matcher: Matcher<'m, T>) -> Matcher<'m, T>
+where
+    T: 'm,
+{
+    Matcher::<'m, T>::new(move |cursor, bindings| {
+        let mut ancestor = cursor.clone();
+        while ancestor.goto_parent() {
+            let len = bindings.len();
+            if (matcher.predicate)(&ancestor, bindings) {
+                return true;
+            }
+            bindings.truncate(len);
+        }
+        false
+    })
+}
+
+/// Matches nodes whose range begins, after expanding macros, in a file whose
+/// path matches the glob pattern.
+///
+/// In the pattern, `?` matches any one character and `*` matches any number of
+/// characters other than `/`, while `**` matches across directories. The
+/// pattern must match the whole path as printed by Clang, so it typically
+/// begins with `**/` to match a file in any directory.
+///
+/// ```
+/// # use clang_ast::{HasKind, HasRange};
+/// # use clang_ast::matcher::Matcher;
+/// use clang_ast::matcher::in_file;
+///
+/// fn in_generated_code<'m, T: HasRange + 'm>() -> Matcher<'m, T> {
+///     in_file("**/generated/*.h")
+/// }
+/// ```
+pub fn in_file<'m, T>(pattern: impl Into<String>) -> Matcher<'m, T>
+where
+    T: HasRange + 'm,
+{
+    let pattern: Vec<char> = pattern.into().chars().collect();
+    Matcher::<'m, T>::new(move |cursor, _bindings| {
+        begin_expansion_loc(cursor.node()).is_some_and(|loc| glob(&pattern, &loc.file))
+    })
+}
+
+/// Matches nodes whose range begins, after expanding macros, in the main file
+/// of the translation unit, rather than in a header included by it.
+pub fn is_expansion_in_main_file<'m, T>() -> Matcher<'m, T>
+where
+    T: HasRange + 'm,
+{
+    Matcher::<'m, T>::new(|cursor, _bindings| {
+        begin_expansion_loc(cursor.node()).is_some_and(|loc| {
+            // Every location in a header carries the file that included it.
+            // Clang's own buffers, like `<built-in>` and `<scratch space>`,
+            // have no includer but are not the main file either.
+            loc.included_from.is_none() && !loc.file.starts_with('<')
+        })
+    })
+}
+
+fn begin_expansion_loc<T>(node: &Node<T>) -> Option<&BareSourceLocation>
+where
+    T: HasRange,
+{
+    let begin = &node.kind.range()?.begin;
+    begin.expansion_loc.as_ref().or(begin.spelling_loc.as_ref())
+}
+
+// Moves to the next node in depth-first order, without leaving the subtree of
+// the ancestor at the given depth. Returns false when there is none.
+fn preorder_next<T>(cursor: &mut Cursor<T>, depth: usize) -> bool {
+    if cursor.goto_first_child() {
+        return true;
+    }
+    while cursor.depth() > depth {
+        if cursor.goto_next_sibling() {
+            return true;
+        }
+        cursor.goto_parent();
+    }
+    false
+}
+
+fn glob(pattern: &[char], path: &str) -> bool {
+    let path: Vec<char> = path.chars().collect();
+
+    // Whether the pattern so far can match each prefix of the path.
+    let mut matches = vec![false; path.len() + 1];
+    matches[0] = true;
+    let mut i = 0;
+    while i < pattern.len() {
+        let mut next = vec![false; path.len() + 1];
+        match pattern[i] {
+            '*' if pattern.get(i + 1) == Some(&'*') && pattern.get(i + 2) == Some(&'/') => {
+                // Zero or more whole directories.
+                i += 2;
+                let mut any = false;
+                for j in 0..=path.len() {
+                    next[j] = matches[j] || (any && path[j - 1] == '/');
+                    any |= matches[j];
+                }
+            }
+            '*' if pattern.get(i + 1) == Some(&'*') => {
+                i += 1;
+                let mut any = false;
+                for j in 0..=path.len() {
+                    any |= matches[j];
+                    next[j] = any;
+                }
+            }
+            '*' => {
+                for j in 0..=path.len() {
+                    next[j] = matches[j] || (j > 0 && next[j - 1] && path[j - 1] != '/');
+                }
+            }
+            '?' => {
+                for j in 1..=path.len() {
+                    next[j] = matches[j - 1] && path[j - 1] != '/';
+                }
+            }
+            ch => {
+                for j in 1..=path.len() {
+                    next[j] = matches[j - 1] && path[j - 1] == ch;
+                }
+            }
+        }
+        matches = next;
+        i += 1;
+    }
+    matches[path.len()]
+}
@@ -0,0 +1,182 @@
+use crate::error::Error;
+use crate::intern::{self, Interner};
+use crate::json::{self, Scanner};
+use crate::loc::{self, ThreadLocalState};
+use crate::Node;
+use serde::de::Deserialize;
+use std::num::NonZeroUsize;
+use std::panic;
+use std::sync::Mutex;
+use std::thread;
+
+// Chunks per thread, so that a thread which finishes early because its chunks
+// held mostly small declarations can pick up more work.
+const CHUNKS_PER_THREAD: usize = 8;
+
+/// Deserializes a syntax tree using all available cores.
+///
+/// The top-level nodes of the dump, such as the declarations directly inside
+/// of the translation unit, are divided into contiguous chunks which are
+/// deserialized on separate threads and then put back together in order. The
+/// result is the same `Node<T>` as produced by [`from_slice`](crate::from_slice).
+///
+/// ```no_run
+/// # use serde_derive::Deserialize;
+/// #
+/// # #[derive(Deserialize)]
+/// # enum Clang {
+/// #     Other,
+/// # }
+/// #
+/// fn main() -> Result<(), clang_ast::Error> {
+///     let json = std::fs::read("ast.json").unwrap();
+///     let node = clang_ast::from_slice_parallel::<Clang>(&json)?;
+///     # let _ = node;
+///     Ok(())
+/// }
+/// ```
+///
+/// Before any thread starts, the calling thread makes one pass over the whole
+/// input which reads only the `"loc"` and `"range"` of each node. This finds
+/// where each top-level node begins and resolves the "same as previous" file
+/// and line that each chunk starts with, so the pass costs a fraction of full
+/// deserialization but does not itself run in parallel. Like with
+/// [`from_slice_filtered`](crate::from_slice_filtered), the source locations
+/// of `T` are resolved correctly only if they all appear in `"loc"` and
+/// `"range"` fields.
+///
+/// File paths are interned across all the threads, using the [`Interner`]
+/// installed on the calling thread if any.
+pub fn from_slice_parallel<'de, T>(bytes: &'de [u8]) -> Result<Node<T>, Error>
+where
+    T: Deserialize<'de> + Send,
+{
+    let interner = intern::installed().unwrap_or_default();
+    let threads = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+    interner.install(|| {
+        let _intern = intern::activate();
+        let mut scanner = Scanner::new(bytes);
+        let (mut root, has_inner) = json::begin_node(&mut scanner)?;
+        if !has_inner {
+            scanner.end()?;
+            return Ok(root);
+        }
+
+        let chunk_len = bytes.len() / (threads * CHUNKS_PER_THREAD) + 1;
+        let mut chunks = Vec::new();
+        let scan_result = scan(&mut scanner, chunk_len, &mut chunks);
+
+        for nodes in deserialize_chunks(bytes, chunks, threads, &interner) {
+            root.inner.extend(nodes?);
+        }
+        // An error in the scan is reported only if there was no error earlier
+        // in the input while deserializing the nodes before it.
+        scan_result?;
+        Ok(root)
+    })
+}
+
+struct Chunk {
+    // The "same as previous" location state as of the first node's beginning.
+    state: ThreadLocalState,
+    starts: Vec<usize>,
+}
+
+// Finds the beginning of each top-level node, skipping over them in the way
+// that resolves the locations inside of them.
+fn scan(scanner: &mut Scanner, chunk_len: usize, chunks: &mut Vec<Chunk>) -> Result<(), Error> {
+    let mut first = true;
+    loop {
+        if scanner.eat(b']') {
+            scanner.end_node()?;
+            return scanner.end();
+        }
+        if !first {
+            scanner.expect(b',', "expected `,` or `]`")?;
+        }
+        first = false;
+        let start = scanner.position();
+        if chunks
+            .last()
+            .map_or(true, |chunk: &Chunk| start - chunk.starts[0] >= chunk_len)
+        {
+            let state = loc::thread_local_replace(ThreadLocalState::default());
+            chunks.push(Chunk {
+                state: state.clone(),
+                starts: Vec::new(),
+            });
+            loc::thread_local_replace(state);
+        }
+        chunks.last_mut().unwrap().starts.push(start);
+        json::skip_node(scanner)?;
+    }
+}
+
+fn deserialize_chunks<'de, T>(
+    bytes: &'de [u8],
+    chunks: Vec<Chunk>,
+    threads: usize,
+    interner: &Interner,
+) -> Vec<Result<Vec<Node<T>>, Error>>
+where
+    T: Deserialize<'de> + Send,
+{
+    let count = chunks.len();
+    let chunks = Mutex::new(chunks.into_iter().enumerate());
+
+    let mut results: Vec<_> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads.min(count))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut results = Vec::new();
+                    loop {
+                        let Some((i, chunk)) = chunks.lock().unwrap().next() else {
+                            return results;
+                        };
+                        let nodes = interner.install(|| deserialize_chunk(bytes, chunk));
+                        let failed = nodes.is_err();
+                        results.push((i, nodes));
+                        if failed {
+                            // Chunks after this one are not needed.
+                            chunks.lock().unwrap().by_ref().for_each(drop);
+                        }
+                    }
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| match worker.join() {
+                Ok(results) => results,
+                Err(panic) => panic::resume_unwind(panic),
+            })
+            .collect()
+    });
+
+    results.sort_by_key(|(i, _nodes)| *i);
+    // Stop at the first error. Later chunks may have been abandoned.
+    let mut ordered = Vec::new();
+    for (_i, nodes) in results {
+        let failed = nodes.is_err();
+        ordered.push(nodes);
+        if failed {
+            break;
+        }
+    }
+    ordered
+}
+
+fn deserialize_chunk<'de, T>(bytes: &'de [u8], chunk: Chunk) -> Result<Vec<Node<T>>, Error>
+where
+    T: Deserialize<'de>,
+{
+    let _intern = intern::activate();
+    loc::thread_local_replace(chunk.state);
+    let mut scanner = Scanner::new(bytes);
+    let mut nodes = Vec::with_capacity(chunk.starts.len());
+    for start in chunk.starts {
+        scanner.seek(start);
+        nodes.push(json::parse_node(&mut scanner, None)?);
+    }
+    Ok(nodes)
+}
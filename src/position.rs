@@ -0,0 +1,220 @@
+use crate::expansion::file_location;
+use crate::loc::{BareSourceLocation, SourceRange};
+use crate::{Cursor, Flow, Node, Visit};
+use std::cmp::Ordering;
+
+/// Types from which the [`SourceRange`] of a node can be read.
+///
+/// This is what allows [`Node::innermost_at`] to find nodes by position
+/// regardless of how the caller's `T` is laid out. Kinds of node which have no
+/// range in the dump can return `None`.
+///
+/// ```
+/// # use clang_ast::{HasRange, SourceRange};
+/// # use serde_derive::Deserialize;
+/// #
+/// #[derive(Deserialize)]
+/// struct Clang {
+///     #[serde(default)]
+///     range: SourceRange,
+/// }
+///
+/// impl HasRange for Clang {
+///     fn range(&self) -> Option<&SourceRange> {
+///         Some(&self.range)
+///     }
+/// }
+/// ```
+pub trait HasRange {
+    fn range(&self) -> Option<&SourceRange>;
+}
+
+impl HasRange for SourceRange {
+    fn range(&self) -> Option<&SourceRange> {
+        Some(self)
+    }
+}
+
+/// Locations are ordered by file path and then by offset within the file.
+///
+/// Locations at the same offset of the same file are ordered by their other
+/// fields, so that the order is total and consistent with `Eq`.
+impl Ord for BareSourceLocation {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let BareSourceLocation {
+            offset,
+            file,
+            line,
+            presumed_file,
+            presumed_line,
+            col,
+            tok_len,
+            included_from,
+            is_macro_arg_expansion,
+        } = self;
+        file.cmp(&other.file)
+            .then(offset.cmp(&other.offset))
+            .then_with(|| line.cmp(&other.line))
+            .then_with(|| col.cmp(&other.col))
+            .then_with(|| tok_len.cmp(&other.tok_len))
+            .then_with(|| presumed_file.cmp(&other.presumed_file))
+            .then_with(|| presumed_line.cmp(&other.presumed_line))
+            .then_with(|| included_from.cmp(&other.included_from))
+            .then_with(|| is_macro_arg_expansion.cmp(&other.is_macro_arg_expansion))
+    }
+}
+
+impl PartialOrd for BareSourceLocation {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// The characters covered by a range, from the beginning of its first token to
+// the end of its last token, after mapping both ends out of any macros.
+struct Extent<'a> {
+    begin: &'a BareSourceLocation,
+    end: &'a BareSourceLocation,
+    end_offset: usize,
+}
+
+impl SourceRange {
+    /// Whether all of the text of `other` is within the text of this range.
+    ///
+    /// Ranges are compared by their [file ranges](SourceRange::file_range), so
+    /// a range inside of a macro invocation is contained by any range which
+    /// contains the invocation. Empty ranges neither contain nor are contained
+    /// by anything.
+    pub fn contains(&self, other: &SourceRange) -> bool {
+        let (Some(this), Some(other)) = (self.extent(), other.extent()) else {
+            return false;
+        };
+        this.begin.file == other.begin.file
+            && this.begin.offset <= other.begin.offset
+            && other.end_offset <= this.end_offset
+    }
+
+    /// Whether the character at `offset` in `file` is within the text of this
+    /// range.
+    pub fn contains_offset(&self, file: &str, offset: usize) -> bool {
+        self.extent().map_or(false, |this| {
+            *this.begin.file == *file && this.begin.offset <= offset && offset < this.end_offset
+        })
+    }
+
+    /// Whether the text of the two ranges has any character in common.
+    pub fn overlaps(&self, other: &SourceRange) -> bool {
+        let (Some(this), Some(other)) = (self.extent(), other.extent()) else {
+            return false;
+        };
+        this.begin.file == other.begin.file
+            && this.begin.offset < other.end_offset
+            && other.begin.offset < this.end_offset
+    }
+
+    /// The smallest file range covering the text of both ranges.
+    ///
+    /// Returns `None` if either range is empty or the two are in different
+    /// files.
+    pub fn union(&self, other: &SourceRange) -> Option<SourceRange> {
+        let this = self.extent()?;
+        let other = other.extent()?;
+        if this.begin.file != other.begin.file {
+            return None;
+        }
+        let begin = if other.begin.offset < this.begin.offset {
+            other.begin
+        } else {
+            this.begin
+        };
+        let end = if other.end_offset > this.end_offset {
+            other.end
+        } else {
+            this.end
+        };
+        Some(SourceRange {
+            begin: file_location(begin),
+            end: file_location(end),
+        })
+    }
+
+    fn extent(&self) -> Option<Extent<'_>> {
+        let (begin, end) = self.file_locs()?;
+        Some(Extent {
+            begin,
+            end,
+            end_offset: end.offset.checked_add(end.tok_len)?,
+        })
+    }
+}
+
+impl<T> Node<T> {
+    /// Cursor positioned at the deepest node whose range contains the
+    /// character at `offset` in `file`, as for an editor's "node under the
+    /// cursor" query.
+    ///
+    /// Nodes without a range are never the result, but their descendants are
+    /// still searched. Returns `None` if no node in the tree contains the
+    /// position. If several nodes at the same depth contain it, the first one
+    /// is chosen.
+    pub fn innermost_at(&self, file: &str, offset: usize) -> Option<Cursor<'_, T>>
+    where
+        T: HasRange,
+    {
+        let mut finder = InnermostAt {
+            file,
+            offset,
+            ancestors: Vec::new(),
+            innermost: None,
+        };
+        self.visit(&mut finder);
+        let (node, path) = finder.innermost?;
+        Some(Cursor::from_path(node, path))
+    }
+}
+
+struct InnermostAt<'a, 'ast, T> {
+    file: &'a str,
+    offset: usize,
+    // Each ancestor of the next node, and how many of its children have been
+    // entered so far.
+    ancestors: Vec<(&'ast Node<T>, usize)>,
+    innermost: Option<Path<'ast, T>>,
+}
+
+type Path<'ast, T> = (&'ast Node<T>, Vec<(&'ast Node<T>, usize)>);
+
+impl<'a, 'ast, T> Visit<'ast, T> for InnermostAt<'a, 'ast, T>
+where
+    T: HasRange,
+{
+    fn enter(&mut self, node: &'ast Node<T>, depth: usize) -> Flow {
+        if let Some((_parent, count)) = self.ancestors.last_mut() {
+            *count += 1;
+        }
+        self.ancestors.push((node, 0));
+        let Some(range) = node.kind.range().filter(|range| range.extent().is_some()) else {
+            return Flow::Continue;
+        };
+        if !range.contains_offset(self.file, self.offset) {
+            return Flow::Skip;
+        }
+        if self
+            .innermost
+            .as_ref()
+            .map_or(true, |(_node, path)| path.len() < depth)
+        {
+            let path = self.ancestors[..depth]
+                .iter()
+                .map(|(ancestor, count)| (*ancestor, count - 1))
+                .collect();
+            self.innermost = Some((node, path));
+        }
+        Flow::Continue
+    }
+
+    fn leave(&mut self, _node: &'ast Node<T>, _depth: usize) -> Flow {
+        self.ancestors.pop();
+        Flow::Continue
+    }
+}
@@ -0,0 +1,56 @@
+use crate::loc::{BareSourceLocation, SourceLocation};
+use std::fmt::{self, Display};
+
+/// A location as adjusted by `#line` directives, like Clang's `PresumedLoc`.
+///
+/// This is the file and line which Clang's own diagnostics report, and which
+/// tools should report for code generated from another source file, such as
+/// by bison or protoc, in order to point back at the original input.
+///
+/// ```
+/// # use clang_ast::SourceLocation;
+/// #
+/// fn report(loc: &SourceLocation, message: &str) {
+///     match loc.presumed() {
+///         Some(presumed) => eprintln!("{}: {}", presumed, message),
+///         None => eprintln!("{}", message),
+///     }
+/// }
+/// ```
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct PresumedLoc<'a> {
+    pub file: &'a str,
+    pub line: usize,
+    pub col: usize,
+}
+
+impl BareSourceLocation {
+    /// The effective file and line of this location, which are the physical
+    /// ones unless a `#line` directive is in effect.
+    ///
+    /// When deserializing, `presumed_file` and `presumed_line` are filled in
+    /// for every location to which a `#line` directive applies, not only the
+    /// ones on which Clang printed them.
+    pub fn presumed(&self) -> PresumedLoc<'_> {
+        PresumedLoc {
+            file: self.presumed_file.as_deref().unwrap_or(&self.file),
+            line: self.presumed_line.unwrap_or(self.line),
+            col: self.col,
+        }
+    }
+}
+
+impl SourceLocation {
+    /// The effective location of this location's expansion, which is where
+    /// Clang reports diagnostics for code inside of macros.
+    pub fn presumed(&self) -> Option<PresumedLoc<'_>> {
+        let loc = self.expansion_loc.as_ref().or(self.spelling_loc.as_ref())?;
+        Some(loc.presumed())
+    }
+}
+
+impl<'a> Display for PresumedLoc<'a> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{}:{}:{}", self.file, self.line, self.col)
+    }
+}
@@ -0,0 +1,615 @@
+use crate::cursor::HasKind;
+use crate::loc::SourceLocation;
+use crate::{Flow, Id, Kind, Node, Visit};
+use foldhash::{HashMap, HashSet};
+use std::collections::hash_map::Entry;
+use std::collections::VecDeque;
+use std::sync::{Arc, OnceLock};
+
+/// Types from which the identifying information of a declaration can be read.
+///
+/// This is what allows a [`Project`] to recognize the same declaration in the
+/// dumps of different translation units, regardless of how the caller's `T`
+/// is laid out. Only [`name`](HasDecl::name) is required. The rest default to
+/// `None` or empty, in which case declarations are told apart by less
+/// information and queries which rely on it find nothing.
+///
+/// ```
+/// # use clang_ast::{HasDecl, HasKind, Kind, SourceLocation};
+/// # use serde_derive::Deserialize;
+/// #
+/// #[derive(Deserialize)]
+/// struct Clang {
+///     kind: Kind,
+///     name: Option<String>,
+///     #[serde(rename = "mangledName")]
+///     mangled_name: Option<String>,
+///     #[serde(default)]
+///     loc: SourceLocation,
+/// }
+///
+/// impl HasKind for Clang {
+///     fn kind(&self) -> Kind {
+///         self.kind
+///     }
+/// }
+///
+/// impl HasDecl for Clang {
+///     fn name(&self) -> Option<&str> {
+///         self.name.as_deref()
+///     }
+///
+///     fn mangled_name(&self) -> Option<&str> {
+///         self.mangled_name.as_deref()
+///     }
+///
+///     fn loc(&self) -> Option<&SourceLocation> {
+///         Some(&self.loc)
+///     }
+/// }
+/// ```
+pub trait HasDecl: HasKind {
+    /// The `"name"` of a named declaration.
+    fn name(&self) -> Option<&str>;
+
+    /// The `"mangledName"` of a function or variable, which distinguishes
+    /// overloads.
+    fn mangled_name(&self) -> Option<&str> {
+        None
+    }
+
+    /// The `"loc"` of the declaration.
+    fn loc(&self) -> Option<&SourceLocation> {
+        None
+    }
+
+    /// The `"qualType"` of the declaration's `"type"`, which for a method is
+    /// its signature.
+    fn qual_type(&self) -> Option<&str> {
+        None
+    }
+
+    /// For a class, the `"qualType"` of each of its `"bases"`.
+    fn bases(&self) -> Vec<&str> {
+        Vec::new()
+    }
+
+    /// The `"storageClass"` of a function or variable, such as `"static"`.
+    fn storage_class(&self) -> Option<&str> {
+        None
+    }
+
+    /// The `"parentDeclContextId"` of a declaration outside of the class or
+    /// namespace which it belongs to, such as the out-of-line definition of
+    /// a method.
+    fn parent_decl_context(&self) -> Option<Id> {
+        None
+    }
+}
+
+/// Declarations of many translation units, merged into one symbol table.
+///
+/// Ids in a dump are addresses in the memory of the Clang process that
+/// produced it and mean nothing in any other dump. A project instead
+/// identifies each namespace-scope and class-scope declaration by a
+/// [`SymbolKey`] consisting of its kind, fully qualified name and mangled
+/// name, so that for example the same class defined in a header is one
+/// symbol no matter how many translation units include it. Declarations with
+/// internal linkage are further told apart by where they are declared.
+///
+/// ```no_run
+/// # use clang_ast::{HasDecl, HasKind, Kind, Node, Project};
+/// #
+/// # struct Clang;
+/// # impl HasKind for Clang {
+/// #     fn kind(&self) -> Kind {
+/// #         unimplemented!()
+/// #     }
+/// # }
+/// # impl HasDecl for Clang {
+/// #     fn name(&self) -> Option<&str> {
+/// #         unimplemented!()
+/// #     }
+/// # }
+/// #
+/// fn print_overrides(units: &[Node<Clang>], method: &str) {
+///     let mut project = Project::new();
+///     for unit in units {
+///         project.add(unit);
+///     }
+///     for symbol in project.lookup(method) {
+///         for overrider in project.overrides(&symbol.key) {
+///             println!("{}", overrider.key.qualified_name);
+///         }
+///     }
+/// }
+/// ```
+///
+/// Declarations inside of function bodies are not part of the symbol table.
+pub struct Project<'ast, T> {
+    units: Vec<&'ast Node<T>>,
+    symbols: Vec<Symbol<'ast, T>>,
+    index: HashMap<SymbolKey, usize>,
+    by_name: HashMap<String, Vec<usize>>,
+    // From the address of each node that was entered in the symbol table.
+    nodes: HashMap<usize, usize>,
+    // The classes deriving directly from each class. Built on first use,
+    // because resolving base classes needs every unit, and discarded whenever
+    // a unit is added.
+    derived_from: OnceLock<HashMap<usize, Vec<usize>>>,
+}
+
+/// Identity of a declared entity across translation units.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct SymbolKey {
+    pub kind: Kind,
+    /// Names of the enclosing namespaces and classes and of the declaration
+    /// itself, separated by `::`, like `std::vector`.
+    pub qualified_name: String,
+    pub mangled_name: Option<String>,
+    /// For a declaration inside of an anonymous namespace, which is a
+    /// different entity in every translation unit, the index of its unit.
+    pub unit: Option<usize>,
+    /// For a function or variable declared `static` outside of a class, the
+    /// file in which it is declared. Such declarations in different files
+    /// are different entities even though their names are the same.
+    pub file: Option<Arc<str>>,
+}
+
+/// All the declarations of one entity, such as every declaration and the
+/// definition of a function.
+pub struct Symbol<'ast, T> {
+    pub key: SymbolKey,
+    /// Distinct declarations by location, in the order first seen.
+    pub declarations: Vec<Declaration<'ast, T>>,
+    parent: Option<usize>,
+    members: Vec<usize>,
+}
+
+/// One declaration, as it appears in each translation unit which contains
+/// it.
+pub struct Declaration<'ast, T> {
+    pub file: Option<Arc<str>>,
+    pub offset: Option<usize>,
+    /// Index of the translation unit within the project, and the node in it.
+    pub nodes: Vec<(usize, &'ast Node<T>)>,
+}
+
+impl<'ast, T> Project<'ast, T> {
+    pub fn new() -> Self {
+        Project {
+            units: Vec::new(),
+            symbols: Vec::new(),
+            index: HashMap::default(),
+            by_name: HashMap::default(),
+            nodes: HashMap::default(),
+            derived_from: OnceLock::new(),
+        }
+    }
+
+    /// Adds the declarations of a translation unit, given the root of its
+    /// dump. Returns the index of the unit within the project.
+    pub fn add(&mut self, unit: &'ast Node<T>) -> usize
+    where
+        T: HasDecl,
+    {
+        let index = self.units.len();
+        self.units.push(unit);
+        self.derived_from.take();
+        let mut collector = Collector {
+            project: self,
+            unit: index,
+            scopes: Vec::new(),
+            frames: Vec::new(),
+            by_id: HashMap::default(),
+        };
+        unit.visit(&mut collector);
+        index
+    }
+
+    /// Root of every translation unit, in the order added.
+    pub fn units(&self) -> &[&'ast Node<T>] {
+        &self.units
+    }
+
+    /// Every symbol, in the order first seen.
+    pub fn symbols(&self) -> &[Symbol<'ast, T>] {
+        &self.symbols
+    }
+
+    pub fn symbol(&self, key: &SymbolKey) -> Option<&Symbol<'ast, T>> {
+        let i = *self.index.get(key)?;
+        Some(&self.symbols[i])
+    }
+
+    /// The symbol which a node of one of the translation units declares.
+    pub fn symbol_of(&self, node: &Node<T>) -> Option<&Symbol<'ast, T>> {
+        let i = *self.nodes.get(&address(node))?;
+        Some(&self.symbols[i])
+    }
+
+    /// Symbols of any kind with the given qualified name, such as all the
+    /// overloads of a function.
+    pub fn lookup<'a>(
+        &'a self,
+        qualified_name: &str,
+    ) -> impl Iterator<Item = &'a Symbol<'ast, T>> + 'a {
+        let symbols = self
+            .by_name
+            .get(qualified_name)
+            .map_or(&[][..], Vec::as_slice);
+        symbols.iter().map(move |i| &self.symbols[*i])
+    }
+
+    /// The class or namespace directly enclosing a symbol.
+    pub fn parent(&self, symbol: &SymbolKey) -> Option<&Symbol<'ast, T>> {
+        let i = *self.index.get(symbol)?;
+        Some(&self.symbols[self.symbols[i].parent?])
+    }
+
+    /// Symbols declared directly inside of a class or namespace.
+    pub fn members<'a>(
+        &'a self,
+        symbol: &SymbolKey,
+    ) -> impl Iterator<Item = &'a Symbol<'ast, T>> + 'a {
+        let members = match self.index.get(symbol) {
+            Some(i) => &self.symbols[*i].members[..],
+            None => &[],
+        };
+        members.iter().map(move |i| &self.symbols[*i])
+    }
+
+    /// Classes which derive from the given class, directly or indirectly, in
+    /// any translation unit.
+    ///
+    /// Base classes are found by resolving the `qualType` of each of a class's
+    /// [`bases`](HasDecl::bases) against the names of classes in the project,
+    /// starting from the scope of the derived class like C++ name lookup
+    /// would. Clang's dump does not give the id of a base class, only this
+    /// spelling of its type, so a base named through a typedef or alias, or
+    /// one which is a dependent type inside of a template, is not found.
+    pub fn derived_classes(&self, class: &SymbolKey) -> Vec<&Symbol<'ast, T>>
+    where
+        T: HasDecl,
+    {
+        let Some(&class) = self.index.get(class) else {
+            return Vec::new();
+        };
+        self.derived(class)
+            .into_iter()
+            .map(|i| &self.symbols[i])
+            .collect()
+    }
+
+    /// Methods in derived classes with the same name and signature as the
+    /// given method, which are the methods that override it if it is virtual.
+    ///
+    /// If the method is not virtual, these are instead the methods which hide
+    /// it. Derived classes are found as in
+    /// [`derived_classes`](Project::derived_classes), with the same
+    /// limitations.
+    pub fn overrides(&self, method: &SymbolKey) -> Vec<&Symbol<'ast, T>>
+    where
+        T: HasDecl,
+    {
+        let Some(&method) = self.index.get(method) else {
+            return Vec::new();
+        };
+        let Some(class) = self.symbols[method].parent else {
+            return Vec::new();
+        };
+        let Some(decl) = self.symbols[method].node() else {
+            return Vec::new();
+        };
+        let name = decl.kind.name();
+        let qual_type = decl.kind.qual_type();
+
+        let mut overrides = Vec::new();
+        for derived in self.derived(class) {
+            for &member in &self.symbols[derived].members {
+                let symbol = &self.symbols[member];
+                if !symbol.key.kind.is_a(Kind::CXXMethodDecl) {
+                    continue;
+                }
+                let Some(node) = symbol.node() else {
+                    continue;
+                };
+                if node.kind.name() == name && node.kind.qual_type() == qual_type {
+                    overrides.push(symbol);
+                }
+            }
+        }
+        overrides
+    }
+
+    // Breadth-first over the classes deriving from `class`.
+    fn derived(&self, class: usize) -> Vec<usize>
+    where
+        T: HasDecl,
+    {
+        let derived_from = self.derived_from.get_or_init(|| self.build_derived_from());
+        let mut derived = Vec::new();
+        let mut seen = HashSet::default();
+        let mut queue = VecDeque::from([class]);
+        while let Some(class) = queue.pop_front() {
+            for &next in derived_from.get(&class).map_or(&[][..], Vec::as_slice) {
+                if seen.insert(next) {
+                    derived.push(next);
+                    queue.push_back(next);
+                }
+            }
+        }
+        derived
+    }
+
+    // The classes deriving directly from each class.
+    fn build_derived_from(&self) -> HashMap<usize, Vec<usize>>
+    where
+        T: HasDecl,
+    {
+        let mut derived_from: HashMap<usize, Vec<usize>> = HashMap::default();
+        for (i, symbol) in self.symbols.iter().enumerate() {
+            if !symbol.key.kind.is_a(Kind::RecordDecl) {
+                continue;
+            }
+            let mut bases = HashSet::default();
+            for declaration in &symbol.declarations {
+                for (_unit, node) in &declaration.nodes {
+                    for base in node.kind.bases() {
+                        if let Some(base) = self.resolve_class(i, base) {
+                            bases.insert(base);
+                        }
+                    }
+                }
+            }
+            for base in bases {
+                derived_from.entry(base).or_default().push(i);
+            }
+        }
+        derived_from
+    }
+
+    // Looks up a base class named by `qual_type` from the scope of `derived`.
+    fn resolve_class(&self, derived: usize, qual_type: &str) -> Option<usize> {
+        let name = qual_type
+            .trim_start_matches("class ")
+            .trim_start_matches("struct ")
+            .trim_start_matches("::");
+        let without_args = name.find('<').map(|i| &name[..i]);
+        let mut scope = self.symbols[derived].key.qualified_name.as_str();
+        loop {
+            scope = scope.rfind("::").map_or("", |i| &scope[..i]);
+            for name in [Some(name), without_args].into_iter().flatten() {
+                let qualified = if scope.is_empty() {
+                    name.to_owned()
+                } else {
+                    format!("{}::{}", scope, name)
+                };
+                let found = self.lookup(&qualified).find(|symbol| {
+                    symbol.key.kind.is_a(Kind::RecordDecl)
+                        && symbol
+                            .key
+                            .unit
+                            .map_or(true, |unit| Some(unit) == self.symbols[derived].key.unit)
+                });
+                if let Some(found) = found {
+                    return self.index.get(&found.key).copied();
+                }
+            }
+            if scope.is_empty() {
+                return None;
+            }
+        }
+    }
+}
+
+impl<'ast, T> Symbol<'ast, T> {
+    // Any one node declaring this symbol.
+    fn node(&self) -> Option<&'ast Node<T>> {
+        let declaration = self.declarations.first()?;
+        let (_unit, node) = declaration.nodes.first()?;
+        Some(node)
+    }
+}
+
+impl<'ast, T> Default for Project<'ast, T> {
+    fn default() -> Self {
+        Project::new()
+    }
+}
+
+fn address<T>(node: &Node<T>) -> usize {
+    node as *const Node<T> as usize
+}
+
+struct Collector<'p, 'ast, T> {
+    project: &'p mut Project<'ast, T>,
+    unit: usize,
+    scopes: Vec<Scope>,
+    // For each node entered and not yet left, whether it pushed a scope.
+    frames: Vec<bool>,
+    // Every scope so far in this unit, for declarations which name their
+    // parent by id.
+    by_id: HashMap<Id, Scope>,
+}
+
+#[derive(Clone)]
+struct Scope {
+    qualified_name: String,
+    symbol: Option<usize>,
+    anonymous_namespace: bool,
+}
+
+impl<'p, 'ast, T> Visit<'ast, T> for Collector<'p, 'ast, T>
+where
+    T: HasDecl,
+{
+    fn enter(&mut self, node: &'ast Node<T>, depth: usize) -> Flow {
+        let kind = node.kind.kind();
+        // The translation unit and `extern "C"` blocks do not add a scope.
+        let (flow, scope) = if depth == 0 || kind == Kind::LinkageSpecDecl {
+            (Flow::Continue, None)
+        } else if kind.is_a(Kind::NamedDecl) {
+            self.declare(node, kind)
+        } else {
+            (Flow::Skip, None)
+        };
+        self.frames.push(scope.is_some());
+        self.scopes.extend(scope);
+        flow
+    }
+
+    fn leave(&mut self, _node: &'ast Node<T>, _depth: usize) -> Flow {
+        if self.frames.pop() == Some(true) {
+            self.scopes.pop();
+        }
+        Flow::Continue
+    }
+}
+
+impl<'p, 'ast, T> Collector<'p, 'ast, T>
+where
+    T: HasDecl,
+{
+    // Enters a named declaration into the symbol table, and decides whether to
+    // look for more declarations inside of it.
+    fn declare(&mut self, node: &'ast Node<T>, kind: Kind) -> (Flow, Option<Scope>) {
+        let outer = node
+            .kind
+            .parent_decl_context()
+            .and_then(|id| self.by_id.get(&id))
+            .or(self.scopes.last());
+        let name = node.kind.name().filter(|name| !name.is_empty());
+        let is_scope = kind == Kind::NamespaceDecl || kind.is_a(Kind::TagDecl);
+
+        // Every class contains an implicit declaration of its own name.
+        if kind.is_a(Kind::RecordDecl) {
+            if let (Some(name), Some(outer)) = (name, outer) {
+                if outer.symbol.map_or(false, |outer| {
+                    let outer = &self.project.symbols[outer].key;
+                    outer.kind.is_a(Kind::RecordDecl)
+                        && outer.qualified_name.rsplit("::").next() == Some(name)
+                }) {
+                    return (Flow::Skip, None);
+                }
+            }
+        }
+
+        let anonymous_namespace = outer.map_or(false, |outer| outer.anonymous_namespace)
+            || (kind == Kind::NamespaceDecl && name.is_none());
+        let qualified_name = {
+            let name = match name {
+                Some(name) => name,
+                None if kind == Kind::NamespaceDecl => "(anonymous namespace)",
+                None => "(anonymous)",
+            };
+            match outer {
+                Some(outer) if !outer.qualified_name.is_empty() => {
+                    format!("{}::{}", outer.qualified_name, name)
+                }
+                _ => name.to_owned(),
+            }
+        };
+        let parent = outer.and_then(|outer| outer.symbol);
+        // A static member of a class has external linkage like the class.
+        let internal_linkage = node.kind.storage_class() == Some("static")
+            && !parent.map_or(false, |parent| {
+                self.project.symbols[parent].key.kind.is_a(Kind::RecordDecl)
+            });
+
+        let symbol = name.map(|_| {
+            let key = SymbolKey {
+                kind,
+                qualified_name: qualified_name.clone(),
+                mangled_name: node.kind.mangled_name().map(str::to_owned),
+                unit: if anonymous_namespace {
+                    Some(self.unit)
+                } else {
+                    None
+                },
+                file: if internal_linkage {
+                    node.kind
+                        .loc()
+                        .and_then(SourceLocation::file_loc)
+                        .map(|loc| Arc::clone(&loc.file))
+                } else {
+                    None
+                },
+            };
+            self.project.insert(key, parent, self.unit, node)
+        });
+
+        if is_scope {
+            let scope = Scope {
+                qualified_name,
+                symbol,
+                anonymous_namespace,
+            };
+            if node.id != Id::NULL {
+                self.by_id.insert(node.id, scope.clone());
+            }
+            (Flow::Continue, Some(scope))
+        } else if kind.is_a(Kind::TemplateDecl) {
+            // The templated declaration inside is named like the template.
+            (Flow::Continue, None)
+        } else {
+            (Flow::Skip, None)
+        }
+    }
+}
+
+impl<'ast, T> Project<'ast, T>
+where
+    T: HasDecl,
+{
+    fn insert(
+        &mut self,
+        key: SymbolKey,
+        parent: Option<usize>,
+        unit: usize,
+        node: &'ast Node<T>,
+    ) -> usize {
+        let i = match self.index.entry(key) {
+            Entry::Occupied(entry) => *entry.get(),
+            Entry::Vacant(entry) => {
+                let i = self.symbols.len();
+                self.by_name
+                    .entry(entry.key().qualified_name.clone())
+                    .or_default()
+                    .push(i);
+                self.symbols.push(Symbol {
+                    key: entry.key().clone(),
+                    declarations: Vec::new(),
+                    parent,
+                    members: Vec::new(),
+                });
+                entry.insert(i);
+                if let Some(parent) = parent {
+                    self.symbols[parent].members.push(i);
+                }
+                i
+            }
+        };
+
+        let loc = node.kind.loc().and_then(SourceLocation::file_loc);
+        let file = loc.map(|loc| Arc::clone(&loc.file));
+        let offset = loc.map(|loc| loc.offset);
+        let declarations = &mut self.symbols[i].declarations;
+        let position = declarations
+            .iter()
+            .position(|declaration| declaration.file == file && declaration.offset == offset);
+        let declaration = if let Some(position) = position {
+            &mut declarations[position]
+        } else {
+            declarations.push(Declaration {
+                file,
+                offset,
+                nodes: Vec::new(),
+            });
+            declarations.last_mut().unwrap()
+        };
+        declaration.nodes.push((unit, node));
+        self.nodes.insert(address(node), i);
+        i
+    }
+}
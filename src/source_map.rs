@@ -0,0 +1,224 @@
+use crate::loc::{BareSourceLocation, SourceLocation, SourceRange};
+use foldhash::HashMap;
+use std::cell::RefCell;
+use std::fmt::{self, Debug, Display};
+use std::fs;
+use std::ops::Deref;
+use std::sync::Arc;
+
+/// Contents of source files, for looking up the text covered by source
+/// locations and ranges.
+///
+/// Files are either inserted up front with [`insert`](SourceMap::insert), or
+/// read lazily the first time they are needed by the loader passed to
+/// [`with_loader`](SourceMap::with_loader). [`from_filesystem`] reads them
+/// from disk using the paths recorded in the AST dump.
+///
+/// [`from_filesystem`]: SourceMap::from_filesystem
+///
+/// ```no_run
+/// # use clang_ast::{SourceMap, SourceRange};
+/// #
+/// fn print_source(range: &SourceRange) {
+///     let source_map = SourceMap::from_filesystem();
+///     if let Some(text) = source_map.text(range) {
+///         println!("{}", text);
+///     }
+/// }
+/// ```
+pub struct SourceMap {
+    loader: Option<Box<Loader>>,
+    files: RefCell<HashMap<Arc<str>, Option<Arc<str>>>>,
+}
+
+/// Text of a range of a source file, as returned by [`SourceMap::text`].
+///
+/// Dereferences to `str`. It holds a reference count on the contents of the
+/// file, which remain valid even if the file is replaced in the source map.
+#[derive(Clone)]
+pub struct SourceText {
+    contents: Arc<str>,
+    begin: usize,
+    end: usize,
+}
+
+type Loader = dyn Fn(&str) -> Option<String>;
+
+impl SourceMap {
+    /// Source map containing only the files that are inserted into it.
+    pub fn new() -> Self {
+        SourceMap {
+            loader: None,
+            files: RefCell::new(HashMap::default()),
+        }
+    }
+
+    /// Source map which reads files on demand from the filesystem.
+    pub fn from_filesystem() -> Self {
+        SourceMap::with_loader(|file| fs::read_to_string(file).ok())
+    }
+
+    /// Source map which calls `loader` at most once per file, the first time
+    /// a file's contents are needed. Files for which the loader returns
+    /// `None` have no text.
+    pub fn with_loader(loader: impl Fn(&str) -> Option<String> + 'static) -> Self {
+        SourceMap {
+            loader: Some(Box::new(loader)),
+            files: RefCell::new(HashMap::default()),
+        }
+    }
+
+    /// Provides the contents of a file, replacing any previous contents.
+    pub fn insert(&mut self, file: impl Into<Arc<str>>, contents: impl Into<Arc<str>>) {
+        self.files
+            .get_mut()
+            .insert(file.into(), Some(contents.into()));
+    }
+
+    /// The entire contents of a file.
+    pub fn file(&self, file: &str) -> Option<Arc<str>> {
+        if let Some(contents) = self.files.borrow().get(file) {
+            return contents.clone();
+        }
+        // The map is not borrowed while the loader runs, in case the loader
+        // itself looks up files in this source map.
+        let contents = self.loader.as_ref().and_then(|loader| loader(file));
+        let contents = contents.map(Arc::from);
+        let mut files = self.files.borrow_mut();
+        files.entry(Arc::from(file)).or_insert(contents).clone()
+    }
+
+    /// Source text of a range, from the beginning of its first token to the
+    /// end of its last token, as it appears at the expansion locations.
+    ///
+    /// For a range involving macros, this is the text of the macro invocation
+    /// in the file where the macro is used. Returns `None` if the range is
+    /// empty, if the beginning and end are in different files, or if the file
+    /// is not available.
+    pub fn text(&self, range: &SourceRange) -> Option<SourceText> {
+        let begin = expansion(&range.begin)?;
+        let end = expansion(&range.end)?;
+        self.text_between(begin, end)
+    }
+
+    /// Source text of a range, as it appears at the spelling locations.
+    ///
+    /// For a range involving macros, this is text from within the macro's
+    /// definition or from the macro's arguments.
+    pub fn spelling_text(&self, range: &SourceRange) -> Option<SourceText> {
+        let begin = spelling(&range.begin)?;
+        let end = spelling(&range.end)?;
+        self.text_between(begin, end)
+    }
+
+    /// Source text of the single token at a location, as it appears at the
+    /// expansion location.
+    pub fn token(&self, loc: &SourceLocation) -> Option<SourceText> {
+        let loc = expansion(loc)?;
+        self.text_between(loc, loc)
+    }
+
+    fn text_between(
+        &self,
+        begin: &BareSourceLocation,
+        end: &BareSourceLocation,
+    ) -> Option<SourceText> {
+        if begin.file != end.file {
+            return None;
+        }
+        // The end of a range is the start of its last token.
+        let end = end.offset.checked_add(end.tok_len)?;
+        let contents = self.file(&begin.file)?;
+        contents.get(begin.offset..end)?;
+        Some(SourceText {
+            contents,
+            begin: begin.offset,
+            end,
+        })
+    }
+}
+
+fn expansion(loc: &SourceLocation) -> Option<&BareSourceLocation> {
+    loc.expansion_loc.as_ref().or(loc.spelling_loc.as_ref())
+}
+
+fn spelling(loc: &SourceLocation) -> Option<&BareSourceLocation> {
+    loc.spelling_loc.as_ref().or(loc.expansion_loc.as_ref())
+}
+
+impl Default for SourceMap {
+    fn default() -> Self {
+        SourceMap::new()
+    }
+}
+
+impl Debug for SourceMap {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let files = self.files.borrow();
+        let mut debug = formatter.debug_set();
+        for (file, contents) in files.iter() {
+            if contents.is_some() {
+                debug.entry(file);
+            }
+        }
+        debug.finish()
+    }
+}
+
+impl SourceText {
+    /// The entire contents of the file which the text is from.
+    pub fn file_contents(&self) -> &Arc<str> {
+        &self.contents
+    }
+
+    /// Byte offset of the beginning of the text within its file.
+    pub fn offset(&self) -> usize {
+        self.begin
+    }
+}
+
+impl Deref for SourceText {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.contents[self.begin..self.end]
+    }
+}
+
+impl AsRef<str> for SourceText {
+    fn as_ref(&self) -> &str {
+        self
+    }
+}
+
+impl PartialEq for SourceText {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl Eq for SourceText {}
+
+impl PartialEq<str> for SourceText {
+    fn eq(&self, other: &str) -> bool {
+        **self == *other
+    }
+}
+
+impl<'a> PartialEq<&'a str> for SourceText {
+    fn eq(&self, other: &&'a str) -> bool {
+        **self == **other
+    }
+}
+
+impl Display for SourceText {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        Display::fmt(&**self, formatter)
+    }
+}
+
+impl Debug for SourceText {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        Debug::fmt(&**self, formatter)
+    }
+}
@@ -0,0 +1,124 @@
+use crate::error::Error;
+use crate::intern;
+use crate::json::{self, Scanner};
+use crate::loc::{self, ThreadLocalState};
+use crate::Node;
+use serde::de::Deserialize;
+use std::iter::FusedIterator;
+use std::mem;
+
+/// Deserializes the children of the root of a syntax tree one at a time.
+///
+/// For a dump of a whole translation unit, each item is one top-level
+/// declaration. The items are parsed lazily as the iterator is advanced, so
+/// only the item currently held by the caller needs to be in memory rather
+/// than the entire tree. Combined with a memory-mapped input file, this allows
+/// processing dumps that are much larger than the available memory.
+///
+/// ```no_run
+/// # use serde_derive::Deserialize;
+/// #
+/// # #[derive(Deserialize)]
+/// # enum Clang {
+/// #     Other,
+/// # }
+/// #
+/// fn main() -> Result<(), clang_ast::Error> {
+///     let json = std::fs::read("ast.json").unwrap();
+///     for decl in clang_ast::top_level::<Clang>(&json)? {
+///         let decl = decl?;
+///         // ...
+///         # let _ = decl;
+///     }
+///     Ok(())
+/// }
+/// ```
+///
+/// Like [`from_slice`](crate::from_slice), this requires the `"inner"` field
+/// to come after all other fields of a node. Locations are resolved exactly as
+/// if the whole tree had been deserialized at once, even if other dumps are
+/// deserialized on the same thread between items.
+pub fn top_level<'de, T>(bytes: &'de [u8]) -> Result<TopLevel<'de, T>, Error>
+where
+    T: Deserialize<'de>,
+{
+    let mut scanner = Scanner::new(bytes);
+    let mut loc = ThreadLocalState::default();
+    let (root, has_inner) = resume(&mut loc, || json::begin_node(&mut scanner))?;
+    if !has_inner {
+        scanner.end()?;
+    }
+    Ok(TopLevel {
+        scanner,
+        loc,
+        root,
+        first: true,
+        done: !has_inner,
+    })
+}
+
+/// Iterator over the top-level nodes of a syntax tree, returned by
+/// [`top_level`].
+pub struct TopLevel<'de, T> {
+    scanner: Scanner<'de>,
+    loc: ThreadLocalState,
+    root: Node<T>,
+    first: bool,
+    done: bool,
+}
+
+impl<'de, T> TopLevel<'de, T> {
+    /// The root node, whose `inner` is always empty.
+    pub fn root(&self) -> &Node<T> {
+        &self.root
+    }
+}
+
+impl<'de, T> Iterator for TopLevel<'de, T>
+where
+    T: Deserialize<'de>,
+{
+    type Item = Result<Node<T>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let scanner = &mut self.scanner;
+        let first = mem::replace(&mut self.first, false);
+        let result = resume(&mut self.loc, || {
+            if scanner.eat(b']') {
+                scanner.end_node()?;
+                scanner.end()?;
+                return Ok(None);
+            }
+            if !first {
+                scanner.expect(b',', "expected `,` or `]`")?;
+            }
+            json::parse_node(scanner, None).map(Some)
+        });
+        match result {
+            Ok(Some(node)) => Some(Ok(node)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(error) => {
+                self.done = true;
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+impl<'de, T> FusedIterator for TopLevel<'de, T> where T: Deserialize<'de> {}
+
+// Runs `f` with this dump's "same as previous" location state installed in the
+// thread-local, and saves the state afterward for the next item.
+fn resume<R>(state: &mut ThreadLocalState, f: impl FnOnce() -> R) -> R {
+    let _intern = intern::activate();
+    let outer = loc::thread_local_replace(mem::take(state));
+    let result = f();
+    *state = loc::thread_local_replace(outer);
+    result
+}
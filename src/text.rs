@@ -0,0 +1,404 @@
+use crate::hierarchy::Category;
+use crate::loc::{BareSourceLocation, SourceLocation, SourceRange};
+use crate::{HasKind, HasRange, Id, Kind, Node};
+use std::fmt::{self, Display};
+
+/// Renders a syntax tree as text, the way `clang -Xclang -ast-dump` prints it.
+///
+/// Each node is printed on one line as its kind, id and source range, followed
+/// by whatever [`TextAttributes`] the caller's `T` provides, and indented
+/// beneath its parent with `|-` and `` `- `` like Clang's TextNodeDumper:
+///
+/// ```text
+/// TranslationUnitDecl 0x1c7e3b8 <<invalid sloc>> <invalid sloc>
+/// `-FunctionDecl 0x1cbf1e8 <main.c:1:1, line:3:1> line:1:5 main 'int ()'
+///   `-CompoundStmt 0x1cbf330 <col:12, line:3:1>
+///     `-ReturnStmt 0x1cbf320 <line:2:3, col:10>
+///       `-IntegerLiteral 0x1cbf300 <col:10> 'int' 0
+/// ```
+///
+/// Locations are printed relative to the previous one, omitting the file and
+/// line when unchanged.
+///
+/// ```
+/// # use clang_ast::{HasKind, HasRange, Kind, SourceRange, TextAttributes, TextWriter};
+/// # use serde_derive::Deserialize;
+/// #
+/// # #[derive(Deserialize)]
+/// # struct Clang {
+/// #     kind: Kind,
+/// #     #[serde(default)]
+/// #     range: SourceRange,
+/// # }
+/// #
+/// # impl HasKind for Clang {
+/// #     fn kind(&self) -> Kind {
+/// #         self.kind
+/// #     }
+/// # }
+/// #
+/// # impl HasRange for Clang {
+/// #     fn range(&self) -> Option<&SourceRange> {
+/// #         Some(&self.range)
+/// #     }
+/// # }
+/// #
+/// # impl TextAttributes for Clang {}
+/// #
+/// use clang_ast::TextDump;
+///
+/// fn print(node: &clang_ast::Node<Clang>) {
+///     print!("{}", TextDump::new(node).color(true));
+/// }
+/// ```
+pub struct TextDump<'a, T> {
+    node: &'a Node<T>,
+    color: bool,
+}
+
+/// The details of a node which a [`TextDump`] prints after its kind, id and
+/// source range.
+///
+/// Typically these are read from the kind-specific fields of the node, such as
+/// the `"name"` and `"type"` of a declaration. Each of the methods of
+/// [`TextWriter`] prints one attribute in the same format and color that Clang
+/// uses for it.
+///
+/// ```
+/// # use clang_ast::{HasKind, HasRange, Kind, SourceLocation, SourceRange};
+/// use clang_ast::{Category, TextAttributes, TextWriter};
+/// # use serde_derive::Deserialize;
+/// use std::fmt;
+///
+/// #[derive(Deserialize)]
+/// struct Clang {
+///     kind: Kind,
+///     #[serde(default)]
+///     loc: SourceLocation,
+///     #[serde(default)]
+///     range: SourceRange,
+///     name: Option<String>,
+///     #[serde(rename = "type")]
+///     ty: Option<Type>,
+/// }
+///
+/// #[derive(Deserialize)]
+/// #[serde(rename_all = "camelCase")]
+/// struct Type {
+///     qual_type: String,
+///     desugared_qual_type: Option<String>,
+/// }
+/// #
+/// # impl HasKind for Clang {
+/// #     fn kind(&self) -> Kind {
+/// #         self.kind
+/// #     }
+/// # }
+/// #
+/// # impl HasRange for Clang {
+/// #     fn range(&self) -> Option<&SourceRange> {
+/// #         Some(&self.range)
+/// #     }
+/// # }
+///
+/// impl TextAttributes for Clang {
+///     fn loc(&self) -> Option<&SourceLocation> {
+///         if self.kind.category() == Category::Decl {
+///             Some(&self.loc)
+///         } else {
+///             None
+///         }
+///     }
+///
+///     fn write_attributes(&self, out: &mut TextWriter) -> fmt::Result {
+///         if let Some(name) = &self.name {
+///             out.name(name)?;
+///         }
+///         if let Some(ty) = &self.ty {
+///             out.qual_type(&ty.qual_type, ty.desugared_qual_type.as_deref())?;
+///         }
+///         Ok(())
+///     }
+/// }
+/// ```
+pub trait TextAttributes: HasKind + HasRange {
+    /// The location which Clang prints after the range of a declaration,
+    /// which is its `"loc"`.
+    fn loc(&self) -> Option<&SourceLocation> {
+        None
+    }
+
+    /// Writes the rest of the node's line.
+    fn write_attributes(&self, out: &mut TextWriter) -> fmt::Result {
+        let _ = out;
+        Ok(())
+    }
+}
+
+/// Output of a [`TextDump`], through which [`TextAttributes`] are written.
+///
+/// Each method begins with a space separating the attribute from what was
+/// printed before it on the same line.
+pub struct TextWriter<'a> {
+    out: &'a mut dyn fmt::Write,
+    color: bool,
+    last_file: String,
+    last_line: usize,
+}
+
+#[derive(Copy, Clone)]
+struct Color {
+    code: u8,
+    bold: bool,
+}
+
+// The same colors as Clang's ASTDumperUtils.h.
+const GREEN: u8 = 2;
+const YELLOW: u8 = 3;
+const BLUE: u8 = 4;
+const MAGENTA: u8 = 5;
+const CYAN: u8 = 6;
+const DECL_KIND_COLOR: Color = Color {
+    code: GREEN,
+    bold: true,
+};
+const STMT_COLOR: Color = Color {
+    code: MAGENTA,
+    bold: true,
+};
+const TYPE_KIND_COLOR: Color = Color {
+    code: GREEN,
+    bold: false,
+};
+const ATTR_COLOR: Color = Color {
+    code: BLUE,
+    bold: true,
+};
+const COMMENT_COLOR: Color = Color {
+    code: BLUE,
+    bold: false,
+};
+const INDENT_COLOR: Color = Color {
+    code: BLUE,
+    bold: false,
+};
+const NULL_COLOR: Color = Color {
+    code: BLUE,
+    bold: false,
+};
+const ADDRESS_COLOR: Color = Color {
+    code: YELLOW,
+    bold: false,
+};
+const LOCATION_COLOR: Color = Color {
+    code: YELLOW,
+    bold: false,
+};
+const TYPE_COLOR: Color = Color {
+    code: GREEN,
+    bold: false,
+};
+const VALUE_KIND_COLOR: Color = Color {
+    code: CYAN,
+    bold: false,
+};
+const DECL_NAME_COLOR: Color = Color {
+    code: CYAN,
+    bold: true,
+};
+const VALUE_COLOR: Color = Color {
+    code: CYAN,
+    bold: true,
+};
+
+impl<'a, T> TextDump<'a, T> {
+    pub fn new(node: &'a Node<T>) -> Self {
+        TextDump { node, color: false }
+    }
+
+    /// Whether to color the output with ANSI escape sequences, as Clang does
+    /// when printing to a terminal. Off by default.
+    #[must_use]
+    pub fn color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+}
+
+impl<'a, T> Display for TextDump<'a, T>
+where
+    T: TextAttributes,
+{
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let mut out = TextWriter {
+            out: formatter,
+            color: self.color,
+            last_file: String::new(),
+            last_line: 0,
+        };
+        out.node(self.node)?;
+
+        // The tree lines to the left of the children being printed, two
+        // characters per level of nesting below the root.
+        let mut indent = String::new();
+        let mut stack = vec![self.node.inner.iter()];
+        while let Some(siblings) = stack.last_mut() {
+            let Some(node) = siblings.next() else {
+                stack.pop();
+                indent.truncate(indent.len().saturating_sub(2));
+                continue;
+            };
+            let last = siblings.len() == 0;
+            let branch = if last { "`-" } else { "|-" };
+            out.colored(INDENT_COLOR, format_args!("{}{}", indent, branch))?;
+            out.node(node)?;
+            indent.push_str(if last { "  " } else { "| " });
+            stack.push(node.inner.iter());
+        }
+        Ok(())
+    }
+}
+
+impl<'a> TextWriter<'a> {
+    /// Prints text as is, such as `implicit` or `used`.
+    pub fn plain(&mut self, text: impl Display) -> fmt::Result {
+        write!(self.out, " {}", text)
+    }
+
+    /// Prints the name of a declaration.
+    pub fn name(&mut self, name: &str) -> fmt::Result {
+        self.out.write_char(' ')?;
+        self.colored(DECL_NAME_COLOR, name)
+    }
+
+    /// Prints a type in quotes, followed by its desugared form if that is
+    /// different, as in `'size_t':'unsigned long'`.
+    pub fn qual_type(&mut self, qual_type: &str, desugared: Option<&str>) -> fmt::Result {
+        self.out.write_char(' ')?;
+        match desugared {
+            Some(desugared) if desugared != qual_type => {
+                self.colored(TYPE_COLOR, format_args!("'{}':'{}'", qual_type, desugared))
+            }
+            _ => self.colored(TYPE_COLOR, format_args!("'{}'", qual_type)),
+        }
+    }
+
+    /// Prints the `"valueCategory"` of an expression. Like Clang, this prints
+    /// nothing for a prvalue.
+    pub fn value_category(&mut self, value_category: &str) -> fmt::Result {
+        if value_category == "prvalue" {
+            return Ok(());
+        }
+        self.out.write_char(' ')?;
+        self.colored(VALUE_KIND_COLOR, value_category)
+    }
+
+    /// Prints a value, such as that of a literal or an operator.
+    pub fn value(&mut self, value: impl Display) -> fmt::Result {
+        self.out.write_char(' ')?;
+        self.colored(VALUE_COLOR, value)
+    }
+
+    /// Prints the id of a node, such as a referenced declaration.
+    pub fn id(&mut self, id: Id) -> fmt::Result {
+        self.out.write_char(' ')?;
+        self.colored(ADDRESS_COLOR, id)
+    }
+
+    /// Prints a location, relative to the previous one printed.
+    pub fn location(&mut self, loc: &SourceLocation) -> fmt::Result {
+        self.out.write_char(' ')?;
+        self.write_location(loc)
+    }
+
+    /// Prints a source range in angle brackets.
+    pub fn range(&mut self, range: &SourceRange) -> fmt::Result {
+        self.out.write_str(" <")?;
+        self.write_location(&range.begin)?;
+        if range.end != range.begin {
+            self.out.write_str(", ")?;
+            self.write_location(&range.end)?;
+        }
+        self.out.write_char('>')
+    }
+
+    fn node<T>(&mut self, node: &Node<T>) -> fmt::Result
+    where
+        T: TextAttributes,
+    {
+        let kind = node.kind.kind();
+        if kind == Kind::null {
+            // The `{}` which Clang puts in place of an absent child.
+            self.colored(NULL_COLOR, "<<<NULL>>>")?;
+            return self.out.write_char('\n');
+        }
+
+        let color = match kind.category() {
+            Category::Decl => Some(DECL_KIND_COLOR),
+            Category::Stmt | Category::Expr => Some(STMT_COLOR),
+            Category::Type => Some(TYPE_KIND_COLOR),
+            Category::Attr => Some(ATTR_COLOR),
+            Category::Comment => Some(COMMENT_COLOR),
+            Category::Other => None,
+        };
+        match color {
+            Some(color) => self.colored(color, kind)?,
+            None => write!(self.out, "{}", kind)?,
+        }
+        if node.id != Id::NULL {
+            self.id(node.id)?;
+        }
+        // Types have no location in the source.
+        if kind.category() != Category::Type {
+            if let Some(range) = node.kind.range() {
+                self.range(range)?;
+            }
+        }
+        if let Some(loc) = node.kind.loc() {
+            self.location(loc)?;
+        }
+        node.kind.write_attributes(self)?;
+        self.out.write_char('\n')
+    }
+
+    fn write_location(&mut self, loc: &SourceLocation) -> fmt::Result {
+        let Some(expansion_loc) = loc.expansion_loc.as_ref().or(loc.spelling_loc.as_ref()) else {
+            return self.colored(LOCATION_COLOR, "<invalid sloc>");
+        };
+        self.write_bare_location(expansion_loc)?;
+        if let Some(spelling_loc) = &loc.spelling_loc {
+            if spelling_loc != expansion_loc {
+                self.out.write_str(" <Spelling=")?;
+                self.write_bare_location(spelling_loc)?;
+                self.out.write_char('>')?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_bare_location(&mut self, loc: &BareSourceLocation) -> fmt::Result {
+        let presumed = loc.presumed();
+        if presumed.file != self.last_file {
+            self.last_file.clear();
+            self.last_file.push_str(presumed.file);
+            self.last_line = presumed.line;
+            self.colored(LOCATION_COLOR, presumed)
+        } else if presumed.line != self.last_line {
+            self.last_line = presumed.line;
+            let line = presumed.line;
+            let col = presumed.col;
+            self.colored(LOCATION_COLOR, format_args!("line:{}:{}", line, col))
+        } else {
+            self.colored(LOCATION_COLOR, format_args!("col:{}", presumed.col))
+        }
+    }
+
+    fn colored(&mut self, color: Color, text: impl Display) -> fmt::Result {
+        if self.color {
+            let bold = if color.bold { "1;" } else { "" };
+            write!(self.out, "\x1b[0;{}3{}m{}\x1b[0m", bold, color.code, text)
+        } else {
+            write!(self.out, "{}", text)
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use crate::Node;
+use std::mem;
+use std::slice;
+
+/// Control flow returned by the hooks of [`Visit`] and [`VisitMut`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Flow {
+    /// Keep traversing normally.
+    Continue,
+    /// Do not descend into the children of the current node. The node's
+    /// `leave` hook is still called. When returned from `leave`, this is the
+    /// same as `Continue`.
+    Skip,
+    /// Abort the traversal. No further hooks are called.
+    Break,
+}
+
+/// Read-only traversal of a syntax tree.
+///
+/// Both hooks receive the node's depth, where the node on which the traversal
+/// was started has depth 0. The traversal uses a heap-allocated stack so trees
+/// of any depth can be walked on a thread with an ordinary stack size.
+///
+/// ```
+/// # use clang_ast::{Flow, Node, Visit};
+/// #
+/// struct MaxDepth(usize);
+///
+/// impl<'ast, T> Visit<'ast, T> for MaxDepth {
+///     fn enter(&mut self, _node: &'ast Node<T>, depth: usize) -> Flow {
+///         self.0 = self.0.max(depth);
+///         Flow::Continue
+///     }
+/// }
+/// #
+/// # fn max_depth<T>(node: &Node<T>) -> usize {
+/// #     let mut visitor = MaxDepth(0);
+/// #     node.visit(&mut visitor);
+/// #     visitor.0
+/// # }
+/// ```
+pub trait Visit<'ast, T> {
+    /// Called before any of the node's children are visited.
+    fn enter(&mut self, node: &'ast Node<T>, depth: usize) -> Flow {
+        let _ = node;
+        let _ = depth;
+        Flow::Continue
+    }
+
+    /// Called after all of the node's children have been visited.
+    fn leave(&mut self, node: &'ast Node<T>, depth: usize) -> Flow {
+        let _ = node;
+        let _ = depth;
+        Flow::Continue
+    }
+}
+
+/// Traversal of a syntax tree with mutable access to each node.
+///
+/// The `enter` hook runs before the node's children are visited, so changes it
+/// makes to `node.inner` determine which children the traversal descends into.
+pub trait VisitMut<T> {
+    /// Called before any of the node's children are visited.
+    fn enter(&mut self, node: &mut Node<T>, depth: usize) -> Flow {
+        let _ = node;
+        let _ = depth;
+        Flow::Continue
+    }
+
+    /// Called after all of the node's children have been visited.
+    fn leave(&mut self, node: &mut Node<T>, depth: usize) -> Flow {
+        let _ = node;
+        let _ = depth;
+        Flow::Continue
+    }
+}
+
+impl<T> Node<T> {
+    /// Walks this node and all of its descendants in depth-first order.
+    ///
+    /// Returns `Flow::Break` if a hook aborted the traversal, otherwise
+    /// `Flow::Continue`.
+    pub fn visit<'ast, V>(&'ast self, visitor: &mut V) -> Flow
+    where
+        V: ?Sized + Visit<'ast, T>,
+    {
+        let mut stack: Vec<(&'ast Node<T>, slice::Iter<'ast, Node<T>>)> = Vec::new();
+        match visitor.enter(self, 0) {
+            Flow::Continue => stack.push((self, self.inner.iter())),
+            Flow::Skip => stack.push((self, [].iter())),
+            Flow::Break => return Flow::Break,
+        }
+
+        while let Some((node, children)) = stack.last_mut() {
+            let node = *node;
+            if let Some(child) = children.next() {
+                match visitor.enter(child, stack.len()) {
+                    Flow::Continue => stack.push((child, child.inner.iter())),
+                    Flow::Skip => stack.push((child, [].iter())),
+                    Flow::Break => return Flow::Break,
+                }
+            } else {
+                stack.pop();
+                if let Flow::Break = visitor.leave(node, stack.len()) {
+                    return Flow::Break;
+                }
+            }
+        }
+
+        Flow::Continue
+    }
+
+    /// Walks this node and all of its descendants in depth-first order, with
+    /// mutable access to each node.
+    ///
+    /// Returns `Flow::Break` if a hook aborted the traversal, otherwise
+    /// `Flow::Continue`.
+    pub fn visit_mut<V>(&mut self, visitor: &mut V) -> Flow
+    where
+        V: ?Sized + VisitMut<T>,
+    {
+        // While a node's children are being visited, they are moved out of
+        // the node onto this stack, together with the index of the next child
+        // to visit. They are moved back before the node's `leave` hook.
+        let mut stack: Vec<(Vec<Node<T>>, usize)> = Vec::new();
+        match visitor.enter(self, 0) {
+            Flow::Continue => stack.push((mem::take(&mut self.inner), 0)),
+            Flow::Skip => return visitor.leave(self, 0),
+            Flow::Break => return Flow::Break,
+        }
+
+        let flow = loop {
+            let depth = stack.len();
+            let Some((children, next)) = stack.last_mut() else {
+                break Flow::Continue;
+            };
+            if let Some(child) = children.get_mut(*next) {
+                *next += 1;
+                match visitor.enter(child, depth) {
+                    Flow::Continue => {
+                        let inner = mem::take(&mut child.inner);
+                        stack.push((inner, 0));
+                    }
+                    Flow::Skip => {
+                        if let Flow::Break = visitor.leave(child, depth) {
+                            break Flow::Break;
+                        }
+                    }
+                    Flow::Break => break Flow::Break,
+                }
+            } else {
+                let children = mem::take(children);
+                stack.pop();
+                let node = parent_of_children(self, &mut stack);
+                node.inner = children;
+                if let Flow::Break = visitor.leave(node, depth - 1) {
+                    break Flow::Break;
+                }
+            }
+        };
+
+        // Put back the children of every node still being visited if the
+        // traversal was aborted.
+        while let Some((children, _next)) = stack.pop() {
+            parent_of_children(self, &mut stack).inner = children;
+        }
+        flow
+    }
+}
+
+// The node whose children are at the top of the stack once that entry has been
+// popped, which is the child most recently entered one level up.
+fn parent_of_children<'a, T>(
+    root: &'a mut Node<T>,
+    stack: &'a mut [(Vec<Node<T>>, usize)],
+) -> &'a mut Node<T> {
+    match stack.last_mut() {
+        Some((children, next)) => &mut children[*next - 1],
+        None => root,
+    }
+}
@@ -0,0 +1,83 @@
+use clang_ast::{Id, Kind, SourceLocation, SourceRange};
+use serde_derive::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+pub type Node = clang_ast::Node<Clang>;
+
+#[derive(Deserialize, Serialize, PartialEq, Debug)]
+pub struct Clang {
+    pub kind: Kind,
+    #[serde(default)]
+    pub loc: SourceLocation,
+    #[serde(default)]
+    pub range: SourceRange,
+    #[serde(flatten)]
+    pub data: Map<String, Value>,
+}
+
+#[test]
+fn test_round_trip() {
+    let json = clang_ast_test_suite::cxx_ast_json();
+    let node: Node = clang_ast::from_slice(&json).unwrap();
+
+    let binary = clang_ast::to_binary(&node).unwrap();
+    assert!(binary.len() * 3 < json.len());
+
+    let round_trip: Node = clang_ast::from_binary(&binary).unwrap();
+    assert_eq!(node, round_trip);
+    assert_eq!(
+        serde_json::to_value(&node).unwrap(),
+        serde_json::to_value(&round_trip).unwrap(),
+    );
+}
+
+#[test]
+fn test_untyped() {
+    let json = r#"{
+      "id": "0x1",
+      "kind": "TranslationUnitDecl",
+      "inner": [
+        {
+          "id": "0x2",
+          "kind": "VarDecl",
+          "loc": {"offset": 120, "file": "a.h", "line": 9, "col": 5, "tokLen": 1},
+          "type": {"qualType": "int"},
+          "inner": [
+            {"id": "0x3", "kind": "IntegerLiteral", "value": "-1"}
+          ]
+        },
+        {
+          "id": "0x4",
+          "kind": "VarDecl",
+          "loc": {"offset": 60, "line": 4, "col": 5, "tokLen": 1},
+          "type": {"qualType": "int"},
+          "isUsed": true
+        }
+      ]
+    }"#;
+    let node: clang_ast::Node<Value> = clang_ast::from_str(json).unwrap();
+    let binary = clang_ast::to_binary(&node).unwrap();
+    let round_trip: clang_ast::Node<Value> = clang_ast::from_binary(&binary).unwrap();
+    assert_eq!(node, round_trip);
+    assert_eq!(round_trip.inner[1].id, node.inner[1].id);
+    assert_ne!(round_trip.inner[1].id, Id::NULL);
+}
+
+#[test]
+fn test_errors() {
+    let node: clang_ast::Node<Value> =
+        clang_ast::from_str(r#"{"id": "0x1", "kind": "TranslationUnitDecl"}"#).unwrap();
+    let binary = clang_ast::to_binary(&node).unwrap();
+
+    let error = clang_ast::from_binary::<Value>(b"{}").unwrap_err();
+    assert_eq!(error.to_string(), "not a binary syntax tree");
+
+    let truncated = &binary[..binary.len() - 1];
+    let error = clang_ast::from_binary::<Value>(truncated).unwrap_err();
+    assert!(error.to_string().starts_with("unexpected end of input"));
+
+    let mut trailing = binary.clone();
+    trailing.push(0);
+    let error = clang_ast::from_binary::<Value>(&trailing).unwrap_err();
+    assert!(error.to_string().starts_with("trailing bytes"));
+}
@@ -0,0 +1,219 @@
+use clang_ast::Kind;
+use serde_derive::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+pub type Node<'a> = clang_ast::Node<Clang<'a>>;
+
+#[derive(Deserialize, Debug)]
+pub enum Clang<'a> {
+    VarDecl(#[serde(borrow)] VarDecl<'a>),
+    Other {
+        kind: Kind,
+        #[serde(borrow, default)]
+        name: Cow<'a, str>,
+    },
+}
+
+#[derive(Deserialize, Debug)]
+pub struct VarDecl<'a> {
+    pub name: &'a str,
+    #[serde(borrow, rename = "type")]
+    pub ty: Type<'a>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Type<'a> {
+    #[serde(borrow, rename = "qualType")]
+    pub qual_type: Cow<'a, str>,
+}
+
+const JSON: &str = r#"{
+  "id": "0x1",
+  "kind": "TranslationUnitDecl",
+  "inner": [
+    {
+      "id": "0x2",
+      "kind": "VarDecl",
+      "loc": {"offset": 4, "file": "main.cc", "line": 1, "col": 5, "tokLen": 1},
+      "name": "x",
+      "type": {"qualType": "const char *"}
+    },
+    {
+      "id": "0x3",
+      "kind": "FunctionDecl",
+      "name": "f"
+    },
+    {
+      "id": "0x4",
+      "kind": "FunctionDecl",
+      "name": "operator\"\"_s"
+    }
+  ]
+}"#;
+
+fn is_borrowed(string: &str) -> bool {
+    JSON.as_bytes().as_ptr_range().contains(&string.as_ptr())
+}
+
+fn check(node: &Node) {
+    let Clang::VarDecl(var) = &node.inner[0].kind else {
+        panic!("expected VarDecl");
+    };
+    assert_eq!(var.name, "x");
+    assert!(is_borrowed(var.name));
+    assert!(matches!(var.ty.qual_type, Cow::Borrowed("const char *")));
+
+    let Clang::Other { kind, name } = &node.inner[1].kind else {
+        panic!("expected Other");
+    };
+    assert_eq!(*kind, Kind::FunctionDecl);
+    assert!(matches!(name, Cow::Borrowed("f")));
+    assert!(is_borrowed(name));
+
+    // Strings containing escape sequences cannot point into the input.
+    let Clang::Other { name, .. } = &node.inner[2].kind else {
+        panic!("expected Other");
+    };
+    assert!(matches!(name, Cow::Owned(name) if name == "operator\"\"_s"));
+}
+
+#[test]
+fn test_borrowed() {
+    check(&clang_ast::from_str::<Clang>(JSON).unwrap());
+    check(&clang_ast::from_slice::<Clang>(JSON.as_bytes()).unwrap());
+    check(&serde_json::from_str::<Node>(JSON).unwrap());
+    check(&clang_ast::from_slice_filtered::<Clang>(JSON.as_bytes(), |_loc| true).unwrap());
+
+    let inner = clang_ast::top_level::<Clang>(JSON.as_bytes())
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let root = clang_ast::Node {
+        id: clang_ast::Id::NULL,
+        kind: Clang::Other {
+            kind: Kind::TranslationUnitDecl,
+            name: Cow::Borrowed(""),
+        },
+        inner,
+    };
+    check(&root);
+}
+
+#[test]
+fn test_escaped_str() {
+    // A `&str` field has nothing to fall back to when the string is escaped.
+    #[derive(Deserialize, Debug)]
+    #[allow(dead_code)]
+    struct Named<'a> {
+        name: Option<&'a str>,
+    }
+
+    let error = clang_ast::from_str::<Named>(JSON).unwrap_err();
+    assert!(
+        error.to_string().starts_with("invalid type: string"),
+        "{error}"
+    );
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Located<'a> {
+    #[serde(borrow, default)]
+    pub loc: clang_ast::BorrowedSourceLocation<'a>,
+    #[serde(borrow, default)]
+    pub range: clang_ast::BorrowedSourceRange<'a>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct Owned {
+    #[serde(default)]
+    pub loc: clang_ast::SourceLocation,
+    #[serde(default)]
+    pub range: clang_ast::SourceRange,
+}
+
+const LOC_JSON: &str = r#"{
+  "id": "0x1",
+  "kind": "TranslationUnitDecl",
+  "inner": [
+    {
+      "id": "0x2",
+      "kind": "VarDecl",
+      "loc": {"offset": 4, "file": "main.cc", "line": 1, "presumedFile": "gen.y", "col": 5, "tokLen": 1},
+      "range": {
+        "begin": {"offset": 0, "col": 1, "tokLen": 3},
+        "end": {"offset": 4, "col": 5, "tokLen": 1}
+      }
+    },
+    {
+      "id": "0x3",
+      "kind": "VarDecl",
+      "loc": {"offset": 4, "file": "include\/a.h", "line": 1, "col": 5, "tokLen": 1, "includedFrom": {"file": "main.cc"}},
+      "range": {
+        "begin": {"offset": 0, "col": 1, "tokLen": 3},
+        "end": {"offset": 4, "col": 5, "tokLen": 1}
+      }
+    }
+  ]
+}"#;
+
+#[test]
+fn test_borrowed_file() {
+    let root = clang_ast::from_str::<Located>(LOC_JSON).unwrap();
+
+    let node = &root.inner[0].kind;
+    let file = &node.loc.spelling_loc.as_ref().unwrap().file;
+    assert!(matches!(file, clang_ast::FilePath::Borrowed(file) if is_borrowed_loc(file)));
+    assert_eq!(*file, "main.cc");
+    let presumed_file = node
+        .loc
+        .spelling_loc
+        .as_ref()
+        .unwrap()
+        .presumed_file
+        .as_ref();
+    assert!(
+        matches!(presumed_file, Some(clang_ast::FilePath::Borrowed(file)) if is_borrowed_loc(file))
+    );
+    // Omitted by Clang because it is unchanged since the previous location.
+    let file = &node.range.end.expansion_loc.as_ref().unwrap().file;
+    assert!(matches!(file, clang_ast::FilePath::Shared(_)));
+    assert_eq!(*file, "main.cc");
+
+    // Paths containing escape sequences cannot point into the input.
+    let node = &root.inner[1].kind;
+    let loc = node.loc.spelling_loc.as_ref().unwrap();
+    assert!(matches!(loc.file, clang_ast::FilePath::Shared(_)));
+    assert_eq!(loc.file, "include/a.h");
+    assert_eq!(loc.included_from.as_ref().unwrap().file.as_ref(), "main.cc");
+
+    // Same locations as the owned types.
+    let owned = clang_ast::from_str::<Owned>(LOC_JSON).unwrap();
+    for (node, owned) in root.inner.iter().zip(&owned.inner) {
+        let loc = clang_ast::SourceLocation::from(node.kind.loc.clone());
+        assert_eq!(loc, owned.kind.loc);
+        let range = clang_ast::SourceRange::from(node.kind.range.clone());
+        assert_eq!(range, owned.kind.range);
+    }
+
+    // And serialized the same way.
+    let json = clang_ast::faithful(|| serde_json::to_value(&root)).unwrap();
+    let expected = clang_ast::faithful(|| serde_json::to_value(&owned)).unwrap();
+    assert_eq!(json, expected);
+    let expected: serde_json::Value = serde_json::from_str(LOC_JSON).unwrap();
+    for (node, expected) in json["inner"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .zip(expected["inner"].as_array().unwrap())
+    {
+        assert_eq!(node["loc"], expected["loc"]);
+        assert_eq!(node["range"], expected["range"]);
+    }
+}
+
+fn is_borrowed_loc(string: &str) -> bool {
+    LOC_JSON
+        .as_bytes()
+        .as_ptr_range()
+        .contains(&string.as_ptr())
+}
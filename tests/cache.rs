@@ -0,0 +1,197 @@
+#![cfg(all(feature = "driver", unix))]
+
+use clang_ast::{Cache, CompilationDatabase, Driver, Kind, SourceLocation};
+use serde_derive::{Deserialize, Serialize};
+use std::fs;
+use std::os::unix::fs::PermissionsExt as _;
+use std::path::{Path, PathBuf};
+
+pub type Node = clang_ast::Node<Clang>;
+
+#[derive(Deserialize, Serialize, PartialEq, Debug)]
+pub struct Clang {
+    pub kind: Kind,
+    pub name: Option<String>,
+    #[serde(default)]
+    pub loc: SourceLocation,
+}
+
+// Stands in for clang, counting its runs. The declarations are in main.cc and
+// in inc.h which it includes. It also includes config.h, which contains only
+// macros, so it has no locations in the dump but is in the dependency file.
+const FAKE_CLANG: &str = r#"#!/bin/sh
+if [ "$1" = --version ]; then
+  cat version
+  exit
+fi
+echo run >> runs
+while [ $# -gt 0 ]; do
+  if [ "$1" = -MF ]; then
+    printf 'main.o: main.cc inc.h \\\n  config.h\n' > "$2"
+  fi
+  shift
+done
+echo '{"id": "0x1", "kind": "TranslationUnitDecl", "inner": [
+  {"id": "0x2", "kind": "VarDecl", "loc": {"offset": 4, "file": "inc.h", "line": 1, "col": 5, "tokLen": 1, "includedFrom": {"file": "main.cc"}}, "name": "x"},
+  {"id": "0x3", "kind": "VarDecl", "loc": {"offset": 22, "file": "main.cc", "line": 2, "col": 5, "tokLen": 1}, "name": "y"}
+]}'
+"#;
+
+fn setup(test: &str) -> (PathBuf, PathBuf) {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join(test);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let clang = dir.join("clang");
+    fs::write(&clang, FAKE_CLANG).unwrap();
+    fs::set_permissions(&clang, fs::Permissions::from_mode(0o755)).unwrap();
+    fs::write(
+        dir.join("main.cc"),
+        "#include \"config.h\"\n#include \"inc.h\"\nint y;\n",
+    )
+    .unwrap();
+    fs::write(dir.join("config.h"), "#define HAVE_X 1\n").unwrap();
+    fs::write(dir.join("inc.h"), "int x;\n").unwrap();
+    fs::write(dir.join("version"), "clang version 18.1.3\n").unwrap();
+    (dir, clang)
+}
+
+fn runs(dir: &Path) -> usize {
+    fs::read_to_string(dir.join("runs")).map_or(0, |runs| runs.lines().count())
+}
+
+#[test]
+fn test_cache() {
+    let (dir, clang) = setup("cache");
+    let json = format!(
+        r#"[{{"directory": "{}", "file": "main.cc", "arguments": ["c++", "-c", "main.cc"]}}]"#,
+        dir.display(),
+    );
+    let database = CompilationDatabase::from_slice(json.as_bytes()).unwrap();
+    let cache = Cache::new(dir.join("cache"));
+    let mut driver = Driver::new();
+    driver.clang(&clang);
+
+    let run = |driver: &Driver| {
+        let mut results = driver.run_cached::<Clang>(database.commands(), &cache);
+        results.pop().unwrap().unwrap()
+    };
+
+    let first = run(&driver);
+    assert_eq!(runs(&dir), 1);
+    assert_eq!(first.inner[0].kind.name.as_deref(), Some("x"));
+    let loc = first.inner[0].kind.loc.spelling_loc.as_ref().unwrap();
+    assert_eq!(&*loc.file, "inc.h");
+    assert_eq!(&*loc.included_from.as_ref().unwrap().file, "main.cc");
+
+    // Nothing changed.
+    let second = run(&driver);
+    assert_eq!(runs(&dir), 1);
+    assert_eq!(second, first);
+
+    // A header changed.
+    fs::write(dir.join("inc.h"), "int x = 1;\n").unwrap();
+    assert_eq!(run(&driver), first);
+    assert_eq!(runs(&dir), 2);
+    run(&driver);
+    assert_eq!(runs(&dir), 2);
+
+    // A header which only affects preprocessing changed.
+    fs::write(dir.join("config.h"), "#define HAVE_X 0\n").unwrap();
+    run(&driver);
+    assert_eq!(runs(&dir), 3);
+    run(&driver);
+    assert_eq!(runs(&dir), 3);
+
+    // The command changed.
+    driver.arg("-DNDEBUG");
+    run(&driver);
+    assert_eq!(runs(&dir), 4);
+    run(&driver);
+    assert_eq!(runs(&dir), 4);
+
+    // The compiler was upgraded in place. A new cache is needed to observe
+    // this because the version is looked up once per cache.
+    fs::write(dir.join("version"), "clang version 18.1.8\n").unwrap();
+    run(&driver);
+    assert_eq!(runs(&dir), 4);
+    let cache = Cache::new(dir.join("cache"));
+    let run = |driver: &Driver| {
+        let mut results = driver.run_cached::<Clang>(database.commands(), &cache);
+        results.pop().unwrap().unwrap()
+    };
+    run(&driver);
+    assert_eq!(runs(&dir), 5);
+    run(&driver);
+    assert_eq!(runs(&dir), 5);
+
+    // The entry is damaged.
+    for entry in fs::read_dir(cache.dir()).unwrap() {
+        let path = entry.unwrap().path();
+        let mut entry = fs::read(&path).unwrap();
+        entry.pop();
+        fs::write(&path, entry).unwrap();
+    }
+    assert_eq!(run(&driver), first);
+    assert_eq!(runs(&dir), 6);
+}
+
+#[test]
+fn test_without_locations() {
+    // The cache entry depends on inc.h even if T deserializes no locations.
+    #[derive(Deserialize, Serialize)]
+    struct Name {
+        kind: Kind,
+        name: Option<String>,
+    }
+
+    let (dir, clang) = setup("cache_without_locations");
+    let json = format!(
+        r#"[{{"directory": "{}", "file": "main.cc", "command": "c++ -c main.cc"}}]"#,
+        dir.display(),
+    );
+    let database = CompilationDatabase::from_slice(json.as_bytes()).unwrap();
+    let cache = Cache::new(dir.join("cache"));
+    let mut driver = Driver::new();
+    driver.clang(&clang);
+
+    let node = driver
+        .run_cached::<Name>(database.commands(), &cache)
+        .remove(0)
+        .unwrap();
+    assert_eq!(node.inner[1].kind.name.as_deref(), Some("y"));
+    driver.run_cached::<Name>(database.commands(), &cache);
+    assert_eq!(runs(&dir), 1);
+
+    // Entries for a different T are separate.
+    driver.run_cached::<Clang>(database.commands(), &cache);
+    assert_eq!(runs(&dir), 2);
+
+    fs::write(dir.join("inc.h"), "int x = 1;\n").unwrap();
+    driver.run_cached::<Name>(database.commands(), &cache);
+    assert_eq!(runs(&dir), 3);
+}
+
+#[test]
+fn test_concurrent_store() {
+    // Worker threads of the same process storing the same entry at once.
+    let (dir, clang) = setup("cache_concurrent_store");
+    let command = format!(
+        r#"{{"directory": "{}", "file": "main.cc", "arguments": ["c++", "-c", "main.cc"]}}"#,
+        dir.display(),
+    );
+    let json = format!("[{}]", vec![command; 8].join(","));
+    let database = CompilationDatabase::from_slice(json.as_bytes()).unwrap();
+    let cache = Cache::new(dir.join("cache"));
+    let mut driver = Driver::new();
+    driver.clang(&clang).jobs(8);
+
+    for result in driver.run_cached::<Clang>(database.commands(), &cache) {
+        assert_eq!(result.unwrap().inner[1].kind.name.as_deref(), Some("y"));
+    }
+    let entries: Vec<PathBuf> = fs::read_dir(cache.dir())
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    assert_eq!(entries.len(), 1, "{:?}", entries);
+    assert_eq!(entries[0].extension().unwrap(), "ast");
+}
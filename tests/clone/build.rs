@@ -11,7 +11,7 @@ use std::io::ErrorKind;
 use std::path::PathBuf;
 use std::process::Command;
 
-// Executable names to try.
+// Executable names to try. Keep in sync with the list in src/driver.rs.
 static CLANG: &[&str] = &[
     "clang++-20",
     "clang++-19",
@@ -0,0 +1,100 @@
+use clang_ast::{Cursor, Flow, HasKind, Id, IdIndex, Kind, Visit};
+use serde_derive::Deserialize;
+use std::ptr;
+
+pub type Node = clang_ast::Node<Clang>;
+
+#[derive(Deserialize)]
+pub struct Clang {
+    #[serde(default)]
+    pub kind: Kind,
+}
+
+impl HasKind for Clang {
+    fn kind(&self) -> Kind {
+        self.kind
+    }
+}
+
+#[derive(Default)]
+struct Preorder<'ast> {
+    nodes: Vec<(&'ast Node, usize)>,
+}
+
+impl<'ast> Visit<'ast, Clang> for Preorder<'ast> {
+    fn enter(&mut self, node: &'ast Node, depth: usize) -> Flow {
+        self.nodes.push((node, depth));
+        Flow::Continue
+    }
+}
+
+fn check(cursor: &Cursor<Clang>, index: &IdIndex<Clang>) {
+    let node = cursor.node();
+    match cursor.child_index() {
+        Some(i) => assert!(ptr::eq(&cursor.siblings()[i], node)),
+        None => assert!(cursor.parent().is_none()),
+    }
+    assert_eq!(cursor.ancestors().len(), cursor.depth());
+    if let Some(parent) = cursor.ancestors().next() {
+        assert!(ptr::eq(parent, cursor.parent().unwrap()));
+    }
+    let expected = cursor
+        .ancestors()
+        .find(|node| node.kind.kind.is_a(Kind::NamedDecl));
+    let actual = cursor.enclosing(Kind::NamedDecl);
+    assert_eq!(
+        actual.map(|n| n as *const Node),
+        expected.map(|n| n as *const Node)
+    );
+
+    if node.id != Id::NULL && ptr::eq(index.get(node.id).unwrap(), node) {
+        let other = index.cursor(node.id).unwrap();
+        assert_eq!(other.depth(), cursor.depth());
+        assert_eq!(other.child_index(), cursor.child_index());
+        assert!(cursor
+            .ancestors()
+            .zip(other.ancestors())
+            .all(|(a, b)| ptr::eq(a, b)));
+    }
+}
+
+#[test]
+fn test_cursor() {
+    let json = clang_ast_test_suite::cxx_ast_json();
+    let root: Node = serde_json::from_slice(&json).unwrap();
+    let index = IdIndex::new(&root);
+
+    let mut preorder = Preorder::default();
+    root.visit(&mut preorder);
+
+    // Walk the tree in preorder using only cursor movements.
+    let mut cursor = root.cursor();
+    let mut visited = Vec::new();
+    'walk: loop {
+        check(&cursor, &index);
+        visited.push((cursor.node(), cursor.depth()));
+        if cursor.goto_first_child() {
+            continue;
+        }
+        while !cursor.goto_next_sibling() {
+            if !cursor.goto_parent() {
+                break 'walk;
+            }
+        }
+        let prev = cursor.prev_sibling().unwrap();
+        assert!(ptr::eq(
+            &cursor.siblings()[cursor.child_index().unwrap() - 1],
+            prev
+        ));
+    }
+
+    assert_eq!(visited.len(), preorder.nodes.len());
+    for ((a, a_depth), (b, b_depth)) in visited.iter().zip(&preorder.nodes) {
+        assert!(ptr::eq(*a, *b));
+        assert_eq!(a_depth, b_depth);
+    }
+
+    assert!(!cursor.goto_parent());
+    assert!(!cursor.goto_prev_sibling());
+    assert!(cursor.next_sibling().is_none());
+}
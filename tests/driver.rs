@@ -0,0 +1,180 @@
+#![cfg(all(feature = "driver", unix))]
+
+use clang_ast::{CompilationDatabase, Driver};
+use serde_derive::Deserialize;
+use std::fs;
+use std::os::unix::fs::PermissionsExt as _;
+use std::path::{Path, PathBuf};
+
+pub type Node = clang_ast::Node<Clang>;
+
+#[derive(Deserialize, Debug)]
+pub struct Clang {
+    pub name: Option<String>,
+}
+
+// Stands in for clang. Prints a translation unit containing one declaration
+// named after the source file, and saves the arguments next to it.
+const FAKE_CLANG: &str = r#"#!/bin/sh
+for arg; do
+  case "$arg" in
+    bad.cc) echo "bad.cc:1:1: error: unknown type name 'oops'" >&2; exit 1 ;;
+    garbage.cc) echo '{"id": "0x1", "kind": '; exit 0 ;;
+    *.cc) file="$arg" ;;
+  esac
+done
+if [ -n "$file" ]; then
+  printf '%s\n' "$@" > "$file.args"
+fi
+echo "{\"id\": \"0x1\", \"kind\": \"TranslationUnitDecl\", \"inner\": [{\"id\": \"0x2\", \"kind\": \"VarDecl\", \"name\": \"$file\"}]}"
+"#;
+
+fn fake_clang(test: &str, name: &str) -> (PathBuf, PathBuf) {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join(test);
+    let _ = fs::remove_dir_all(&dir);
+    let bin = dir.join("bin");
+    fs::create_dir_all(&bin).unwrap();
+    let clang = bin.join(name);
+    fs::write(&clang, FAKE_CLANG).unwrap();
+    fs::set_permissions(&clang, fs::Permissions::from_mode(0o755)).unwrap();
+    (dir, clang)
+}
+
+fn database(dir: &Path, files: &[&str]) -> CompilationDatabase {
+    let entries: Vec<String> = files
+        .iter()
+        .map(|file| {
+            format!(
+                r#"{{"directory": "{}", "file": "{file}", "arguments": ["g++", "-DNDEBUG", "-Iinclude", "-c", "-o", "{file}.o", "{file}"]}}"#,
+                dir.display(),
+            )
+        })
+        .collect();
+    CompilationDatabase::from_slice(format!("[{}]", entries.join(",")).as_bytes()).unwrap()
+}
+
+#[test]
+fn test_parse() {
+    let json = r#"[
+        {
+            "directory": "/build",
+            "file": "../src/a.cc",
+            "arguments": ["/usr/bin/c++", "-DNDEBUG", "-I", "../include", "-std=c++17", "-MD", "-MT", "a.o", "-MF", "a.o.d", "-o", "a.o", "-c", "../src/a.cc"],
+            "output": "a.o"
+        },
+        {
+            "directory": "/build",
+            "file": "../src/b.cc",
+            "command": "/usr/bin/c++ -DNAME=\"\\\"b c\\\"\" '-DEMPTY=' -Ib\\ c -oout/b.o -c ../src/b.cc"
+        }
+    ]"#;
+    let database = CompilationDatabase::from_slice(json.as_bytes()).unwrap();
+    let [a, b] = database.commands() else {
+        panic!("expected 2 commands");
+    };
+
+    assert_eq!(a.directory, Path::new("/build"));
+    assert_eq!(a.file, Path::new("../src/a.cc"));
+    assert_eq!(a.output.as_deref(), Some(Path::new("a.o")));
+    assert_eq!(
+        a.ast_dump_arguments(),
+        [
+            "-fsyntax-only",
+            "-Xclang",
+            "-ast-dump=json",
+            "-DNDEBUG",
+            "-I",
+            "../include",
+            "-std=c++17",
+            "../src/a.cc",
+        ],
+    );
+
+    assert_eq!(
+        b.arguments,
+        [
+            "/usr/bin/c++",
+            "-DNAME=\"b c\"",
+            "-DEMPTY=",
+            "-Ib c",
+            "-oout/b.o",
+            "-c",
+            "../src/b.cc",
+        ],
+    );
+    assert_eq!(b.output, None);
+    assert_eq!(
+        b.ast_dump_arguments()[3..],
+        ["-DNAME=\"b c\"", "-DEMPTY=", "-Ib c", "../src/b.cc"],
+    );
+
+    let json = r#"[
+        {
+            "directory": "/build",
+            "file": "c.c",
+            "arguments": ["cc", "-objcmt-migrate-literals", "-order_file", "-ooutput.o", "-o", "c.o", "c.c"]
+        }
+    ]"#;
+    let database = CompilationDatabase::from_slice(json.as_bytes()).unwrap();
+    assert_eq!(
+        database.commands()[0].ast_dump_arguments()[3..],
+        ["-x", "c", "-objcmt-migrate-literals", "-order_file", "c.c"],
+    );
+
+    let error = CompilationDatabase::from_slice(br#"[{"directory": "/build", "file": "a.cc"}]"#)
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "missing field `arguments` at line 1 column 39"
+    );
+}
+
+#[test]
+fn test_run() {
+    let (dir, clang) = fake_clang("driver_run", "clang");
+    let database = database(&dir, &["a.cc", "bad.cc", "b.cc", "garbage.cc"]);
+    let results = Driver::new()
+        .clang(&clang)
+        .jobs(2)
+        .arg("-std=c++20")
+        .run::<Clang>(database.commands());
+    assert_eq!(results.len(), 4);
+
+    let a = results[0].as_ref().unwrap();
+    assert_eq!(a.inner[0].kind.name.as_deref(), Some("a.cc"));
+    let args = fs::read_to_string(dir.join("a.cc.args")).unwrap();
+    assert_eq!(
+        args.lines().collect::<Vec<_>>(),
+        [
+            "-fsyntax-only",
+            "-Xclang",
+            "-ast-dump=json",
+            "-DNDEBUG",
+            "-Iinclude",
+            "a.cc",
+            "-std=c++20",
+        ],
+    );
+
+    let bad = results[1].as_ref().unwrap_err();
+    assert_eq!(bad.file(), Some(Path::new("bad.cc")));
+    assert_eq!(
+        bad.stderr(),
+        Some("bad.cc:1:1: error: unknown type name 'oops'\n"),
+    );
+    let message = bad.to_string();
+    assert!(message.contains("failed on bad.cc"), "{message}");
+    assert!(
+        message.ends_with("\nbad.cc:1:1: error: unknown type name 'oops'"),
+        "{message}"
+    );
+
+    assert!(results[2].is_ok());
+
+    let garbage = results[3].as_ref().unwrap_err();
+    assert_eq!(garbage.file(), Some(Path::new("garbage.cc")));
+    assert_eq!(garbage.stderr(), None);
+    assert!(garbage
+        .to_string()
+        .starts_with("failed to deserialize the AST of garbage.cc: "));
+}
@@ -0,0 +1,64 @@
+#![cfg(all(feature = "driver", unix))]
+
+// Separate from tests/driver.rs because it modifies PATH, which is not safe to
+// do while other tests in the same process are spawning processes.
+
+use clang_ast::{CompilationDatabase, Driver};
+use serde_derive::Deserialize;
+use std::env;
+use std::fs;
+use std::os::unix::fs::PermissionsExt as _;
+use std::path::Path;
+
+#[derive(Deserialize, Debug)]
+pub struct Clang {
+    pub name: Option<String>,
+}
+
+// Stands in for clang. Prints a translation unit containing one declaration
+// named after the source file.
+const FAKE_CLANG: &str = r#"#!/bin/sh
+for arg; do
+  case "$arg" in
+    *.cc) file="$arg" ;;
+  esac
+done
+echo "{\"id\": \"0x1\", \"kind\": \"TranslationUnitDecl\", \"inner\": [{\"id\": \"0x2\", \"kind\": \"VarDecl\", \"name\": \"$file\"}]}"
+"#;
+
+#[test]
+fn test_path() {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("driver_path");
+    let _ = fs::remove_dir_all(&dir);
+    let bin = dir.join("bin");
+    fs::create_dir_all(&bin).unwrap();
+    let clang = bin.join("clang++");
+    fs::write(&clang, FAKE_CLANG).unwrap();
+    fs::set_permissions(&clang, fs::Permissions::from_mode(0o755)).unwrap();
+    // A newer version which is on the PATH but does not run is skipped.
+    let broken = bin.join("clang++-20");
+    fs::write(&broken, "#!/bin/sh\nexit 1\n").unwrap();
+    fs::set_permissions(&broken, fs::Permissions::from_mode(0o755)).unwrap();
+    env::set_var("PATH", &bin);
+
+    let json = format!(
+        r#"[
+            {{"directory": "{0}", "file": "a.cc", "arguments": ["c++", "-c", "a.cc"]}},
+            {{"directory": "{0}", "file": "b.cc", "arguments": ["c++", "-c", "b.cc"]}}
+        ]"#,
+        dir.display(),
+    );
+    let database = CompilationDatabase::from_slice(json.as_bytes()).unwrap();
+    let results = Driver::new().run::<Clang>(database.commands());
+    let names: Vec<_> = results
+        .iter()
+        .map(|result| {
+            result.as_ref().unwrap().inner[0]
+                .kind
+                .name
+                .as_deref()
+                .unwrap()
+        })
+        .collect();
+    assert_eq!(names, ["a.cc", "b.cc"]);
+}
@@ -4422,20 +4422,36 @@ where
     })
 }
 
+fn print_context(json: &[u8], line: usize) {
+    if env::var_os("CI").is_some() {
+        if let Ok(json) = str::from_utf8(json) {
+            let mut stderr = io::stderr().lock();
+            for line in json.lines().skip(line.saturating_sub(30)).take(60) {
+                let _ = writeln!(stderr, "{}", line);
+            }
+        }
+    }
+}
+
 #[test]
 fn test() -> Result<(), serde_json::Error> {
     let json = clang_ast_test_suite::cxx_ast_json();
     let result = with_much_stack(|| serde_json::from_slice::<Node>(&json).map(drop));
 
     if let Err(error) = &result {
-        if env::var_os("CI").is_some() {
-            if let Ok(json) = str::from_utf8(&json) {
-                let mut stderr = io::stderr().lock();
-                for line in json.lines().skip(error.line().saturating_sub(30)).take(60) {
-                    let _ = writeln!(stderr, "{}", line);
-                }
-            }
-        }
+        print_context(&json, error.line());
+    }
+
+    result
+}
+
+#[test]
+fn test_from_slice() -> Result<(), clang_ast::Error> {
+    let json = clang_ast_test_suite::cxx_ast_json();
+    let result = clang_ast::from_slice::<Clang>(&json).map(drop);
+
+    if let Err(error) = &result {
+        print_context(&json, error.line());
     }
 
     result
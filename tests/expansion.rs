@@ -0,0 +1,108 @@
+use clang_ast::{SourceLocation, SourceMap, SourceRange};
+use serde_derive::Deserialize;
+
+pub type Node = clang_ast::Node<Clang>;
+
+#[derive(Deserialize, Debug)]
+pub struct Clang {
+    #[serde(default)]
+    pub range: SourceRange,
+}
+
+// #define ADD(a, b) a + b
+// int x = ADD(1, 2);
+// int y = 3;
+const SOURCE: &str = "#define ADD(a, b) a + b\nint x = ADD(1, 2);\nint y = 3;\n";
+
+const JSON: &str = r#"{
+  "kind": "TranslationUnitDecl",
+  "inner": [
+    {
+      "kind": "BinaryOperator",
+      "range": {
+        "begin": {
+          "spellingLoc": {"offset": 36, "file": "main.cc", "line": 2, "col": 13, "tokLen": 1},
+          "expansionLoc": {"offset": 32, "col": 9, "tokLen": 3, "isMacroArgExpansion": true}
+        },
+        "end": {
+          "spellingLoc": {"offset": 39, "col": 16, "tokLen": 1},
+          "expansionLoc": {"offset": 32, "col": 9, "tokLen": 3, "isMacroArgExpansion": true}
+        }
+      }
+    },
+    {
+      "kind": "BinaryOperator",
+      "range": {
+        "begin": {
+          "spellingLoc": {"offset": 36, "col": 13, "tokLen": 1},
+          "expansionLoc": {"offset": 32, "col": 9, "tokLen": 3, "isMacroArgExpansion": true}
+        },
+        "end": {"offset": 47, "line": 3, "col": 5, "tokLen": 1}
+      }
+    },
+    {
+      "kind": "VarDecl",
+      "range": {
+        "begin": {"offset": 24, "line": 2, "col": 1, "tokLen": 3},
+        "end": {
+          "spellingLoc": {"offset": 22, "line": 1, "col": 23, "tokLen": 1},
+          "expansionLoc": {"offset": 32, "line": 2, "col": 9, "tokLen": 3}
+        }
+      }
+    },
+    {
+      "kind": "VarDecl",
+      "range": {
+        "begin": {"offset": 43, "line": 3, "col": 1, "tokLen": 3},
+        "end": {"offset": 51, "col": 9, "tokLen": 1}
+      }
+    }
+  ]
+}"#;
+
+#[test]
+fn test_location() {
+    let node = clang_ast::from_str::<Clang>(JSON).unwrap();
+    let arg = &node.inner[0].kind.range;
+    let body = &node.inner[2].kind.range;
+    let plain = &node.inner[3].kind.range;
+
+    assert!(arg.begin.is_from_macro());
+    assert!(arg.begin.is_macro_arg());
+    assert_eq!(arg.begin.file_loc().unwrap().offset, 36);
+    assert!(arg.begin.same_expansion(&arg.end));
+    assert!(arg.begin.same_expansion(&body.end));
+
+    assert!(body.end.is_from_macro());
+    assert!(!body.end.is_macro_arg());
+    assert_eq!(body.end.file_loc().unwrap().offset, 32);
+
+    assert!(!plain.begin.is_from_macro());
+    assert!(!plain.begin.is_macro_arg());
+    assert_eq!(plain.begin.file_loc().unwrap().offset, 43);
+    assert!(!plain.begin.same_expansion(&plain.begin));
+
+    let empty = SourceLocation::default();
+    assert!(!empty.is_from_macro());
+    assert_eq!(empty.file_loc(), None);
+}
+
+#[test]
+fn test_file_range() {
+    let node = clang_ast::from_str::<Clang>(JSON).unwrap();
+    let mut source_map = SourceMap::new();
+    source_map.insert("main.cc", SOURCE);
+
+    let file_text = |node: &Node| {
+        let range = node.kind.range.file_range()?;
+        assert!(!range.begin.is_from_macro());
+        assert!(!range.end.is_from_macro());
+        source_map.text(&range)
+    };
+
+    assert_eq!(file_text(&node.inner[0]).as_deref(), Some("1, 2"));
+    assert_eq!(file_text(&node.inner[1]).as_deref(), Some("1, 2);\nint y"));
+    assert_eq!(file_text(&node.inner[2]).as_deref(), Some("int x = ADD"));
+    assert_eq!(file_text(&node.inner[3]).as_deref(), Some("int y = 3"));
+    assert_eq!(SourceRange::default().file_range(), None);
+}
@@ -0,0 +1,167 @@
+use serde_derive::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+pub type Node = clang_ast::Node<Clang>;
+
+// Keeps every field of every node, so that serializing it again can be
+// compared against the original dump.
+#[derive(Deserialize, Serialize)]
+pub struct Clang {
+    #[serde(default)]
+    pub kind: clang_ast::Kind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub loc: Option<clang_ast::SourceLocation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub range: Option<clang_ast::SourceRange>,
+    #[serde(flatten)]
+    pub data: Map<String, Value>,
+}
+
+// #include "a.h"            // a.h includes b.h; each ends in a declaration
+//                            // spanning two lines
+// #define ID(x) x
+// # 10 "gen.y"
+// int x;
+// int main() { return ID(0); }
+const JSON: &str = r#"{
+  "id": "0x1",
+  "kind": "TranslationUnitDecl",
+  "loc": {},
+  "range": {"begin": {}, "end": {}},
+  "inner": [
+    {
+      "id": "0x2",
+      "kind": "TypedefDecl",
+      "loc": {"offset": 14, "file": "b.h", "line": 2, "col": 3, "tokLen": 1, "includedFrom": {"file": "a.h"}},
+      "range": {
+        "begin": {"offset": 0, "line": 1, "col": 1, "tokLen": 7, "includedFrom": {"file": "a.h"}},
+        "end": {"offset": 14, "line": 2, "col": 3, "tokLen": 1, "includedFrom": {"file": "a.h"}}
+      },
+      "name": "T"
+    },
+    {
+      "id": "0x3",
+      "kind": "VarDecl",
+      "loc": {"offset": 21, "file": "a.h", "line": 3, "col": 3, "tokLen": 1, "includedFrom": {"file": "main.cc"}},
+      "range": {
+        "begin": {"offset": 15, "line": 2, "col": 1, "tokLen": 3, "includedFrom": {"file": "main.cc"}},
+        "end": {"offset": 21, "line": 3, "col": 3, "tokLen": 1, "includedFrom": {"file": "main.cc"}}
+      },
+      "name": "t"
+    },
+    {
+      "id": "0x4",
+      "kind": "VarDecl",
+      "loc": {"offset": 47, "file": "main.cc", "line": 4, "presumedFile": "gen.y", "presumedLine": 10, "col": 5, "tokLen": 1},
+      "range": {
+        "begin": {"offset": 43, "col": 1, "tokLen": 3},
+        "end": {"offset": 47, "col": 5, "tokLen": 1}
+      },
+      "name": "x"
+    },
+    {
+      "id": "0x5",
+      "kind": "FunctionDecl",
+      "loc": {"offset": 54, "line": 5, "presumedLine": 11, "col": 5, "tokLen": 4},
+      "range": {
+        "begin": {"offset": 50, "col": 1, "tokLen": 3},
+        "end": {"offset": 77, "col": 28, "tokLen": 1}
+      },
+      "name": "main",
+      "inner": [
+        {
+          "id": "0x6",
+          "kind": "CompoundStmt",
+          "range": {
+            "begin": {"offset": 61, "col": 12, "tokLen": 1},
+            "end": {"offset": 77, "col": 28, "tokLen": 1}
+          },
+          "inner": [
+            {
+              "id": "0x7",
+              "kind": "ReturnStmt",
+              "range": {
+                "begin": {"offset": 63, "col": 14, "tokLen": 6},
+                "end": {
+                  "spellingLoc": {"offset": 73, "col": 24, "tokLen": 1},
+                  "expansionLoc": {"offset": 70, "col": 21, "tokLen": 2, "isMacroArgExpansion": true}
+                }
+              },
+              "inner": [
+                {
+                  "id": "0x8",
+                  "kind": "IntegerLiteral",
+                  "range": {
+                    "begin": {
+                      "spellingLoc": {"offset": 73, "col": 24, "tokLen": 1},
+                      "expansionLoc": {"offset": 70, "col": 21, "tokLen": 2, "isMacroArgExpansion": true}
+                    },
+                    "end": {
+                      "spellingLoc": {"offset": 73, "col": 24, "tokLen": 1},
+                      "expansionLoc": {"offset": 70, "col": 21, "tokLen": 2, "isMacroArgExpansion": true}
+                    }
+                  },
+                  "value": "0"
+                }
+              ]
+            }
+          ]
+        }
+      ]
+    }
+  ]
+}"#;
+
+#[test]
+fn test_round_trip() {
+    // Serializing reproduces Clang's dump exactly, including which parts of
+    // each location are omitted as unchanged from the previous location.
+    let expected: Value = serde_json::from_str(JSON).unwrap();
+    let node: Node = clang_ast::from_str(JSON).unwrap();
+    let actual = clang_ast::faithful(|| serde_json::to_value(&node)).unwrap();
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_round_trip_cxx() {
+    let json = clang_ast_test_suite::cxx_ast_json();
+    let expected: Value = serde_json::from_slice(&json).unwrap();
+    let node: Node = clang_ast::from_slice(&json).unwrap();
+    let actual = clang_ast::faithful(|| serde_json::to_value(&node)).unwrap();
+    assert!(actual == expected);
+}
+
+#[test]
+fn test_null_id() {
+    let node = Node {
+        id: clang_ast::Id::NULL,
+        kind: Clang {
+            kind: clang_ast::Kind::NullStmt,
+            loc: None,
+            range: None,
+            data: Map::new(),
+        },
+        inner: Vec::new(),
+    };
+    let json = serde_json::to_value(&node).unwrap();
+    assert_eq!(json["id"], "0x0");
+    assert_eq!(json["kind"], "NullStmt");
+
+    let json = clang_ast::faithful(|| serde_json::to_value(&node)).unwrap();
+    assert_eq!(json.get("id"), None);
+    assert_eq!(json["kind"], "NullStmt");
+}
+
+#[test]
+fn test_default_locations() {
+    // Outside of faithful, locations in the main file are written with a
+    // separate spellingLoc and expansionLoc, as in earlier versions.
+    let node: Node = clang_ast::from_str(JSON).unwrap();
+    let json = serde_json::to_value(&node.inner[2]).unwrap();
+    let loc = &json["loc"];
+    assert_eq!(loc["spellingLoc"]["file"], "main.cc");
+    assert_eq!(loc["expansionLoc"]["offset"], 47);
+
+    let json = clang_ast::faithful(|| serde_json::to_value(&node.inner[2])).unwrap();
+    assert_eq!(json["loc"]["file"], "main.cc");
+}
@@ -0,0 +1,114 @@
+use clang_ast::{Flow, SourceLocation, VisitMut};
+use serde_derive::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+pub type Node = clang_ast::Node<Clang>;
+
+#[derive(Deserialize, PartialEq, Debug)]
+pub struct Clang {
+    #[serde(default)]
+    pub kind: clang_ast::Kind,
+    pub loc: Option<SourceLocation>,
+    #[serde(default)]
+    pub range: clang_ast::SourceRange,
+    #[serde(flatten)]
+    pub data: Map<String, Value>,
+}
+
+fn file(loc: &SourceLocation) -> Option<&Arc<str>> {
+    loc.expansion_loc.as_ref().map(|loc| &loc.file)
+}
+
+struct Retain<F>(F);
+
+impl<F> VisitMut<Clang> for Retain<F>
+where
+    F: Fn(&SourceLocation) -> bool,
+{
+    fn enter(&mut self, node: &mut Node, _depth: usize) -> Flow {
+        node.inner
+            .retain(|child| child.kind.loc.as_ref().map_or(true, &self.0));
+        Flow::Continue
+    }
+}
+
+struct Count<'a>(&'a mut BTreeMap<Arc<str>, usize>);
+
+impl VisitMut<Clang> for Count<'_> {
+    fn enter(&mut self, node: &mut Node, _depth: usize) -> Flow {
+        if let Some(file) = node.kind.loc.as_ref().and_then(file) {
+            *self.0.entry(Arc::clone(file)).or_insert(0) += 1;
+        }
+        Flow::Continue
+    }
+}
+
+#[test]
+fn test_same_as_after_the_fact() {
+    let json = clang_ast_test_suite::cxx_ast_json();
+    let unfiltered = clang_ast::from_slice::<Clang>(&json).unwrap();
+
+    let all = clang_ast::from_slice_filtered::<Clang>(&json, |_| true).unwrap();
+    assert_eq!(all, unfiltered);
+
+    // Leave out whichever file has the most declarations in it.
+    let mut files = BTreeMap::new();
+    let mut expected = clang_ast::from_slice::<Clang>(&json).unwrap();
+    expected.visit_mut(&mut Count(&mut files));
+    let (most, _) = files.iter().max_by_key(|(_, count)| **count).unwrap();
+    let keep = |loc: &SourceLocation| file(loc) != Some(most);
+
+    let actual = clang_ast::from_slice_filtered::<Clang>(&json, keep).unwrap();
+    expected.visit_mut(&mut Retain(keep));
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_same_as_previous() {
+    let json = r#"{
+      "kind": "TranslationUnitDecl",
+      "inner": [
+        {
+          "kind": "FunctionDecl",
+          "loc": {"offset": 1, "file": "/usr/include/a.h", "line": 1, "col": 1, "tokLen": 1},
+          "range": {
+            "begin": {"offset": 1, "col": 1, "tokLen": 1},
+            "end": {"offset": 5, "line": 2, "col": 1, "tokLen": 1}
+          },
+          "inner": [
+            {"kind": "ParmVarDecl", "loc": {"offset": 3, "col": 3, "tokLen": 1}}
+          ]
+        },
+        {
+          "kind": "FunctionDecl",
+          "loc": {"offset": 9, "line": 4, "col": 1, "tokLen": 1}
+        },
+        {
+          "kind": "FunctionDecl",
+          "loc": {"offset": 20, "file": "main.cc", "line": 10, "col": 1, "tokLen": 1},
+          "range": {
+            "begin": {"offset": 20, "col": 1, "tokLen": 1},
+            "end": {"offset": 30, "line": 12, "col": 2, "tokLen": 1}
+          }
+        },
+        {
+          "kind": "VarDecl",
+          "loc": {"offset": 40, "col": 5, "tokLen": 1}
+        }
+      ]
+    }"#;
+
+    let node = clang_ast::from_slice_filtered::<Clang>(json.as_bytes(), |loc| {
+        file(loc).map_or(true, |file| !file.starts_with("/usr/include/"))
+    })
+    .unwrap();
+
+    assert_eq!(node.inner.len(), 2);
+    let loc = node.inner[1].kind.loc.as_ref().unwrap();
+    let loc = loc.spelling_loc.as_ref().unwrap();
+    assert_eq!(&*loc.file, "main.cc");
+    assert_eq!(loc.line, 12);
+    assert_eq!(loc.col, 5);
+}
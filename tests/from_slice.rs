@@ -0,0 +1,67 @@
+use serde_derive::Deserialize;
+use serde_json::{Map, Value};
+
+pub type Node = clang_ast::Node<Clang>;
+
+#[derive(Deserialize, PartialEq, Debug)]
+pub struct Clang {
+    #[serde(default)]
+    pub kind: clang_ast::Kind,
+    #[serde(default)]
+    pub loc: clang_ast::SourceLocation,
+    #[serde(default)]
+    pub range: clang_ast::SourceRange,
+    #[serde(flatten)]
+    pub data: Map<String, Value>,
+}
+
+#[test]
+fn test_same_as_serde_json() {
+    let json = clang_ast_test_suite::cxx_ast_json();
+    let expected: Node = serde_json::from_slice(&json).unwrap();
+    let actual = clang_ast::from_slice::<Clang>(&json).unwrap();
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_deep() {
+    const DEPTH: usize = 100_000;
+
+    let mut json = String::new();
+    for _ in 0..DEPTH {
+        json += r#"{"id":"0x1","kind":"ParenExpr","inner":["#;
+    }
+    json += r#"{"id":"0x2","kind":"IntegerLiteral","value":"0"}"#;
+    for _ in 0..DEPTH {
+        json += "]}";
+    }
+
+    let node = clang_ast::from_str::<Clang>(&json).unwrap();
+    let mut depth = 0;
+    let mut leaf = &node;
+    while let [child] = leaf.inner.as_slice() {
+        depth += 1;
+        leaf = child;
+    }
+    assert_eq!(depth, DEPTH);
+    assert_eq!(leaf.kind.kind, clang_ast::Kind::IntegerLiteral);
+}
+
+#[test]
+fn test_errors() {
+    let error =
+        clang_ast::from_str::<Clang>("{\n  \"id\": \"0x1\",\n  \"kind\": 1\n}").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "invalid type: integer `1`, expected enum Kind at line 3 column 11",
+    );
+
+    let error = clang_ast::from_str::<Clang>(r#"{"kind": "NullStmt", "inner": [{"#).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "EOF while parsing clang syntax tree node at line 1 column 32",
+    );
+
+    let error = clang_ast::from_str::<Clang>(r#"{"kind": "NullStmt"} {}"#).unwrap_err();
+    assert_eq!(error.to_string(), "trailing characters at line 1 column 22");
+}
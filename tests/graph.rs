@@ -0,0 +1,124 @@
+use clang_ast::{Graph, GraphFormat, Id};
+use serde_json::Value;
+
+pub type Node = clang_ast::Node<Value>;
+
+const JSON: &str = r#"
+{
+  "id": "0x1",
+  "kind": "FunctionDecl",
+  "name": "f",
+  "inner": [
+    {
+      "id": "0x2",
+      "kind": "ParmVarDecl",
+      "name": "x"
+    },
+    {
+      "id": "0x3",
+      "kind": "CompoundStmt",
+      "inner": [
+        {
+          "id": "0x4",
+          "kind": "ReturnStmt",
+          "inner": [
+            {
+              "id": "0x5",
+              "kind": "DeclRefExpr",
+              "referencedDecl": {"id": "0x2", "kind": "ParmVarDecl", "name": "x"}
+            }
+          ]
+        }
+      ]
+    }
+  ]
+}
+"#;
+
+fn label(node: &Node) -> String {
+    let kind = node.kind["kind"].as_str().unwrap();
+    match node.kind["name"].as_str() {
+        Some(name) => format!("{kind}\n\"{name}\""),
+        None => kind.to_owned(),
+    }
+}
+
+fn references(node: &Node) -> Vec<Id> {
+    match node.kind.get("referencedDecl") {
+        Some(decl) => vec![serde_json::from_value(decl["id"].clone()).unwrap()],
+        None => Vec::new(),
+    }
+}
+
+#[test]
+fn test_dot() {
+    let node: Node = clang_ast::from_str(JSON).unwrap();
+    let graph = Graph::new(&node, label).references(references);
+    let expected = r#"digraph {
+  node [shape=box];
+  n0 [label="FunctionDecl\n\"f\""];
+  n1 [label="ParmVarDecl\n\"x\""];
+  n0 -> n1;
+  n2 [label="CompoundStmt"];
+  n0 -> n2;
+  n3 [label="ReturnStmt"];
+  n2 -> n3;
+  n4 [label="DeclRefExpr"];
+  n3 -> n4;
+  n4 -> n1 [style=dashed, constraint=false];
+}
+"#;
+    assert_eq!(graph.to_string(), expected);
+}
+
+#[test]
+fn test_mermaid() {
+    let node: Node = clang_ast::from_str(JSON).unwrap();
+    let graph = Graph::new(&node, label)
+        .references(references)
+        .format(GraphFormat::Mermaid);
+    let expected = r#"flowchart TD
+  n0["FunctionDecl<br>#quot;f#quot;"]
+  n1["ParmVarDecl<br>#quot;x#quot;"]
+  n0 --> n1
+  n2["CompoundStmt"]
+  n0 --> n2
+  n3["ReturnStmt"]
+  n2 --> n3
+  n4["DeclRefExpr"]
+  n3 --> n4
+  n4 -.-> n1
+"#;
+    assert_eq!(graph.to_string(), expected);
+}
+
+#[test]
+fn test_limits() {
+    let node: Node = clang_ast::from_str(JSON).unwrap();
+
+    let graph = Graph::new(&node, label).max_depth(1);
+    let expected = r#"digraph {
+  node [shape=box];
+  n0 [label="FunctionDecl\n\"f\""];
+  n1 [label="ParmVarDecl\n\"x\""];
+  n0 -> n1;
+  n2 [label="CompoundStmt"];
+  n0 -> n2;
+  m2 [label="1 more", shape=plaintext];
+  n2 -> m2 [style=dotted];
+}
+"#;
+    assert_eq!(graph.to_string(), expected);
+
+    // References to nodes outside of the graph are not drawn.
+    let graph = Graph::new(&node, label)
+        .references(references)
+        .max_nodes(1)
+        .format(GraphFormat::Mermaid);
+    let expected = r#"flowchart TD
+  n0["FunctionDecl<br>#quot;f#quot;"]
+  m0(["2 more"])
+  n0 -.- m0
+"#;
+    assert_eq!(graph.to_string(), expected);
+}
@@ -0,0 +1,75 @@
+use clang_ast::{Category, Flow, Kind, Visit};
+use serde_derive::Deserialize;
+
+pub type Node = clang_ast::Node<Clang>;
+
+#[derive(Deserialize)]
+pub struct Clang {
+    #[serde(default)]
+    pub kind: Kind,
+}
+
+fn expected_category(kind: Kind) -> Option<Category> {
+    let name = kind.as_str();
+    if name.ends_with("Decl") {
+        Some(Category::Decl)
+    } else if name.ends_with("Expr") || name.ends_with("Literal") {
+        Some(Category::Expr)
+    } else if name.ends_with("Stmt") {
+        Some(Category::Stmt)
+    } else if name.ends_with("Type") {
+        Some(Category::Type)
+    } else if name.ends_with("Attr") {
+        Some(Category::Attr)
+    } else if name.ends_with("Comment") {
+        Some(Category::Comment)
+    } else {
+        None
+    }
+}
+
+struct Check {
+    count: usize,
+}
+
+impl<'ast> Visit<'ast, Clang> for Check {
+    fn enter(&mut self, node: &'ast Node, _depth: usize) -> Flow {
+        let kind = node.kind.kind;
+        let category = kind.category();
+        if let Some(expected) = expected_category(kind) {
+            assert_eq!(category, expected, "{kind}");
+            self.count += 1;
+        }
+        let root = match category {
+            Category::Decl => Kind::Decl,
+            Category::Stmt | Category::Expr => Kind::Stmt,
+            Category::Type => Kind::Type,
+            Category::Attr => Kind::Attr,
+            Category::Comment => Kind::Comment,
+            _ => return Flow::Continue,
+        };
+        assert!(kind.is_a(root), "{kind}");
+        assert!(kind.is_a(kind));
+        Flow::Continue
+    }
+}
+
+#[test]
+fn test_hierarchy() {
+    let json = clang_ast_test_suite::cxx_ast_json();
+    let node: Node = serde_json::from_slice(&json).unwrap();
+    let mut check = Check { count: 0 };
+    node.visit(&mut check);
+    assert!(check.count > 0);
+
+    assert!(Kind::CXXConstructorDecl.is_a(Kind::FunctionDecl));
+    assert!(Kind::ClassTemplatePartialSpecializationDecl.is_a(Kind::TagDecl));
+    assert!(Kind::CXXStaticCastExpr.is_a(Kind::CastExpr));
+    assert!(Kind::CompoundAssignOperator.is_a(Kind::Expr));
+    assert!(!Kind::CompoundStmt.is_a(Kind::Expr));
+    assert!(!Kind::FieldDecl.is_a(Kind::FunctionDecl));
+    assert!(!Kind::Decl.is_a(Kind::NamedDecl));
+    assert_eq!(Kind::CaseStmt.category(), Category::Stmt);
+    assert_eq!(Kind::TemplateArgument.category(), Category::Other);
+    assert_eq!(Kind::null.category(), Category::Other);
+}
@@ -0,0 +1,124 @@
+use clang_ast::{IncludedFrom, SourceLocation};
+use serde_derive::{Deserialize, Serialize};
+use std::panic;
+use std::sync::Arc;
+
+pub type Node = clang_ast::Node<Clang>;
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Clang {
+    #[serde(default)]
+    pub loc: SourceLocation,
+}
+
+const JSON: &str = r#"{
+  "kind": "TranslationUnitDecl",
+  "inner": [
+    {"kind": "VarDecl", "loc": {"offset": 1, "file": "a.h", "line": 1, "col": 1, "tokLen": 1, "includedFrom": {"file": "main.cc"}}},
+    {"kind": "VarDecl", "loc": {"offset": 2, "file": "b.h", "line": 1, "col": 1, "tokLen": 1, "includedFrom": {"file": "a.h"}}},
+    {"kind": "VarDecl", "loc": {"offset": 3, "col": 5, "tokLen": 1, "includedFrom": {"file": "a.h"}}},
+    {"kind": "VarDecl", "loc": {"offset": 4, "file": "main.cc", "line": 3, "col": 1, "tokLen": 1}},
+    {"kind": "VarDecl", "loc": {"offset": 5, "file": "b.h", "line": 2, "col": 1, "tokLen": 1, "includedFrom": {"file": "a.h"}}}
+  ]
+}"#;
+
+fn chain(node: &Node) -> Vec<&str> {
+    let mut chain = Vec::new();
+    let loc = node.kind.loc.spelling_loc.as_ref().unwrap();
+    let mut included_from: Option<&IncludedFrom> = loc.included_from.as_ref();
+    while let Some(include) = included_from {
+        chain.push(&*include.file);
+        included_from = include.included_from.as_deref();
+    }
+    chain
+}
+
+#[test]
+fn test_included_from_chain() {
+    for node in [
+        serde_json::from_str::<Node>(JSON).unwrap(),
+        clang_ast::from_str::<Clang>(JSON).unwrap(),
+    ] {
+        assert_eq!(chain(&node.inner[0]), ["main.cc"]);
+        assert_eq!(chain(&node.inner[1]), ["a.h", "main.cc"]);
+        // Still in b.h, where only the immediate includer is printed again.
+        assert_eq!(chain(&node.inner[2]), ["a.h", "main.cc"]);
+        assert!(chain(&node.inner[3]).is_empty());
+        assert_eq!(chain(&node.inner[4]), ["a.h", "main.cc"]);
+
+        // Serialization prints only the immediate includer, like Clang.
+        let json = serde_json::to_value(&node.inner[1]).unwrap();
+        let loc = &json["loc"];
+        let loc = loc.get("spellingLoc").unwrap_or(loc);
+        let included_from = &loc["includedFrom"];
+        assert_eq!(*included_from, serde_json::json!({"file": "a.h"}));
+
+        // And on every location in an included file, also like Clang.
+        let json = clang_ast::faithful(|| serde_json::to_value(&node)).unwrap();
+        let expected: serde_json::Value = serde_json::from_str(JSON).unwrap();
+        let actual = json["inner"].as_array().unwrap();
+        let expected = expected["inner"].as_array().unwrap();
+        for (actual, expected) in actual.iter().zip(expected) {
+            assert_eq!(actual["loc"], expected["loc"]);
+        }
+    }
+}
+
+#[test]
+fn test_filtered() {
+    // Filtering reads each loc ahead of deserializing the node. That must not
+    // disturb the chains seen by the nodes which are kept.
+    let node = clang_ast::from_slice_filtered::<Clang>(JSON.as_bytes(), |loc| {
+        loc.spelling_loc
+            .as_ref()
+            .map_or(true, |loc| &*loc.file != "a.h")
+    })
+    .unwrap();
+    assert_eq!(node.inner.len(), 4);
+    assert_eq!(chain(&node.inner[0]), ["a.h", "main.cc"]);
+    assert_eq!(chain(&node.inner[3]), ["a.h", "main.cc"]);
+}
+
+#[test]
+fn test_include_graph() {
+    let (node, graph) = clang_ast::IncludeGraph::collect(|| clang_ast::from_str::<Clang>(JSON));
+    node.unwrap();
+
+    let files: Vec<&str> = graph.files().iter().map(|file| &*file.file).collect();
+    assert_eq!(files, ["a.h", "main.cc", "b.h"]);
+
+    let main = graph.get("main.cc").unwrap();
+    assert!(main.includers.is_empty());
+    assert_eq!(main.first_offset, Some(4));
+
+    let a = graph.get("a.h").unwrap();
+    assert_eq!(a.includers, [Arc::from("main.cc")]);
+    assert_eq!(a.first_offset, Some(1));
+
+    let b = graph.get("b.h").unwrap();
+    assert_eq!(b.includers, [Arc::from("a.h")]);
+    assert_eq!(b.first_offset, Some(2));
+
+    let included: Vec<&str> = graph.includes("a.h").map(|file| &*file.file).collect();
+    assert_eq!(included, ["b.h"]);
+
+    // Nothing is collected outside of `collect`.
+    let (_node, graph) = clang_ast::IncludeGraph::collect(|| ());
+    assert!(graph.is_empty());
+}
+
+#[test]
+fn test_include_graph_panic() {
+    // A panic inside a nested collect must not leave its graph in place of
+    // the outer one.
+    let ((), graph) = clang_ast::IncludeGraph::collect(|| {
+        let result = panic::catch_unwind(|| {
+            clang_ast::IncludeGraph::collect(|| {
+                clang_ast::from_str::<Clang>(JSON).unwrap();
+                panic!("deserialization failed");
+            })
+        });
+        assert!(result.is_err());
+    });
+    assert!(graph.is_empty());
+}
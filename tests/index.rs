@@ -0,0 +1,59 @@
+use clang_ast::{Flow, Id, IdIndex, Visit};
+use serde_derive::Deserialize;
+use std::ptr;
+
+pub type Node = clang_ast::Node<Clang>;
+
+#[derive(Deserialize)]
+pub struct Clang {}
+
+struct Check<'a, 'ast> {
+    index: &'a IdIndex<'ast, Clang>,
+    ancestors: Vec<&'ast Node>,
+}
+
+impl<'ast> Visit<'ast, Clang> for Check<'_, 'ast> {
+    fn enter(&mut self, node: &'ast Node, _depth: usize) -> Flow {
+        if node.id != Id::NULL {
+            let found = self.index.get(node.id).unwrap();
+            if ptr::eq(found, node) {
+                let parent = self.index.parent(node.id);
+                assert!(match (parent, self.ancestors.last()) {
+                    (Some(a), Some(b)) => ptr::eq(a, *b),
+                    (None, None) => true,
+                    _ => false,
+                });
+                let path = self.index.path(node.id).unwrap();
+                assert_eq!(path.len(), self.ancestors.len() + 1);
+                for (a, b) in path.iter().zip(&self.ancestors) {
+                    assert!(ptr::eq(*a, *b));
+                }
+                assert!(ptr::eq(path[path.len() - 1], node));
+            }
+        }
+        self.ancestors.push(node);
+        Flow::Continue
+    }
+
+    fn leave(&mut self, _node: &'ast Node, _depth: usize) -> Flow {
+        self.ancestors.pop();
+        Flow::Continue
+    }
+}
+
+#[test]
+fn test_index() {
+    let json = clang_ast_test_suite::cxx_ast_json();
+    let node: Node = serde_json::from_slice(&json).unwrap();
+    let index = IdIndex::new(&node);
+    assert!(!index.is_empty());
+    assert!(ptr::eq(&index[node.id], &node));
+    assert!(index.parent(node.id).is_none());
+    assert!(index.resolve(None).is_none());
+    assert!(!index.contains(Id::NULL));
+
+    node.visit(&mut Check {
+        index: &index,
+        ancestors: Vec::new(),
+    });
+}
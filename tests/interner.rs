@@ -0,0 +1,80 @@
+use clang_ast::{Interner, SourceLocation};
+use serde_derive::Deserialize;
+use std::sync::Arc;
+use std::thread;
+
+pub type Node = clang_ast::Node<Clang>;
+
+#[derive(Deserialize, Debug)]
+pub struct Clang {
+    #[serde(default)]
+    pub loc: SourceLocation,
+}
+
+const JSON: &str = r#"{
+  "kind": "TranslationUnitDecl",
+  "inner": [
+    {"kind": "VarDecl", "loc": {"offset": 1, "file": "/usr/include/c++/13/vector", "line": 1, "col": 1, "tokLen": 1, "includedFrom": {"file": "main.cc"}}},
+    {"kind": "VarDecl", "loc": {"offset": 4, "file": "main.cc", "line": 3, "col": 1, "tokLen": 1}}
+  ]
+}"#;
+
+fn file(node: &Node) -> &Arc<str> {
+    &node.inner[0].kind.loc.spelling_loc.as_ref().unwrap().file
+}
+
+#[test]
+fn test_separate_dumps() {
+    let a = clang_ast::from_str::<Clang>(JSON).unwrap();
+    let b = clang_ast::from_str::<Clang>(JSON).unwrap();
+    assert_eq!(file(&a), file(&b));
+    assert!(!Arc::ptr_eq(file(&a), file(&b)));
+}
+
+#[test]
+fn test_install() {
+    let interner = Interner::new();
+    let a = interner.install(|| clang_ast::from_str::<Clang>(JSON).unwrap());
+    let b = interner.install(|| serde_json::from_str::<Node>(JSON).unwrap());
+    assert!(Arc::ptr_eq(file(&a), file(&b)));
+    assert!(Arc::ptr_eq(
+        file(&a),
+        &interner.intern("/usr/include/c++/13/vector")
+    ));
+    assert_eq!(interner.len(), 2);
+
+    // Uninstalled afterward.
+    let c = clang_ast::from_str::<Clang>(JSON).unwrap();
+    assert!(!Arc::ptr_eq(file(&a), file(&c)));
+}
+
+#[test]
+fn test_threads() {
+    let interner = Interner::new();
+    let nodes: Vec<Node> = thread::scope(|scope| {
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                scope.spawn(|| {
+                    interner.install(|| {
+                        clang_ast::top_level::<Clang>(JSON.as_bytes())
+                            .unwrap()
+                            .next()
+                            .unwrap()
+                            .unwrap()
+                    })
+                })
+            })
+            .collect();
+        threads
+            .into_iter()
+            .map(|thread| thread.join().unwrap())
+            .collect()
+    });
+    for node in &nodes {
+        let file = &node.kind.loc.spelling_loc.as_ref().unwrap().file;
+        assert!(Arc::ptr_eq(
+            file,
+            &interner.intern("/usr/include/c++/13/vector")
+        ));
+    }
+}
@@ -0,0 +1,293 @@
+use clang_ast::matcher::{
+    any, has_ancestor, has_child, has_descendant, in_file, is_expansion_in_main_file, kind, node,
+    not,
+};
+use clang_ast::{HasKind, HasRange, Kind, SourceRange};
+use serde_derive::Deserialize;
+
+pub type Node = clang_ast::Node<Clang>;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Clang {
+    pub kind: Kind,
+    pub name: Option<String>,
+    pub referenced_decl: Option<Decl>,
+    #[serde(default)]
+    pub range: SourceRange,
+}
+
+#[derive(Deserialize)]
+pub struct Decl {
+    pub name: String,
+}
+
+impl HasKind for Clang {
+    fn kind(&self) -> Kind {
+        self.kind
+    }
+}
+
+impl HasRange for Clang {
+    fn range(&self) -> Option<&SourceRange> {
+        Some(&self.range)
+    }
+}
+
+// #include <lib/helper.h>
+// #define CALL(f) f()
+//
+// int main() {
+//   helper();
+//   CALL(helper);
+// }
+//
+// where lib/helper.h declares `helper` and `helper2`. Clang prints the file
+// and line only when they change, and the includer on every location in a
+// header.
+const JSON: &str = r#"
+{
+  "id": "0x1",
+  "kind": "TranslationUnitDecl",
+  "inner": [
+    {
+      "id": "0x2",
+      "kind": "FunctionDecl",
+      "range": {
+        "begin": {"offset": 0, "file": "/usr/include/lib/helper.h", "line": 1, "col": 1, "tokLen": 4, "includedFrom": {"file": "src/main.cc"}},
+        "end": {"offset": 12, "col": 13, "tokLen": 1, "includedFrom": {"file": "src/main.cc"}}
+      },
+      "name": "helper"
+    },
+    {
+      "id": "0x3",
+      "kind": "FunctionDecl",
+      "range": {
+        "begin": {"offset": 15, "line": 2, "col": 1, "tokLen": 4, "includedFrom": {"file": "src/main.cc"}},
+        "end": {"offset": 28, "col": 14, "tokLen": 1, "includedFrom": {"file": "src/main.cc"}}
+      },
+      "name": "helper2"
+    },
+    {
+      "id": "0x4",
+      "kind": "FunctionDecl",
+      "range": {
+        "begin": {"offset": 46, "file": "src/main.cc", "line": 4, "col": 1, "tokLen": 3},
+        "end": {"offset": 89, "line": 7, "col": 1, "tokLen": 1}
+      },
+      "name": "main",
+      "inner": [
+        {
+          "id": "0x5",
+          "kind": "CompoundStmt",
+          "range": {
+            "begin": {"offset": 57, "line": 4, "col": 12, "tokLen": 1},
+            "end": {"offset": 89, "line": 7, "col": 1, "tokLen": 1}
+          },
+          "inner": [
+            {
+              "id": "0x6",
+              "kind": "CallExpr",
+              "range": {
+                "begin": {"offset": 61, "line": 5, "col": 3, "tokLen": 6},
+                "end": {"offset": 68, "col": 10, "tokLen": 1}
+              },
+              "inner": [
+                {
+                  "id": "0x7",
+                  "kind": "DeclRefExpr",
+                  "range": {
+                    "begin": {"offset": 61, "col": 3, "tokLen": 6},
+                    "end": {"offset": 61, "col": 3, "tokLen": 6}
+                  },
+                  "referencedDecl": {"id": "0x2", "kind": "FunctionDecl", "name": "helper"}
+                }
+              ]
+            },
+            {
+              "id": "0x8",
+              "kind": "CallExpr",
+              "range": {
+                "begin": {
+                  "spellingLoc": {"offset": 6, "file": "<scratch space>", "line": 1, "col": 7, "tokLen": 6},
+                  "expansionLoc": {"offset": 74, "file": "src/main.cc", "line": 6, "col": 3, "tokLen": 4, "isMacroArgExpansion": true}
+                },
+                "end": {
+                  "spellingLoc": {"offset": 24, "line": 2, "col": 19, "tokLen": 1},
+                  "expansionLoc": {"offset": 85, "line": 6, "col": 14, "tokLen": 1}
+                }
+              },
+              "inner": [
+                {
+                  "id": "0x9",
+                  "kind": "DeclRefExpr",
+                  "range": {
+                    "begin": {
+                      "spellingLoc": {"offset": 6, "file": "<scratch space>", "line": 1, "col": 7, "tokLen": 6},
+                      "expansionLoc": {"offset": 79, "file": "src/main.cc", "line": 6, "col": 8, "tokLen": 6, "isMacroArgExpansion": true}
+                    },
+                    "end": {
+                      "spellingLoc": {"offset": 6, "file": "<scratch space>", "line": 1, "col": 7, "tokLen": 6},
+                      "expansionLoc": {"offset": 79, "file": "src/main.cc", "line": 6, "col": 8, "tokLen": 6, "isMacroArgExpansion": true}
+                    }
+                  },
+                  "referencedDecl": {"id": "0x2", "kind": "FunctionDecl", "name": "helper"}
+                }
+              ]
+            }
+          ]
+        }
+      ]
+    }
+  ]
+}
+"#;
+
+fn ids(results: &[clang_ast::matcher::Match<Clang>]) -> Vec<String> {
+    results
+        .iter()
+        .map(|found| found.node().id.to_string())
+        .collect()
+}
+
+fn calls(name: &'static str) -> clang_ast::matcher::Matcher<'static, Clang> {
+    kind(Kind::CallExpr).and(has_child(kind(Kind::DeclRefExpr).and(node(
+        move |clang: &Clang| {
+            clang
+                .referenced_decl
+                .as_ref()
+                .is_some_and(|decl| decl.name == name)
+        },
+    ))))
+}
+
+#[test]
+fn test_kind() {
+    let root: Node = clang_ast::from_str(JSON).unwrap();
+
+    let matcher = kind(Kind::FunctionDecl);
+    assert_eq!(ids(&matcher.find_all(&root)), ["0x2", "0x3", "0x4"]);
+
+    // Matches subclasses too.
+    let matcher = kind(Kind::Expr);
+    assert_eq!(ids(&matcher.find_all(&root)), ["0x6", "0x7", "0x8", "0x9"]);
+
+    let matcher = kind(Kind::Stmt).and(not(kind(Kind::Expr)));
+    assert_eq!(ids(&matcher.find_all(&root)), ["0x5"]);
+
+    let matcher = kind(Kind::CompoundStmt).or(kind(Kind::TranslationUnitDecl));
+    assert_eq!(ids(&matcher.find_all(&root)), ["0x1", "0x5"]);
+
+    assert_eq!(any().find_all(&root).len(), 9);
+    assert!(kind(Kind::TranslationUnitDecl).matches(&root.cursor()));
+}
+
+#[test]
+fn test_traversal() {
+    let root: Node = clang_ast::from_str(JSON).unwrap();
+
+    assert_eq!(ids(&calls("helper").find_all(&root)), ["0x6", "0x8"]);
+    assert!(calls("exit").find_all(&root).is_empty());
+
+    let matcher = kind(Kind::FunctionDecl).and(has_descendant(calls("helper")));
+    assert_eq!(ids(&matcher.find_all(&root)), ["0x4"]);
+
+    // A child is not a descendant of itself, and nodes are not their own
+    // ancestors.
+    let matcher = kind(Kind::CompoundStmt).and(has_child(kind(Kind::CompoundStmt)));
+    assert!(matcher.find_all(&root).is_empty());
+    let matcher = kind(Kind::DeclRefExpr).and(has_ancestor(kind(Kind::DeclRefExpr)));
+    assert!(matcher.find_all(&root).is_empty());
+
+    let matcher = kind(Kind::DeclRefExpr).and(has_ancestor(node(|clang: &Clang| {
+        clang.name.as_deref() == Some("main")
+    })));
+    assert_eq!(ids(&matcher.find_all(&root)), ["0x7", "0x9"]);
+}
+
+#[test]
+fn test_location() {
+    let root: Node = clang_ast::from_str(JSON).unwrap();
+
+    let matcher = kind(Kind::Decl).and(is_expansion_in_main_file());
+    assert_eq!(ids(&matcher.find_all(&root)), ["0x4"]);
+
+    // Spelled in scratch space, but expanded in the main file.
+    let matcher = kind(Kind::Expr).and(is_expansion_in_main_file());
+    assert_eq!(ids(&matcher.find_all(&root)), ["0x6", "0x7", "0x8", "0x9"]);
+
+    let matcher = kind(Kind::Decl).and(in_file("**/lib/*.h"));
+    assert_eq!(ids(&matcher.find_all(&root)), ["0x2", "0x3"]);
+
+    let matcher = kind(Kind::Decl).and(in_file("/usr/*/*.h"));
+    assert!(matcher.find_all(&root).is_empty());
+
+    let matcher = kind(Kind::Decl).and(in_file("**/main.??"));
+    assert_eq!(ids(&matcher.find_all(&root)), ["0x4"]);
+
+    let matcher = kind(Kind::CallExpr).and(in_file("src/**"));
+    assert_eq!(ids(&matcher.find_all(&root)), ["0x6", "0x8"]);
+
+    let matcher = kind(Kind::CallExpr).and(in_file("src/*/main.cc"));
+    assert!(matcher.find_all(&root).is_empty());
+}
+
+#[test]
+fn test_bind() {
+    let root: Node = clang_ast::from_str(JSON).unwrap();
+
+    let matcher = calls("helper")
+        .and(has_ancestor(kind(Kind::FunctionDecl).bind("function")))
+        .and(has_child(kind(Kind::DeclRefExpr).bind("callee")))
+        .bind("call");
+    let results = matcher.find_all(&root);
+    assert_eq!(results.len(), 2);
+    for found in &results {
+        assert_eq!(found.get("call").unwrap().id, found.node().id);
+        let function = found.get("function").unwrap();
+        assert_eq!(function.kind.name.as_deref(), Some("main"));
+        let callee = found.get("callee").unwrap();
+        assert_eq!(callee.kind.kind, Kind::DeclRefExpr);
+        assert!(found.get("missing").is_none());
+    }
+    assert_eq!(results[1].get("callee").unwrap().id.to_string(), "0x9");
+
+    // Bindings from the branch that did not match are discarded.
+    let matcher = kind(Kind::CallExpr)
+        .bind("call")
+        .and(kind(Kind::Decl))
+        .or(kind(Kind::FunctionDecl).bind("function"));
+    let results = matcher.find_all(&root);
+    assert_eq!(ids(&results), ["0x2", "0x3", "0x4"]);
+    assert!(results[0].get("call").is_none());
+    assert!(results[0].get("function").is_some());
+
+    let matcher = kind(Kind::CallExpr).and(not(kind(Kind::CallExpr).bind("call")).or(any()));
+    let results = matcher.find_all(&root);
+    assert_eq!(results.len(), 2);
+    assert!(results[0].get("call").is_none());
+}
+
+#[derive(Deserialize)]
+struct Borrowed<'a> {
+    kind: Kind,
+    #[serde(borrow)]
+    name: Option<&'a str>,
+}
+
+impl HasKind for Borrowed<'_> {
+    fn kind(&self) -> Kind {
+        self.kind
+    }
+}
+
+#[test]
+fn test_borrowed() {
+    let root: clang_ast::Node<Borrowed> = clang_ast::from_str(JSON).unwrap();
+
+    let name = String::from("helper2");
+    let matcher = kind(Kind::FunctionDecl).and(node(|clang: &Borrowed| clang.name == Some(&*name)));
+    let results = matcher.find_all(&root);
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].node().id.to_string(), "0x3");
+}
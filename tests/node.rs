@@ -0,0 +1,48 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+pub type Node = clang_ast::Node<Clang>;
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Clang {
+    ParenExpr,
+    IntegerLiteral(u64),
+}
+
+fn deep(depth: usize, value: u64) -> Node {
+    let mut node = Node {
+        id: clang_ast::Id::NULL,
+        kind: Clang::IntegerLiteral(value),
+        inner: Vec::new(),
+    };
+    for _ in 0..depth {
+        node = Node {
+            id: clang_ast::Id::NULL,
+            kind: Clang::ParenExpr,
+            inner: vec![node],
+        };
+    }
+    node
+}
+
+fn hash(node: &Node) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    node.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[test]
+fn test_deep() {
+    const DEPTH: usize = 1_000_000;
+
+    let node = deep(DEPTH, 0);
+    let clone = node.clone();
+    assert_eq!(clone, node);
+    assert_eq!(hash(&clone), hash(&node));
+
+    let other = deep(DEPTH, 1);
+    assert_ne!(other, node);
+
+    let shallow = deep(DEPTH - 1, 0);
+    assert_ne!(shallow, node);
+}
@@ -0,0 +1,89 @@
+use clang_ast::{Kind, SourceLocation, SourceRange};
+use serde_derive::Deserialize;
+use std::fmt::Write as _;
+use std::sync::Arc;
+
+pub type Node = clang_ast::Node<Clang>;
+
+#[derive(Deserialize, PartialEq, Debug)]
+pub struct Clang {
+    pub kind: Kind,
+    #[serde(default)]
+    pub loc: SourceLocation,
+    #[serde(default)]
+    pub range: SourceRange,
+}
+
+// Enough top-level declarations for the dump to be split into several chunks,
+// with most locations leaving out the file and line.
+fn generated_json() -> String {
+    let mut json = String::from(r#"{"id":"0x1","kind":"TranslationUnitDecl","inner":["#);
+    for i in 0..2000 {
+        if i > 0 {
+            json.push(',');
+        }
+        let offset = i * 10;
+        let loc = match i % 100 {
+            0 => format!(
+                r#"{{"offset":{offset},"file":"header{}.h","line":{i},"col":1,"tokLen":3,"includedFrom":{{"file":"main.cc"}}}}"#,
+                i / 100 % 3,
+            ),
+            50 => {
+                format!(r#"{{"offset":{offset},"line":{i},"presumedLine":7,"col":1,"tokLen":3}}"#)
+            }
+            _ => format!(r#"{{"offset":{offset},"col":5,"tokLen":1}}"#),
+        };
+        write!(
+            json,
+            r#"{{"id":"0x{:x}","kind":"VarDecl","loc":{loc},"range":{{"begin":{{"offset":{offset},"col":1,"tokLen":3}},"end":{{"offset":{},"col":9,"tokLen":1}}}},"inner":[{{"id":"0x{:x}","kind":"IntegerLiteral","range":{{"begin":{{"offset":{},"col":9,"tokLen":1}},"end":{{"offset":{},"col":9,"tokLen":1}}}}}}]}}"#,
+            2 * i + 2,
+            offset + 8,
+            2 * i + 3,
+            offset + 8,
+            offset + 8,
+        )
+        .unwrap();
+    }
+    json.push_str("]}");
+    json
+}
+
+#[test]
+fn test_parallel() {
+    let json = generated_json();
+    let serial = clang_ast::from_str::<Clang>(&json).unwrap();
+    let parallel = clang_ast::from_slice_parallel::<Clang>(json.as_bytes()).unwrap();
+    assert_eq!(serial, parallel);
+
+    // File paths are interned across chunks.
+    let file = |node: &Node| Arc::clone(&node.kind.loc.spelling_loc.as_ref().unwrap().file);
+    assert!(Arc::ptr_eq(
+        &file(&parallel.inner[1]),
+        &file(&parallel.inner[1800])
+    ));
+}
+
+#[test]
+fn test_cxx() {
+    let json = clang_ast_test_suite::cxx_ast_json();
+    let serial = clang_ast::from_slice::<Clang>(&json).unwrap();
+    let parallel = clang_ast::from_slice_parallel::<Clang>(&json).unwrap();
+    assert_eq!(serial, parallel);
+}
+
+#[test]
+fn test_error() {
+    let mut json = generated_json();
+    let i = json
+        .find(r#""kind":"VarDecl","loc":{"offset":15000"#)
+        .unwrap();
+    json.replace_range(i..i + 17, r#""kind":"VarDecl" "#);
+    let serial = clang_ast::from_str::<Clang>(&json).unwrap_err();
+    let parallel = clang_ast::from_slice_parallel::<Clang>(json.as_bytes()).unwrap_err();
+    assert_eq!(serial.to_string(), parallel.to_string());
+
+    let empty =
+        clang_ast::from_slice_parallel::<Clang>(br#"{"kind":"TranslationUnitDecl","inner":[]}"#)
+            .unwrap();
+    assert!(empty.inner.is_empty());
+}
@@ -0,0 +1,204 @@
+use clang_ast::{HasRange, SourceRange};
+use serde_derive::Deserialize;
+use std::cmp::Ordering;
+
+pub type Node = clang_ast::Node<Clang>;
+
+#[derive(Deserialize, Debug)]
+pub struct Clang {
+    pub kind: String,
+    #[serde(default)]
+    pub range: SourceRange,
+}
+
+impl HasRange for Clang {
+    fn range(&self) -> Option<&SourceRange> {
+        Some(&self.range)
+    }
+}
+
+// int f(int a) {
+//   return a + 1;
+// }
+// int g;
+const JSON: &str = r#"{
+  "kind": "TranslationUnitDecl",
+  "range": {"begin": {}, "end": {}},
+  "inner": [
+    {
+      "kind": "FunctionDecl",
+      "range": {
+        "begin": {"offset": 0, "file": "main.cc", "line": 1, "col": 1, "tokLen": 3},
+        "end": {"offset": 30, "line": 3, "col": 1, "tokLen": 1}
+      },
+      "inner": [
+        {
+          "kind": "ParmVarDecl",
+          "range": {
+            "begin": {"offset": 6, "line": 1, "col": 7, "tokLen": 3},
+            "end": {"offset": 10, "col": 11, "tokLen": 1}
+          }
+        },
+        {
+          "kind": "CompoundStmt",
+          "range": {
+            "begin": {"offset": 13, "col": 14, "tokLen": 1},
+            "end": {"offset": 30, "line": 3, "col": 1, "tokLen": 1}
+          },
+          "inner": [
+            {
+              "kind": "ReturnStmt",
+              "range": {
+                "begin": {"offset": 17, "line": 2, "col": 3, "tokLen": 6},
+                "end": {"offset": 28, "col": 14, "tokLen": 1}
+              },
+              "inner": [
+                {
+                  "kind": "ImplicitCastExpr",
+                  "range": {"begin": {}, "end": {}},
+                  "inner": [
+                    {
+                      "kind": "BinaryOperator",
+                      "range": {
+                        "begin": {"offset": 24, "col": 10, "tokLen": 1},
+                        "end": {"offset": 28, "col": 14, "tokLen": 1}
+                      }
+                    }
+                  ]
+                }
+              ]
+            }
+          ]
+        }
+      ]
+    },
+    {
+      "kind": "VarDecl",
+      "range": {
+        "begin": {"offset": 32, "line": 4, "col": 1, "tokLen": 3},
+        "end": {"offset": 36, "col": 5, "tokLen": 1}
+      }
+    }
+  ]
+}"#;
+
+#[test]
+fn test_range_operations() {
+    let node = clang_ast::from_str::<Clang>(JSON).unwrap();
+    let function = &node.inner[0].kind.range;
+    let param = &node.inner[0].inner[0].kind.range;
+    let body = &node.inner[0].inner[1].kind.range;
+    let var = &node.inner[1].kind.range;
+    let empty = &node.kind.range;
+
+    assert!(function.contains(param));
+    assert!(function.contains(body));
+    assert!(function.contains(function));
+    assert!(!param.contains(function));
+    assert!(!function.contains(var));
+    assert!(!function.contains(empty));
+    assert!(!empty.contains(empty));
+
+    assert!(function.overlaps(body));
+    assert!(!param.overlaps(body));
+    assert!(!function.overlaps(var));
+
+    assert!(param.contains_offset("main.cc", 6));
+    assert!(param.contains_offset("main.cc", 10));
+    assert!(!param.contains_offset("main.cc", 11));
+    assert!(!param.contains_offset("other.cc", 6));
+
+    let union = param.union(var).unwrap();
+    assert_eq!(union.begin.file_loc().unwrap().offset, 6);
+    assert_eq!(union.end.file_loc().unwrap().offset, 36);
+    assert!(union.contains(body));
+    assert!(param.union(empty).is_none());
+}
+
+#[test]
+fn test_ord() {
+    let node = clang_ast::from_str::<Clang>(JSON).unwrap();
+    let mut locs: Vec<_> = [&node.inner[1], &node.inner[0].inner[1], &node.inner[0]]
+        .into_iter()
+        .map(|node| node.kind.range.begin.spelling_loc.as_ref().unwrap())
+        .collect();
+    locs.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let offsets: Vec<usize> = locs.iter().map(|loc| loc.offset).collect();
+    assert_eq!(offsets, [0, 13, 32]);
+    assert!(locs[0] < locs[1]);
+}
+
+#[test]
+fn test_ord_several_files() {
+    let node = clang_ast::from_str::<Clang>(JSON).unwrap();
+    let main = node.inner[0].kind.range.begin.spelling_loc.clone().unwrap();
+    let at = |file: &str, offset: usize| {
+        let mut loc = main.clone();
+        loc.file = file.into();
+        loc.offset = offset;
+        loc
+    };
+    let mut locs = [
+        at("main.cc", 32),
+        at("b.h", 4),
+        at("main.cc", 0),
+        at("a.h", 10),
+        at("b.h", 1),
+        at("a.h", 2),
+    ];
+    locs.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let positions: Vec<(&str, usize)> = locs.iter().map(|loc| (&*loc.file, loc.offset)).collect();
+    assert_eq!(
+        positions,
+        [
+            ("a.h", 2),
+            ("a.h", 10),
+            ("b.h", 1),
+            ("b.h", 4),
+            ("main.cc", 0),
+            ("main.cc", 32),
+        ],
+    );
+
+    // Same file and offset, but different otherwise.
+    let mut macro_arg = main.clone();
+    macro_arg.is_macro_arg_expansion = true;
+    assert_eq!(main.cmp(&main.clone()), Ordering::Equal);
+    assert_ne!(main.cmp(&macro_arg), Ordering::Equal);
+    assert_eq!(main.cmp(&macro_arg), macro_arg.cmp(&main).reverse());
+}
+
+#[test]
+fn test_innermost_at() {
+    let node = clang_ast::from_str::<Clang>(JSON).unwrap();
+    let kind_at = |offset| {
+        node.innermost_at("main.cc", offset)
+            .map(|cursor| cursor.node().kind.kind.as_str())
+    };
+
+    assert_eq!(kind_at(0), Some("FunctionDecl"));
+    assert_eq!(kind_at(7), Some("ParmVarDecl"));
+    assert_eq!(kind_at(12), Some("FunctionDecl"));
+    assert_eq!(kind_at(18), Some("ReturnStmt"));
+    assert_eq!(kind_at(26), Some("BinaryOperator"));
+    assert_eq!(kind_at(33), Some("VarDecl"));
+    assert_eq!(kind_at(100), None);
+    assert!(node.innermost_at("other.cc", 26).is_none());
+
+    let cursor = node.innermost_at("main.cc", 26).unwrap();
+    let ancestors: Vec<&str> = cursor
+        .ancestors()
+        .map(|node| node.kind.kind.as_str())
+        .collect();
+    assert_eq!(
+        ancestors,
+        [
+            "ImplicitCastExpr",
+            "ReturnStmt",
+            "CompoundStmt",
+            "FunctionDecl",
+            "TranslationUnitDecl",
+        ],
+    );
+    assert_eq!(cursor.child_index(), Some(0));
+}
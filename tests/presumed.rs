@@ -0,0 +1,82 @@
+use clang_ast::{PresumedLoc, SourceLocation};
+use serde_derive::{Deserialize, Serialize};
+
+pub type Node = clang_ast::Node<Clang>;
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Clang {
+    pub loc: SourceLocation,
+}
+
+// Locations in parser.tab.c as generated by bison, which switches back and
+// forth between `#line N "parser.y"` and `#line N "parser.tab.c"`.
+const JSON: &str = r#"{
+  "kind": "TranslationUnitDecl",
+  "loc": {},
+  "inner": [
+    {"kind": "VarDecl", "loc": {"offset": 10, "file": "parser.tab.c", "line": 5, "col": 1, "tokLen": 3}},
+    {"kind": "VarDecl", "loc": {"offset": 100, "line": 20, "presumedFile": "parser.y", "presumedLine": 3, "col": 1, "tokLen": 3}},
+    {"kind": "VarDecl", "loc": {"offset": 108, "col": 9, "tokLen": 1}},
+    {"kind": "VarDecl", "loc": {"offset": 130, "line": 21, "presumedLine": 4, "col": 1, "tokLen": 3}},
+    {"kind": "VarDecl", "loc": {"offset": 200, "line": 30, "col": 1, "tokLen": 3}},
+    {"kind": "VarDecl", "loc": {"offset": 300, "line": 40, "presumedLine": 100, "col": 5, "tokLen": 1}},
+    {"kind": "VarDecl", "loc": {"offset": 400, "file": "parser.h", "line": 2, "col": 1, "tokLen": 3}}
+  ]
+}"#;
+
+fn presumed(node: &Node) -> PresumedLoc<'_> {
+    node.kind.loc.presumed().unwrap()
+}
+
+#[test]
+fn test_presumed() {
+    for node in [
+        serde_json::from_str::<Node>(JSON).unwrap(),
+        clang_ast::from_str::<Clang>(JSON).unwrap(),
+    ] {
+        let presumed: Vec<String> = node
+            .inner
+            .iter()
+            .map(|node| presumed(node).to_string())
+            .collect();
+        assert_eq!(
+            presumed,
+            [
+                "parser.tab.c:5:1",
+                "parser.y:3:1",
+                "parser.y:3:9",
+                "parser.y:4:1",
+                "parser.tab.c:30:1",
+                "parser.tab.c:100:5",
+                "parser.h:2:1",
+            ],
+        );
+
+        let loc = node.inner[2].kind.loc.spelling_loc.as_ref().unwrap();
+        assert_eq!(loc.presumed_file.as_deref(), Some("parser.y"));
+        assert_eq!(loc.presumed_line, Some(3));
+        let loc = node.inner[4].kind.loc.spelling_loc.as_ref().unwrap();
+        assert_eq!(loc.presumed_file, None);
+        assert_eq!(loc.presumed_line, None);
+
+        assert_eq!(node.kind.loc.presumed(), None);
+    }
+}
+
+#[test]
+fn test_serialize() {
+    // Serializing omits the presumed file and line when unchanged, like Clang.
+    let node = clang_ast::from_str::<Clang>(JSON).unwrap();
+    let expected: serde_json::Value = serde_json::from_str(JSON).unwrap();
+    let actual = serde_json::to_value(&node).unwrap();
+    for (expected, actual) in expected["inner"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .zip(actual["inner"].as_array().unwrap())
+    {
+        let actual = &actual["loc"];
+        let actual = actual.get("spellingLoc").unwrap_or(actual);
+        assert_eq!(expected["loc"], *actual);
+    }
+}
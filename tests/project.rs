@@ -0,0 +1,280 @@
+use clang_ast::{HasDecl, HasKind, Id, Kind, Project, SourceLocation};
+use serde_derive::Deserialize;
+
+pub type Node = clang_ast::Node<Clang>;
+
+#[derive(Deserialize, Debug)]
+pub struct Clang {
+    pub kind: Kind,
+    pub name: Option<String>,
+    #[serde(rename = "mangledName")]
+    pub mangled_name: Option<String>,
+    #[serde(default)]
+    pub loc: SourceLocation,
+    #[serde(rename = "type")]
+    pub ty: Option<Type>,
+    #[serde(default)]
+    pub bases: Vec<Base>,
+    #[serde(rename = "storageClass")]
+    pub storage_class: Option<String>,
+    #[serde(rename = "parentDeclContextId")]
+    pub parent_decl_context_id: Option<Id>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Type {
+    #[serde(rename = "qualType")]
+    pub qual_type: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Base {
+    #[serde(rename = "type")]
+    pub ty: Type,
+}
+
+impl HasKind for Clang {
+    fn kind(&self) -> Kind {
+        self.kind
+    }
+}
+
+impl HasDecl for Clang {
+    fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    fn mangled_name(&self) -> Option<&str> {
+        self.mangled_name.as_deref()
+    }
+
+    fn loc(&self) -> Option<&SourceLocation> {
+        Some(&self.loc)
+    }
+
+    fn qual_type(&self) -> Option<&str> {
+        Some(&self.ty.as_ref()?.qual_type)
+    }
+
+    fn bases(&self) -> Vec<&str> {
+        self.bases.iter().map(|base| &*base.ty.qual_type).collect()
+    }
+
+    fn storage_class(&self) -> Option<&str> {
+        self.storage_class.as_deref()
+    }
+
+    fn parent_decl_context(&self) -> Option<Id> {
+        self.parent_decl_context_id
+    }
+}
+
+// Declarations from shape.h, with ids differing in each unit.
+fn header(id: u32) -> String {
+    let loc = |offset: u32| {
+        format!(r#"{{"offset": {offset}, "file": "shape.h", "line": 1, "col": 1, "tokLen": 1}}"#)
+    };
+    format!(
+        r#"
+    {{
+      "id": "0x{id:x}01", "kind": "NamespaceDecl", "loc": {}, "name": "geo",
+      "inner": [
+        {{
+          "id": "0x{id:x}02", "kind": "CXXRecordDecl", "loc": {}, "name": "Shape",
+          "inner": [
+            {{"id": "0x{id:x}03", "kind": "CXXRecordDecl", "loc": {}, "name": "Shape"}},
+            {{"id": "0x{id:x}04", "kind": "CXXMethodDecl", "loc": {}, "name": "area", "type": {{"qualType": "double () const"}}}}
+          ]
+        }},
+        {{
+          "id": "0x{id:x}05", "kind": "CXXRecordDecl", "loc": {}, "name": "Circle",
+          "bases": [{{"type": {{"qualType": "Shape"}}}}],
+          "inner": [
+            {{"id": "0x{id:x}06", "kind": "CXXMethodDecl", "loc": {}, "name": "area", "type": {{"qualType": "double () const"}}}},
+            {{"id": "0x{id:x}07", "kind": "CXXMethodDecl", "loc": {}, "name": "radius", "type": {{"qualType": "double () const"}}}}
+          ]
+        }},
+        {{"id": "0x{id:x}08", "kind": "FunctionDecl", "loc": {}, "name": "f", "mangledName": "_ZN3geo1fEv", "type": {{"qualType": "void ()"}}}}
+      ]
+    }}"#,
+        loc(10),
+        loc(26),
+        loc(26),
+        loc(50),
+        loc(80),
+        loc(120),
+        loc(140),
+        loc(200),
+    )
+}
+
+fn unit_a() -> String {
+    format!(
+        r#"{{
+  "id": "0xa00", "kind": "TranslationUnitDecl",
+  "inner": [{},
+    {{
+      "id": "0xa10", "kind": "NamespaceDecl", "loc": {{"offset": 0, "file": "a.cc", "line": 1, "col": 1, "tokLen": 9}},
+      "inner": [
+        {{"id": "0xa11", "kind": "VarDecl", "loc": {{"offset": 16, "col": 17, "tokLen": 6}}, "name": "helper", "mangledName": "_ZN12_GLOBAL__N_16helperE"}}
+      ]
+    }},
+    {{
+      "id": "0xa20", "kind": "FunctionDecl", "loc": {{"offset": 50, "line": 3, "col": 5, "tokLen": 4}}, "name": "main", "mangledName": "main",
+      "inner": [
+        {{
+          "id": "0xa21", "kind": "CompoundStmt",
+          "inner": [
+            {{"id": "0xa22", "kind": "DeclStmt", "inner": [{{"id": "0xa23", "kind": "VarDecl", "loc": {{"offset": 70, "col": 7, "tokLen": 5}}, "name": "local"}}]}}
+          ]
+        }}
+      ]
+    }},
+    {{"id": "0xa30", "kind": "FunctionDecl", "loc": {{"offset": 90, "line": 5, "col": 13, "tokLen": 4}}, "name": "init", "mangledName": "_ZL4initv", "storageClass": "static"}}
+  ]
+}}"#,
+        header(0xa),
+    )
+}
+
+fn unit_b() -> String {
+    format!(
+        r#"{{
+  "id": "0xb00", "kind": "TranslationUnitDecl",
+  "inner": [{},
+    {{
+      "id": "0xb10", "kind": "CXXRecordDecl", "loc": {{"offset": 10, "file": "b.cc", "line": 2, "col": 7, "tokLen": 6}}, "name": "Square",
+      "bases": [{{"type": {{"qualType": "geo::Shape"}}}}],
+      "inner": [
+        {{"id": "0xb11", "kind": "CXXRecordDecl", "loc": {{"offset": 10, "col": 7, "tokLen": 6}}, "name": "Square"}},
+        {{"id": "0xb12", "kind": "CXXMethodDecl", "loc": {{"offset": 30, "col": 10, "tokLen": 4}}, "name": "area", "type": {{"qualType": "double () const"}}}},
+        {{"id": "0xb13", "kind": "CXXMethodDecl", "loc": {{"offset": 60, "col": 10, "tokLen": 4}}, "name": "area", "type": {{"qualType": "double (int) const"}}}}
+      ]
+    }},
+    {{"id": "0xb20", "kind": "CXXMethodDecl", "loc": {{"offset": 100, "line": 8, "col": 20, "tokLen": 4}}, "name": "area", "type": {{"qualType": "double () const"}}, "parentDeclContextId": "0xb05"}},
+    {{"id": "0xb30", "kind": "FunctionDecl", "loc": {{"offset": 200, "line": 12, "col": 11, "tokLen": 1}}, "name": "f", "mangledName": "_ZN3geo1fEv", "type": {{"qualType": "void ()"}}, "parentDeclContextId": "0xb01"}},
+    {{
+      "id": "0xb40", "kind": "NamespaceDecl", "loc": {{"offset": 300, "line": 20, "col": 1, "tokLen": 9}},
+      "inner": [
+        {{"id": "0xb41", "kind": "VarDecl", "loc": {{"offset": 316, "col": 17, "tokLen": 6}}, "name": "helper", "mangledName": "_ZN12_GLOBAL__N_16helperE"}}
+      ]
+    }},
+    {{"id": "0xb50", "kind": "FunctionDecl", "loc": {{"offset": 400, "line": 25, "col": 13, "tokLen": 4}}, "name": "init", "mangledName": "_ZL4initv", "storageClass": "static"}}
+  ]
+}}"#,
+        header(0xb),
+    )
+}
+
+fn names<'a>(symbols: impl IntoIterator<Item = &'a clang_ast::Symbol<'a, Clang>>) -> Vec<&'a str> {
+    symbols
+        .into_iter()
+        .map(|symbol| symbol.key.qualified_name.as_str())
+        .collect()
+}
+
+#[test]
+fn test_symbols() {
+    let a = clang_ast::from_str::<Clang>(&unit_a()).unwrap();
+    let b = clang_ast::from_str::<Clang>(&unit_b()).unwrap();
+    let mut project = Project::new();
+    assert_eq!(project.add(&a), 0);
+    assert_eq!(project.add(&b), 1);
+
+    // Declared once in a header included by both units.
+    let shape = project.lookup("geo::Shape").collect::<Vec<_>>();
+    assert_eq!(shape.len(), 1);
+    assert_eq!(shape[0].key.kind, Kind::CXXRecordDecl);
+    assert_eq!(shape[0].declarations.len(), 1);
+    let units: Vec<usize> = shape[0].declarations[0]
+        .nodes
+        .iter()
+        .map(|(unit, _)| *unit)
+        .collect();
+    assert_eq!(units, [0, 1]);
+
+    // Declared in the header and defined out of line in one unit.
+    let f = project.lookup("geo::f").next().unwrap();
+    assert_eq!(f.key.mangled_name.as_deref(), Some("_ZN3geo1fEv"));
+    assert_eq!(f.declarations.len(), 2);
+    assert_eq!(f.declarations[1].file.as_deref(), Some("b.cc"));
+    let area = project.lookup("geo::Circle::area").next().unwrap();
+    assert_eq!(area.declarations.len(), 2);
+    assert!(project
+        .symbol_of(&b.inner[2])
+        .is_some_and(|symbol| symbol.key == area.key));
+
+    // Internal to each unit.
+    let helpers: Vec<Option<usize>> = project
+        .lookup("(anonymous namespace)::helper")
+        .map(|symbol| symbol.key.unit)
+        .collect();
+    assert_eq!(helpers, [Some(0), Some(1)]);
+
+    // Static in each file, with the same mangled name.
+    let inits: Vec<Option<&str>> = project
+        .lookup("init")
+        .map(|symbol| symbol.key.file.as_deref())
+        .collect();
+    assert_eq!(inits, [Some("a.cc"), Some("b.cc")]);
+    assert!(project
+        .lookup("geo::f")
+        .all(|symbol| symbol.key.file.is_none()));
+
+    // Neither local variables nor the implicit injected class name.
+    assert_eq!(project.lookup("main::local").count(), 0);
+    assert_eq!(project.lookup("geo::Shape::Shape").count(), 0);
+    assert!(project
+        .symbols()
+        .iter()
+        .all(|symbol| !symbol.key.qualified_name.ends_with("local")));
+
+    let circle = project.lookup("geo::Circle").next().unwrap();
+    assert_eq!(
+        names(project.members(&circle.key)),
+        ["geo::Circle::area", "geo::Circle::radius"],
+    );
+    assert_eq!(names(project.parent(&circle.key)), ["geo"]);
+}
+
+#[test]
+fn test_overrides() {
+    let a = clang_ast::from_str::<Clang>(&unit_a()).unwrap();
+    let b = clang_ast::from_str::<Clang>(&unit_b()).unwrap();
+    let mut project = Project::new();
+    project.add(&a);
+    project.add(&b);
+
+    let shape = project.lookup("geo::Shape").next().unwrap();
+    assert_eq!(
+        names(project.derived_classes(&shape.key)),
+        ["geo::Circle", "Square"]
+    );
+
+    let area = project.lookup("geo::Shape::area").next().unwrap();
+    assert_eq!(
+        names(project.overrides(&area.key)),
+        ["geo::Circle::area", "Square::area"],
+    );
+
+    let radius = project.lookup("geo::Circle::radius").next().unwrap();
+    assert!(project.overrides(&radius.key).is_empty());
+}
+
+#[test]
+fn test_overrides_incremental() {
+    let a = clang_ast::from_str::<Clang>(&unit_a()).unwrap();
+    let b = clang_ast::from_str::<Clang>(&unit_b()).unwrap();
+    let mut project = Project::new();
+    project.add(&a);
+
+    let shape = project.lookup("geo::Shape").next().unwrap().key.clone();
+    assert_eq!(names(project.derived_classes(&shape)), ["geo::Circle"]);
+
+    // Classes from a unit added after the first query are found too.
+    project.add(&b);
+    assert_eq!(
+        names(project.derived_classes(&shape)),
+        ["geo::Circle", "Square"]
+    );
+}
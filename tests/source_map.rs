@@ -0,0 +1,134 @@
+use clang_ast::{SourceLocation, SourceMap, SourceRange};
+use serde_derive::Deserialize;
+use std::cell::Cell;
+use std::rc::{Rc, Weak};
+
+pub type Node = clang_ast::Node<Clang>;
+
+#[derive(Deserialize, Debug)]
+pub struct Clang {
+    #[serde(default)]
+    pub loc: SourceLocation,
+    #[serde(default)]
+    pub range: SourceRange,
+}
+
+const SOURCE: &str = "#define ZERO 0\nint x = ZERO;\nint y = 1 + 2;\n";
+
+const JSON: &str = r#"{
+  "kind": "TranslationUnitDecl",
+  "inner": [
+    {
+      "kind": "VarDecl",
+      "loc": {"offset": 19, "file": "main.cc", "line": 2, "col": 5, "tokLen": 1},
+      "range": {
+        "begin": {"offset": 15, "col": 1, "tokLen": 3},
+        "end": {
+          "spellingLoc": {"offset": 13, "line": 1, "col": 14, "tokLen": 1},
+          "expansionLoc": {"offset": 23, "line": 2, "col": 9, "tokLen": 4}
+        }
+      }
+    },
+    {
+      "kind": "VarDecl",
+      "loc": {"offset": 33, "line": 3, "col": 5, "tokLen": 1},
+      "range": {
+        "begin": {"offset": 29, "col": 1, "tokLen": 3},
+        "end": {"offset": 41, "col": 13, "tokLen": 1}
+      }
+    },
+    {
+      "kind": "VarDecl",
+      "loc": {"offset": 0, "file": "other.h", "line": 1, "col": 1, "tokLen": 3},
+      "range": {
+        "begin": {"offset": 0, "col": 1, "tokLen": 3},
+        "end": {"offset": 4, "col": 5, "tokLen": 1}
+      }
+    }
+  ]
+}"#;
+
+#[test]
+fn test_text() {
+    let node = clang_ast::from_str::<Clang>(JSON).unwrap();
+    let mut source_map = SourceMap::new();
+    source_map.insert("main.cc", SOURCE);
+
+    let x = &node.inner[0].kind;
+    assert_eq!(source_map.text(&x.range).as_deref(), Some("int x = ZERO"));
+    assert_eq!(source_map.token(&x.loc).as_deref(), Some("x"));
+
+    let y = &node.inner[1].kind;
+    assert_eq!(source_map.text(&y.range).as_deref(), Some("int y = 1 + 2"));
+    assert_eq!(
+        source_map.spelling_text(&y.range).as_deref(),
+        Some("int y = 1 + 2")
+    );
+
+    // Not inserted.
+    let other = &node.inner[2].kind;
+    assert_eq!(source_map.text(&other.range), None);
+
+    assert_eq!(source_map.text(&SourceRange::default()), None);
+}
+
+#[test]
+fn test_spelling_text() {
+    let node = clang_ast::from_str::<Clang>(JSON).unwrap();
+    let mut source_map = SourceMap::new();
+    source_map.insert("main.cc", SOURCE);
+
+    // The end of the range is spelled inside the macro definition, which is
+    // before the beginning of the range.
+    let x = &node.inner[0].kind;
+    assert_eq!(source_map.spelling_text(&x.range), None);
+    assert_eq!(
+        source_map
+            .spelling_text(&SourceRange {
+                begin: x.range.end.clone(),
+                end: x.range.end.clone(),
+            })
+            .as_deref(),
+        Some("0"),
+    );
+}
+
+#[test]
+fn test_loader() {
+    let node = clang_ast::from_str::<Clang>(JSON).unwrap();
+    let loads = Rc::new(Cell::new(0));
+    let source_map = SourceMap::with_loader({
+        let loads = Rc::clone(&loads);
+        move |file| {
+            loads.set(loads.get() + 1);
+            match file {
+                "main.cc" => Some(SOURCE.to_owned()),
+                _ => None,
+            }
+        }
+    });
+
+    let x = source_map.text(&node.inner[0].kind.range).unwrap();
+    let y = source_map.text(&node.inner[1].kind.range).unwrap();
+    assert_eq!(x, "int x = ZERO");
+    assert_eq!(y, "int y = 1 + 2");
+    assert_eq!(source_map.text(&node.inner[2].kind.range), None);
+    assert_eq!(source_map.text(&node.inner[2].kind.range), None);
+    assert_eq!(loads.get(), 2);
+    assert_eq!(source_map.file("main.cc").as_deref(), Some(SOURCE));
+}
+
+#[test]
+fn test_reentrant_loader() {
+    // A loader which looks up another file in the same source map.
+    let source_map = Rc::new_cyclic(|source_map: &Weak<SourceMap>| {
+        let source_map = Weak::clone(source_map);
+        SourceMap::with_loader(move |file| match file {
+            "main.cc" => Some(SOURCE.to_owned()),
+            "copy.cc" => Some(source_map.upgrade()?.file("main.cc")?.to_string()),
+            _ => None,
+        })
+    });
+    assert_eq!(source_map.file("copy.cc").as_deref(), Some(SOURCE));
+    assert_eq!(source_map.file("main.cc").as_deref(), Some(SOURCE));
+}
@@ -0,0 +1,70 @@
+use serde_derive::Deserialize;
+use serde_json::{Map, Value};
+
+pub type Node = clang_ast::Node<Clang>;
+
+#[derive(Deserialize, PartialEq, Debug)]
+pub struct Clang {
+    #[serde(default)]
+    pub kind: clang_ast::Kind,
+    #[serde(default)]
+    pub loc: clang_ast::SourceLocation,
+    #[serde(default)]
+    pub range: clang_ast::SourceRange,
+    #[serde(flatten)]
+    pub data: Map<String, Value>,
+}
+
+#[test]
+fn test_top_level() {
+    let json = clang_ast_test_suite::cxx_ast_json();
+    let expected = clang_ast::from_slice::<Clang>(&json).unwrap();
+
+    let top_level = clang_ast::top_level::<Clang>(&json).unwrap();
+    assert_eq!(top_level.root().id, expected.id);
+    assert_eq!(top_level.root().kind, expected.kind);
+    assert!(top_level.root().inner.is_empty());
+
+    let actual = top_level.collect::<Result<Vec<Node>, _>>().unwrap();
+    assert_eq!(actual, expected.inner);
+}
+
+#[test]
+fn test_interleaved() {
+    let json = clang_ast_test_suite::cxx_ast_json();
+    let expected = clang_ast::from_slice::<Clang>(&json).unwrap();
+
+    // Two streams over the same dump, one item apart, advanced alternately
+    // with a whole-tree deserialization in between. Each must see only its
+    // own "same as previous" location state.
+    let mut a = clang_ast::top_level::<Clang>(&json).unwrap();
+    let mut b = clang_ast::top_level::<Clang>(&json).unwrap();
+    b.next();
+    for (i, node) in expected.inner.iter().enumerate() {
+        assert_eq!(a.next().unwrap().unwrap(), *node);
+        clang_ast::from_slice::<Clang>(&json).unwrap();
+        if let Some(node) = expected.inner.get(i + 1) {
+            assert_eq!(b.next().unwrap().unwrap(), *node);
+        }
+    }
+    assert!(a.next().is_none());
+    assert!(b.next().is_none());
+}
+
+#[test]
+fn test_errors() {
+    let mut top_level = clang_ast::top_level::<Clang>(
+        br#"{"kind": "TranslationUnitDecl", "inner": [{"kind": "EmptyDecl"}, {]}"#,
+    )
+    .unwrap();
+    assert!(top_level.next().unwrap().is_ok());
+    let error = top_level.next().unwrap().unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "key must be a string at line 1 column 67"
+    );
+    assert!(top_level.next().is_none());
+
+    let top_level = clang_ast::top_level::<Clang>(br#"{"kind": "TranslationUnitDecl"}"#).unwrap();
+    assert_eq!(top_level.count(), 0);
+}
@@ -0,0 +1,167 @@
+use clang_ast::{
+    Category, HasKind, HasRange, Kind, SourceLocation, SourceRange, TextAttributes, TextDump,
+    TextWriter,
+};
+use serde_derive::Deserialize;
+use serde_json::{Map, Value};
+use std::fmt;
+
+pub type Node = clang_ast::Node<Clang>;
+
+#[derive(Deserialize)]
+pub struct Clang {
+    #[serde(default)]
+    pub kind: Kind,
+    #[serde(default)]
+    pub loc: SourceLocation,
+    #[serde(default)]
+    pub range: SourceRange,
+    #[serde(flatten)]
+    pub data: Map<String, Value>,
+}
+
+impl HasKind for Clang {
+    fn kind(&self) -> Kind {
+        self.kind
+    }
+}
+
+impl HasRange for Clang {
+    fn range(&self) -> Option<&SourceRange> {
+        Some(&self.range)
+    }
+}
+
+impl TextAttributes for Clang {
+    fn loc(&self) -> Option<&SourceLocation> {
+        if self.kind.category() == Category::Decl {
+            Some(&self.loc)
+        } else {
+            None
+        }
+    }
+
+    fn write_attributes(&self, out: &mut TextWriter) -> fmt::Result {
+        if self.data.get("isImplicit") == Some(&Value::Bool(true)) {
+            out.plain("implicit")?;
+        }
+        if let Some(Value::String(name)) = self.data.get("name") {
+            out.name(name)?;
+        }
+        if let Some(Value::String(qual_type)) = self.data.get("type").map(|ty| &ty["qualType"]) {
+            let desugared = self.data["type"]["desugaredQualType"].as_str();
+            out.qual_type(qual_type, desugared)?;
+        }
+        if let Some(Value::String(value_category)) = self.data.get("valueCategory") {
+            out.value_category(value_category)?;
+        }
+        if let Some(Value::String(value)) = self.data.get("value") {
+            out.value(value)?;
+        }
+        Ok(())
+    }
+}
+
+const JSON: &str = r#"
+{
+  "id": "0x1",
+  "kind": "TranslationUnitDecl",
+  "loc": {},
+  "range": {"begin": {}, "end": {}},
+  "inner": [
+    {
+      "id": "0x2",
+      "kind": "TypedefDecl",
+      "loc": {},
+      "range": {"begin": {}, "end": {}},
+      "isImplicit": true,
+      "name": "__int128_t",
+      "type": {"qualType": "__int128"},
+      "inner": [
+        {"id": "0x3", "kind": "BuiltinType", "type": {"qualType": "__int128"}}
+      ]
+    },
+    {
+      "id": "0x4",
+      "kind": "FunctionDecl",
+      "loc": {"offset": 4, "file": "main.c", "line": 1, "col": 5, "tokLen": 4},
+      "range": {
+        "begin": {"offset": 0, "col": 1, "tokLen": 3},
+        "end": {"offset": 30, "line": 3, "col": 1, "tokLen": 1}
+      },
+      "name": "main",
+      "type": {"qualType": "int ()"},
+      "inner": [
+        {
+          "id": "0x5",
+          "kind": "CompoundStmt",
+          "range": {
+            "begin": {"offset": 11, "line": 1, "col": 12, "tokLen": 1},
+            "end": {"offset": 30, "line": 3, "col": 1, "tokLen": 1}
+          },
+          "inner": [
+            {
+              "id": "0x6",
+              "kind": "ReturnStmt",
+              "range": {
+                "begin": {"offset": 15, "line": 2, "col": 3, "tokLen": 6},
+                "end": {"offset": 22, "col": 10, "tokLen": 1}
+              },
+              "inner": [
+                {
+                  "id": "0x7",
+                  "kind": "IntegerLiteral",
+                  "range": {
+                    "begin": {"offset": 22, "col": 10, "tokLen": 1},
+                    "end": {"offset": 22, "col": 10, "tokLen": 1}
+                  },
+                  "type": {"qualType": "int"},
+                  "valueCategory": "prvalue",
+                  "value": "0"
+                }
+              ]
+            }
+          ]
+        }
+      ]
+    }
+  ]
+}
+"#;
+
+#[test]
+fn test_dump() {
+    let node = clang_ast::from_str::<Clang>(JSON).unwrap();
+    let expected = "\
+TranslationUnitDecl 0x1 <<invalid sloc>> <invalid sloc>
+|-TypedefDecl 0x2 <<invalid sloc>> <invalid sloc> implicit __int128_t '__int128'
+| `-BuiltinType 0x3 '__int128'
+`-FunctionDecl 0x4 <main.c:1:1, line:3:1> line:1:5 main 'int ()'
+  `-CompoundStmt 0x5 <col:12, line:3:1>
+    `-ReturnStmt 0x6 <line:2:3, col:10>
+      `-IntegerLiteral 0x7 <col:10> 'int' 0
+";
+    assert_eq!(TextDump::new(&node).to_string(), expected);
+}
+
+#[test]
+fn test_color() {
+    let node = clang_ast::from_str::<Clang>(JSON).unwrap();
+    let dump = TextDump::new(&node).color(true).to_string();
+    let line = dump.lines().nth(3).unwrap();
+    let expected = "\x1b[0;34m`-\x1b[0m\x1b[0;1;32mFunctionDecl\x1b[0m \x1b[0;33m0x4\x1b[0m <\x1b[0;33mmain.c:1:1\x1b[0m, \x1b[0;33mline:3:1\x1b[0m> \x1b[0;33mline:1:5\x1b[0m \x1b[0;1;36mmain\x1b[0m \x1b[0;32m'int ()'\x1b[0m";
+    assert_eq!(line, expected);
+}
+
+#[test]
+fn test_null_child() {
+    let json =
+        r#"{"id": "0x1", "kind": "ForStmt", "range": {"begin": {}, "end": {}}, "inner": [{}, {}]}"#;
+    let node = clang_ast::from_str::<Clang>(json).unwrap();
+    let expected = "\
+ForStmt 0x1 <<invalid sloc>>
+|-<<<NULL>>>
+`-<<<NULL>>>
+";
+    assert_eq!(TextDump::new(&node).to_string(), expected);
+}
@@ -0,0 +1,141 @@
+use clang_ast::{Flow, Visit, VisitMut};
+use serde_derive::Deserialize;
+
+pub type Node = clang_ast::Node<Clang>;
+
+#[derive(Deserialize)]
+pub enum Clang {
+    NamespaceDecl { name: Option<String> },
+    Unknown,
+}
+
+fn count(node: &Node) -> usize {
+    1 + node.inner.iter().map(count).sum::<usize>()
+}
+
+#[derive(Default)]
+struct Counter {
+    enter: usize,
+    leave: usize,
+    max_depth: usize,
+    stop_after: Option<usize>,
+    skip_below: Option<usize>,
+}
+
+impl<'ast> Visit<'ast, Clang> for Counter {
+    fn enter(&mut self, _node: &'ast Node, depth: usize) -> Flow {
+        self.enter += 1;
+        self.max_depth = self.max_depth.max(depth);
+        if self.stop_after == Some(self.enter) {
+            Flow::Break
+        } else if self.skip_below == Some(depth) {
+            Flow::Skip
+        } else {
+            Flow::Continue
+        }
+    }
+
+    fn leave(&mut self, _node: &'ast Node, _depth: usize) -> Flow {
+        self.leave += 1;
+        Flow::Continue
+    }
+}
+
+#[test]
+fn test_visit() {
+    let json = clang_ast_test_suite::cxx_ast_json();
+    let node: Node = serde_json::from_slice(&json).unwrap();
+
+    let mut counter = Counter::default();
+    assert_eq!(node.visit(&mut counter), Flow::Continue);
+    assert_eq!(counter.enter, count(&node));
+    assert_eq!(counter.leave, counter.enter);
+
+    let mut counter = Counter {
+        skip_below: Some(1),
+        ..Counter::default()
+    };
+    assert_eq!(node.visit(&mut counter), Flow::Continue);
+    assert_eq!(counter.enter, 1 + node.inner.len());
+    assert_eq!(counter.max_depth, usize::from(!node.inner.is_empty()));
+
+    let mut counter = Counter {
+        stop_after: Some(2),
+        ..Counter::default()
+    };
+    assert_eq!(node.visit(&mut counter), Flow::Break);
+    assert_eq!(counter.enter, 2);
+    assert_eq!(counter.leave, 0);
+}
+
+struct Prune {
+    depth: usize,
+}
+
+impl VisitMut<Clang> for Prune {
+    fn enter(&mut self, node: &mut Node, depth: usize) -> Flow {
+        if depth == self.depth {
+            node.inner.clear();
+        }
+        Flow::Continue
+    }
+
+    fn leave(&mut self, node: &mut Node, _depth: usize) -> Flow {
+        if let Clang::NamespaceDecl { name } = &mut node.kind {
+            *name = Some("renamed".to_owned());
+        }
+        Flow::Continue
+    }
+}
+
+struct Names;
+
+impl<'ast> Visit<'ast, Clang> for Names {
+    fn enter(&mut self, node: &'ast Node, _depth: usize) -> Flow {
+        if let Clang::NamespaceDecl { name } = &node.kind {
+            assert_eq!(name.as_deref(), Some("renamed"));
+        }
+        Flow::Continue
+    }
+}
+
+#[test]
+fn test_visit_mut() {
+    let json = clang_ast_test_suite::cxx_ast_json();
+    let mut node: Node = serde_json::from_slice(&json).unwrap();
+
+    node.visit_mut(&mut Prune { depth: 2 });
+
+    let mut counter = Counter::default();
+    node.visit(&mut counter);
+    assert!(counter.max_depth <= 2);
+
+    node.visit(&mut Names);
+}
+
+struct Abort {
+    after: usize,
+}
+
+impl VisitMut<Clang> for Abort {
+    fn enter(&mut self, _node: &mut Node, _depth: usize) -> Flow {
+        if self.after == 0 {
+            return Flow::Break;
+        }
+        self.after -= 1;
+        Flow::Continue
+    }
+}
+
+#[test]
+fn test_visit_mut_break() {
+    let json = clang_ast_test_suite::cxx_ast_json();
+    let mut node: Node = serde_json::from_slice(&json).unwrap();
+    let total = count(&node);
+
+    // Aborting deep inside the tree leaves every node in place.
+    for after in [1, total / 2, total - 1] {
+        assert_eq!(node.visit_mut(&mut Abort { after }), Flow::Break);
+        assert_eq!(count(&node), total);
+    }
+}